@@ -0,0 +1,82 @@
+//! Solve times on 1001x1001 mazes through the maze itself versus through one
+//! `CsrGraph` built per maze and shared by all solvers. Run with
+//! `cargo bench --bench graph_view`.
+use maze_rust::csr_graph::CsrGraph;
+use maze_rust::generators::generator::MazeGenerator;
+use maze_rust::generators::kruskal::KruskalMazeGenerator;
+use maze_rust::maze_graph::MazeGraph;
+use maze_rust::random;
+use maze_rust::solvers::astar::AstarSolver;
+use maze_rust::solvers::bfs_solver::BfsSolver;
+use maze_rust::solvers::dfs_solver::DfsSolver;
+use maze_rust::solvers::dijkstra::DijkstraSolver;
+use maze_rust::solvers::solver::Solver;
+use std::time::{Duration, Instant};
+
+const SIZE: usize = 1001;
+const RUNS: u32 = 5;
+
+fn average(mut run: impl FnMut()) -> Duration {
+    let started = Instant::now();
+    for _ in 0..RUNS {
+        run();
+    }
+    started.elapsed() / RUNS
+}
+
+fn main() {
+    let solvers: [(&str, &dyn Solver); 4] = [
+        ("astar", &AstarSolver),
+        ("bfs", &BfsSolver),
+        ("dfs", &DfsSolver),
+        ("dijkstra", &DijkstraSolver),
+    ];
+    for imperfect_percentage in [0.0, 10.0] {
+        random::seed(1);
+        let maze = KruskalMazeGenerator
+            .generate(SIZE, SIZE, None, None, imperfect_percentage, None)
+            .unwrap();
+        let build = average(|| {
+            CsrGraph::from_maze(&maze);
+        });
+        let graph = CsrGraph::from_maze(&maze);
+        println!(
+            "\n{}x{} maze, {}% walls removed: graph of {} nodes built in {:.2?}",
+            SIZE,
+            SIZE,
+            imperfect_percentage,
+            graph.node_count(),
+            build
+        );
+        println!(
+            "{:<10} {:>12} {:>12} {:>8}",
+            "solver", "maze", "shared", "speedup"
+        );
+
+        let (mut direct_total, mut shared_total) = (Duration::ZERO, build);
+        for (name, solver) in solvers {
+            let direct = average(|| {
+                solver.solve_with_result(&maze, None);
+            });
+            let shared = average(|| {
+                solver.search(&graph, None);
+            });
+            direct_total += direct;
+            shared_total += shared;
+            println!(
+                "{:<10} {:>12.2?} {:>12.2?} {:>7.1}x",
+                name,
+                direct,
+                shared,
+                direct.as_secs_f64() / shared.as_secs_f64()
+            );
+        }
+        println!(
+            "{:<10} {:>12.2?} {:>12.2?} {:>7.1}x",
+            "all + build",
+            direct_total,
+            shared_total,
+            direct_total.as_secs_f64() / shared_total.as_secs_f64()
+        );
+    }
+}
@@ -0,0 +1,900 @@
+//! Animation encoders fed by the same full-color frames: GIF, APNG and animated
+//! WebP, or for video encoders a PNG frame sequence or a raw Y4M stream, chosen
+//! by the file extension. GIF frames are reduced to a palette, exactly when a
+//! frame has at most 256 colors; the other formats keep every color losslessly.
+//! Except for video, every frame after the first only stores the rectangle that
+//! changed, GIF frames leaving unchanged pixels in it transparent.
+use gif::{DisposalMethod, Repeat};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+
+/// Container written by an `AnimationWriter`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnimationFormat {
+    Gif,
+    Apng,
+    Webp,
+    /// Directory of numbered PNG files, one per video frame
+    FrameSequence,
+    /// Uncompressed YUV 4:4:4 video stream
+    Y4m,
+}
+
+impl AnimationFormat {
+    /// Format named on the command line: "gif", "apng", "webp", "frames" or "y4m".
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "gif" => Some(AnimationFormat::Gif),
+            "apng" => Some(AnimationFormat::Apng),
+            "webp" => Some(AnimationFormat::Webp),
+            "frames" => Some(AnimationFormat::FrameSequence),
+            "y4m" => Some(AnimationFormat::Y4m),
+            _ => None,
+        }
+    }
+
+    /// Format of a file by its extension, `.png` and `.apng` being APNG and
+    /// `.frames` a directory for a frame sequence.
+    pub fn from_path(path: &str) -> Option<Self> {
+        let extension = path.rsplit_once('.')?.1.to_lowercase();
+        match extension.as_str() {
+            "gif" => Some(AnimationFormat::Gif),
+            "png" | "apng" => Some(AnimationFormat::Apng),
+            "webp" => Some(AnimationFormat::Webp),
+            "frames" => Some(AnimationFormat::FrameSequence),
+            "y4m" => Some(AnimationFormat::Y4m),
+            _ => None,
+        }
+    }
+
+    /// File extension used for output of this format; APNG files get `.apng`
+    /// so they do not clash with still PNG images of the same maze.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            AnimationFormat::Gif => "gif",
+            AnimationFormat::Apng => "apng",
+            AnimationFormat::Webp => "webp",
+            AnimationFormat::FrameSequence => "frames",
+            AnimationFormat::Y4m => "y4m",
+        }
+    }
+}
+
+/// How the steps of an animation are spread over its frames: early frames cover
+/// fewer steps than late ones unless the easing is linear.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Easing {
+    Linear,
+    Quadratic,
+    Cubic,
+}
+
+impl Easing {
+    /// Easing named on the command line: "linear", "quadratic" or "cubic".
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "linear" => Some(Easing::Linear),
+            "quadratic" => Some(Easing::Quadratic),
+            "cubic" => Some(Easing::Cubic),
+            _ => None,
+        }
+    }
+
+    // share of the steps shown after a share `t` of the frames
+    fn apply(self, t: f64) -> f64 {
+        match self {
+            Easing::Linear => t,
+            Easing::Quadratic => t * t,
+            Easing::Cubic => t * t * t,
+        }
+    }
+}
+
+/// Pacing shared by generator and solver animations, which record one step per
+/// changed cell and would otherwise need one frame per step.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AnimationPolicy {
+    pub timing: FrameTiming,
+    /// Frames the steps are spread over at most
+    pub max_frames: usize,
+    /// Fixed number of steps per frame, overriding `max_frames`
+    pub steps_per_frame: Option<usize>,
+    /// Total time of the stepped frames in hundredths of a second, split
+    /// evenly between them instead of using the timing's delay
+    pub duration: Option<u32>,
+    pub easing: Easing,
+}
+
+impl AnimationPolicy {
+    pub fn new(timing: FrameTiming) -> Self {
+        AnimationPolicy {
+            timing,
+            max_frames: 500,
+            steps_per_frame: None,
+            duration: None,
+            easing: Easing::Linear,
+        }
+    }
+
+    /// Decides after which of `steps` steps a frame is shown and for how long.
+    pub fn pacer(&self, steps: usize) -> FramePacer {
+        let mut frames = match self.steps_per_frame {
+            Some(per_frame) => steps.div_ceil(per_frame.max(1)),
+            None => steps.min(self.max_frames.max(1)),
+        };
+        if let Some(duration) = self.duration {
+            frames = frames.min(duration.max(1) as usize);
+        }
+
+        // frame k ends after the eased share of the steps, each frame taking at
+        // least one step and leaving at least one for every later frame; a fixed
+        // number of steps per frame is kept exactly when linear
+        let span = self
+            .steps_per_frame
+            .map_or(steps, |per_frame| (frames * per_frame.max(1)).max(steps));
+        let mut schedule = Vec::with_capacity(frames);
+        let mut end = 0;
+        for k in 0..frames {
+            let share = self.easing.apply((k + 1) as f64 / frames as f64);
+            let eased = (share * span as f64).ceil() as usize;
+            end = eased.max(end + 1).min(steps - (frames - 1 - k));
+            let delay = match self.duration {
+                Some(duration) => {
+                    let elapsed = |k: usize| duration as u64 * k as u64 / frames as u64;
+                    (elapsed(k + 1) - elapsed(k)).min(u16::MAX as u64) as u16
+                }
+                None => self.timing.delay,
+            };
+            schedule.push((end, delay));
+        }
+        FramePacer {
+            schedule: schedule.into_iter().peekable(),
+            done: 0,
+        }
+    }
+}
+
+/// Tells an animation, step by step, when to show a frame.
+pub struct FramePacer {
+    schedule: std::iter::Peekable<std::vec::IntoIter<(usize, u16)>>,
+    done: usize,
+}
+
+impl FramePacer {
+    /// Records one more step, returning the frame delay if a frame showing
+    /// every step so far is due.
+    pub fn step(&mut self) -> Option<u16> {
+        self.done += 1;
+        self.schedule
+            .next_if(|&(end, _)| end == self.done)
+            .map(|(_, delay)| delay)
+    }
+}
+
+/// How long the frames of an animation are shown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameTiming {
+    /// Delay after each frame, in hundredths of a second
+    pub delay: u16,
+    /// Extra time the last frame stays up, in hundredths of a second
+    pub hold_last: u16,
+    /// Frames per second of frame sequences and Y4M streams, which show each
+    /// animation frame for as many video frames as its delay covers
+    pub frame_rate: u32,
+}
+
+impl FrameTiming {
+    pub fn new(delay: u16) -> Self {
+        FrameTiming {
+            delay,
+            hold_last: 0,
+            frame_rate: 30,
+        }
+    }
+}
+
+/// Compressed rectangle of a frame that differs from the frame before.
+struct Patch {
+    delay: u16,
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+    data: Vec<u8>,
+}
+
+enum Frames {
+    // `transparent` is the palette index reserved for pixels kept from the
+    // frame before, if the shared palette leaves room for one
+    Gif {
+        encoder: gif::Encoder<BufWriter<File>>,
+        transparent: Option<u8>,
+    },
+    // APNG and WebP headers need the frame count or all frames first, so
+    // frames are compressed as they come and the container written on finish
+    Apng {
+        header: Vec<u8>,
+        patches: Vec<Patch>,
+    },
+    Webp {
+        patches: Vec<Patch>,
+    },
+    // video frames come at a fixed rate; `elapsed` is the animation time shown
+    // so far in hundredths of a second and `written` the video frames covering it
+    Video {
+        output: VideoOutput,
+        elapsed: u64,
+        written: u64,
+    },
+}
+
+enum VideoOutput {
+    FrameSequence(PathBuf),
+    Y4m(BufWriter<File>),
+}
+
+/// Writes an animation of RGB frames that all have the same size, played once.
+/// Frame delays are in hundredths of a second, as in GIF.
+pub struct AnimationWriter {
+    file: Option<BufWriter<File>>,
+    width: u32,
+    height: u32,
+    palette: Vec<u8>,
+    hold_last: u16,
+    frame_rate: u32,
+    // each frame waits here until the next one shows whether it is the last
+    pending: Option<(Vec<u8>, u16)>,
+    previous: Option<Vec<u8>>,
+    frames: Frames,
+}
+
+impl AnimationWriter {
+    /// Starts an animation in the format given by the extension of `file_path`.
+    /// `palette` lists the RGB colors GIF frames use most, which then need no
+    /// palette of their own. Only the final hold and frame rate of `timing`
+    /// apply here, each frame bringing its own delay.
+    pub fn create(
+        file_path: &str,
+        width: u32,
+        height: u32,
+        palette: &[u8],
+        timing: &FrameTiming,
+    ) -> Result<Self, Box<dyn Error>> {
+        let format = AnimationFormat::from_path(file_path)
+            .ok_or_else(|| format!("unknown animation format of '{}'", file_path))?;
+        if format == AnimationFormat::FrameSequence {
+            fs::create_dir_all(file_path)?;
+        }
+        let video = |output| Frames::Video {
+            output,
+            elapsed: 0,
+            written: 0,
+        };
+        let mut palette = palette.to_vec();
+        let transparent = (palette.len() / 3 < 256).then(|| {
+            palette.extend_from_slice(&[0, 0, 0]);
+            (palette.len() / 3 - 1) as u8
+        });
+        let (file, frames) = match format {
+            AnimationFormat::FrameSequence => {
+                (None, video(VideoOutput::FrameSequence(file_path.into())))
+            }
+            _ => {
+                let file = BufWriter::new(File::create(file_path)?);
+                match format {
+                    AnimationFormat::Gif => {
+                        let mut encoder =
+                            gif::Encoder::new(file, width as u16, height as u16, &palette)?;
+                        encoder.set_repeat(Repeat::Finite(0))?;
+                        (
+                            None,
+                            Frames::Gif {
+                                encoder,
+                                transparent,
+                            },
+                        )
+                    }
+                    AnimationFormat::Apng => (
+                        Some(file),
+                        Frames::Apng {
+                            header: Vec::new(),
+                            patches: Vec::new(),
+                        },
+                    ),
+                    AnimationFormat::Webp => (
+                        Some(file),
+                        Frames::Webp {
+                            patches: Vec::new(),
+                        },
+                    ),
+                    _ => {
+                        let mut file = file;
+                        // progressive 4:4:4 frames with square pixels
+                        writeln!(
+                            file,
+                            "YUV4MPEG2 W{} H{} F{}:1 Ip A1:1 C444",
+                            width,
+                            height,
+                            timing.frame_rate.max(1)
+                        )?;
+                        (None, video(VideoOutput::Y4m(file)))
+                    }
+                }
+            }
+        };
+        Ok(AnimationWriter {
+            file,
+            width,
+            height,
+            palette,
+            hold_last: timing.hold_last,
+            frame_rate: timing.frame_rate.max(1),
+            pending: None,
+            previous: None,
+            frames,
+        })
+    }
+
+    pub fn write_frame(&mut self, rgb: &[u8], delay: u16) -> Result<(), Box<dyn Error>> {
+        if let Some((frame, delay)) = self.pending.replace((rgb.to_vec(), delay)) {
+            self.emit(&frame, delay, false)?;
+        }
+        Ok(())
+    }
+
+    fn emit(&mut self, rgb: &[u8], delay: u16, last: bool) -> Result<(), Box<dyn Error>> {
+        let (width, height) = (self.width, self.height);
+        if let Frames::Video {
+            output,
+            elapsed,
+            written,
+        } = &mut self.frames
+        {
+            // every video frame whose time falls within this frame shows it,
+            // and the last frame is shown at least once
+            *elapsed += delay as u64;
+            let covered = (*elapsed * self.frame_rate as u64).div_ceil(100);
+            let count = covered.saturating_sub(*written).max(last as u64);
+            if count == 0 {
+                return Ok(());
+            }
+            match output {
+                VideoOutput::FrameSequence(directory) => {
+                    let png = encode_png(rgb, width, height)?;
+                    for index in *written..*written + count {
+                        fs::write(directory.join(format!("frame_{:06}.png", index)), &png)?;
+                    }
+                }
+                VideoOutput::Y4m(file) => {
+                    let yuv = rgb_to_yuv444(rgb);
+                    for _ in 0..count {
+                        file.write_all(b"FRAME\n")?;
+                        file.write_all(&yuv)?;
+                    }
+                }
+            }
+            *written += count;
+            return Ok(());
+        }
+
+        // only the rectangle that changed is stored; WebP offsets must be even
+        let even = matches!(self.frames, Frames::Webp { .. });
+        let region = match &self.previous {
+            None => Some((0, 0, width, height)),
+            Some(previous) => changed_region(previous, rgb, width, height, even),
+        };
+        if let Frames::Gif {
+            encoder,
+            transparent,
+        } = &mut self.frames
+        {
+            // GIF frames are written as they come, so an unchanged frame is one
+            // pixel left as it was
+            let region = region.unwrap_or((0, 0, 1, 1));
+            let cropped = crop(rgb, width, region);
+            let kept: Vec<bool> = match &self.previous {
+                Some(previous) => crop(previous, width, region)
+                    .chunks(3)
+                    .zip(cropped.chunks(3))
+                    .map(|(old, new)| old == new)
+                    .collect(),
+                None => Vec::new(),
+            };
+            let (x, y, patch_width, patch_height) = region;
+            let mut frame = gif_frame(
+                &cropped,
+                patch_width as u16,
+                patch_height as u16,
+                &self.palette,
+                transparent.map(|index| (index, kept.as_slice())),
+            );
+            frame.left = x as u16;
+            frame.top = y as u16;
+            frame.delay = delay;
+            frame.dispose = DisposalMethod::Keep;
+            encoder.write_frame(&frame)?;
+            self.previous = Some(rgb.to_vec());
+            return Ok(());
+        }
+        let Some(region) = region else {
+            // an unchanged frame only lengthens the one before
+            if let Frames::Apng { patches, .. } | Frames::Webp { patches } = &mut self.frames {
+                if let Some(last) = patches.last_mut() {
+                    last.delay = last.delay.saturating_add(delay);
+                }
+            }
+            return Ok(());
+        };
+        let cropped = crop(rgb, width, region);
+        let (x, y, patch_width, patch_height) = region;
+
+        let (data, patches) = match &mut self.frames {
+            Frames::Apng { header, patches } => {
+                let png = encode_png(&cropped, patch_width, patch_height)?;
+                let mut data = Vec::new();
+                for (kind, chunk) in chunks(&png, false) {
+                    match &kind {
+                        b"IHDR" if header.is_empty() => *header = chunk.to_vec(),
+                        b"IDAT" => data.extend_from_slice(chunk),
+                        _ => {}
+                    }
+                }
+                (data, patches)
+            }
+            Frames::Webp { patches } => {
+                let mut webp = Vec::new();
+                image_webp::WebPEncoder::new(&mut webp).encode(
+                    &cropped,
+                    patch_width,
+                    patch_height,
+                    image_webp::ColorType::Rgb8,
+                )?;
+                // the still image is a RIFF container holding one VP8L chunk
+                let (_, vp8l) = chunks(&webp, true)
+                    .find(|(kind, _)| kind == b"VP8L")
+                    .ok_or("WebP encoder wrote no VP8L chunk")?;
+                (vp8l.to_vec(), patches)
+            }
+            Frames::Gif { .. } | Frames::Video { .. } => {
+                unreachable!("GIF and video frames are written directly")
+            }
+        };
+        patches.push(Patch {
+            delay,
+            x,
+            y,
+            width: patch_width,
+            height: patch_height,
+            data,
+        });
+        self.previous = Some(rgb.to_vec());
+        Ok(())
+    }
+
+    /// Writes what is still buffered; the animation is incomplete without it.
+    pub fn finish(mut self) -> Result<(), Box<dyn Error>> {
+        if let Some((frame, delay)) = self.pending.take() {
+            self.emit(&frame, delay.saturating_add(self.hold_last), true)?;
+        }
+        match self.frames {
+            Frames::Gif { encoder, .. } => {
+                encoder.into_inner()?.flush()?;
+            }
+            Frames::Apng { header, patches } => {
+                let mut file = self.file.expect("APNG output file");
+                write_apng(&mut file, &header, &patches)?;
+                file.flush()?;
+            }
+            Frames::Webp { patches } => {
+                let mut file = self.file.expect("WebP output file");
+                write_webp(&mut file, self.width, self.height, &patches)?;
+                file.flush()?;
+            }
+            Frames::Video {
+                output: VideoOutput::Y4m(mut file),
+                ..
+            } => file.flush()?,
+            Frames::Video { .. } => {}
+        }
+        Ok(())
+    }
+}
+
+// bounding rectangle (x, y, width, height) of the pixels that differ, if any
+fn changed_region(
+    previous: &[u8],
+    current: &[u8],
+    width: u32,
+    height: u32,
+    even: bool,
+) -> Option<(u32, u32, u32, u32)> {
+    let row_len = width as usize * 3;
+    let (mut left, mut top, mut right, mut bottom) = (width, height, 0, 0);
+    for (y, (old, new)) in previous
+        .chunks(row_len)
+        .zip(current.chunks(row_len))
+        .enumerate()
+    {
+        if old == new {
+            continue;
+        }
+        let differs = |x: &usize| old[x * 3..x * 3 + 3] != new[x * 3..x * 3 + 3];
+        let first = (0..width as usize).find(differs)? as u32;
+        let last = (0..width as usize).rev().find(differs)? as u32;
+        left = left.min(first);
+        right = right.max(last + 1);
+        top = top.min(y as u32);
+        bottom = y as u32 + 1;
+    }
+    if right == 0 {
+        return None;
+    }
+    if even {
+        left -= left % 2;
+        top -= top % 2;
+    }
+    Some((left, top, right - left, bottom - top))
+}
+
+fn crop(rgb: &[u8], width: u32, (x, y, crop_width, crop_height): (u32, u32, u32, u32)) -> Vec<u8> {
+    let row =
+        |y: u32| &rgb[((y * width + x) * 3) as usize..((y * width + x + crop_width) * 3) as usize];
+    (y..y + crop_height).flat_map(row).copied().collect()
+}
+
+// indexes a frame with the shared palette, extended by a local palette for
+// colors outside it, or quantized beyond 256 colors; pixels marked as kept
+// get the transparent index
+fn gif_frame(
+    rgb: &[u8],
+    width: u16,
+    height: u16,
+    palette: &[u8],
+    transparent: Option<(u8, &[bool])>,
+) -> gif::Frame<'static> {
+    let reserved = transparent.map(|(index, _)| index);
+    let mut indices: HashMap<[u8; 3], u8> = palette
+        .chunks(3)
+        .enumerate()
+        .filter(|&(i, _)| Some(i as u8) != reserved)
+        .map(|(i, color)| ([color[0], color[1], color[2]], i as u8))
+        .collect();
+    let kept = |i: usize| transparent.is_some_and(|(_, kept)| kept.get(i) == Some(&true));
+    let mut local = palette.to_vec();
+    let mut pixels = Vec::with_capacity(rgb.len() / 3);
+    let mut last = None;
+    for (i, color) in rgb.chunks(3).enumerate() {
+        if kept(i) {
+            pixels.push(reserved.unwrap());
+            continue;
+        }
+        let color = [color[0], color[1], color[2]];
+        let index = match last {
+            Some((last_color, index)) if last_color == color => index,
+            _ => match indices.get(&color) {
+                Some(&index) => index,
+                None if local.len() / 3 < 256 => {
+                    let index = (local.len() / 3) as u8;
+                    local.extend_from_slice(&color);
+                    indices.insert(color, index);
+                    index
+                }
+                None => {
+                    let mut rgba: Vec<u8> = rgb
+                        .chunks(3)
+                        .enumerate()
+                        .flat_map(|(i, color)| {
+                            let alpha = if kept(i) { 0 } else { 255 };
+                            [color[0], color[1], color[2], alpha]
+                        })
+                        .collect();
+                    return gif::Frame::from_rgba_speed(width, height, &mut rgba, 10);
+                }
+            },
+        };
+        last = Some((color, index));
+        pixels.push(index);
+    }
+    let mut frame = gif::Frame::from_indexed_pixels(width, height, pixels, reserved);
+    if local.len() > palette.len() {
+        frame.palette = Some(local);
+    }
+    frame
+}
+
+// planes of Y, Cb and Cr in BT.601 studio range, as Y4M players expect
+fn rgb_to_yuv444(rgb: &[u8]) -> Vec<u8> {
+    let pixels = rgb.len() / 3;
+    let mut yuv = vec![0; pixels * 3];
+    for (i, pixel) in rgb.chunks(3).enumerate() {
+        let (r, g, b) = (pixel[0] as f32, pixel[1] as f32, pixel[2] as f32);
+        yuv[i] = (16.0 + (65.481 * r + 128.553 * g + 24.966 * b) / 255.0).round() as u8;
+        yuv[pixels + i] = (128.0 + (-37.797 * r - 74.203 * g + 112.0 * b) / 255.0).round() as u8;
+        yuv[2 * pixels + i] = (128.0 + (112.0 * r - 93.786 * g - 18.214 * b) / 255.0).round() as u8;
+    }
+    yuv
+}
+
+fn encode_png(rgb: &[u8], width: u32, height: u32) -> Result<Vec<u8>, png::EncodingError> {
+    let mut png = Vec::new();
+    let mut encoder = png::Encoder::new(&mut png, width, height);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(rgb)?;
+    writer.finish()?;
+    Ok(png)
+}
+
+// (type, data) of the chunks of a PNG file, or of a RIFF file whose chunk
+// lengths are little-endian and padded to even sizes instead of followed by a CRC
+fn chunks(bytes: &[u8], riff: bool) -> impl Iterator<Item = ([u8; 4], &[u8])> + '_ {
+    let mut position = if riff { 12 } else { 8 };
+    std::iter::from_fn(move || {
+        let field = |at: usize| -> Option<[u8; 4]> { bytes.get(at..at + 4)?.try_into().ok() };
+        let (kind, len) = if riff {
+            (field(position)?, u32::from_le_bytes(field(position + 4)?))
+        } else {
+            (field(position + 4)?, u32::from_be_bytes(field(position)?))
+        };
+        let len = len as usize;
+        let data = bytes.get(position + 8..position + 8 + len)?;
+        position += 8 + len + if riff { len % 2 } else { 4 };
+        Some((kind, data))
+    })
+}
+
+fn png_chunk(out: &mut impl Write, kind: &[u8; 4], data: &[u8]) -> std::io::Result<()> {
+    let mut crc = crc32fast::Hasher::new();
+    crc.update(kind);
+    crc.update(data);
+    out.write_all(&(data.len() as u32).to_be_bytes())?;
+    out.write_all(kind)?;
+    out.write_all(data)?;
+    out.write_all(&crc.finalize().to_be_bytes())
+}
+
+fn write_apng(out: &mut impl Write, header: &[u8], patches: &[Patch]) -> std::io::Result<()> {
+    out.write_all(&[0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'])?;
+    png_chunk(out, b"IHDR", header)?;
+    let mut control = Vec::new();
+    control.extend_from_slice(&(patches.len() as u32).to_be_bytes());
+    control.extend_from_slice(&1u32.to_be_bytes());
+    png_chunk(out, b"acTL", &control)?;
+
+    // fcTL and fdAT chunks share one sequence; the first frame is the default
+    // image and later ones are drawn over it in place
+    let mut sequence = 0u32;
+    for (i, patch) in patches.iter().enumerate() {
+        let mut control = Vec::new();
+        for value in [sequence, patch.width, patch.height, patch.x, patch.y] {
+            control.extend_from_slice(&value.to_be_bytes());
+        }
+        control.extend_from_slice(&patch.delay.to_be_bytes());
+        control.extend_from_slice(&100u16.to_be_bytes());
+        control.extend_from_slice(&[0, 0]);
+        png_chunk(out, b"fcTL", &control)?;
+        sequence += 1;
+        if i == 0 {
+            png_chunk(out, b"IDAT", &patch.data)?;
+        } else {
+            let mut frame_data = sequence.to_be_bytes().to_vec();
+            frame_data.extend_from_slice(&patch.data);
+            png_chunk(out, b"fdAT", &frame_data)?;
+            sequence += 1;
+        }
+    }
+    png_chunk(out, b"IEND", &[])
+}
+
+fn riff_chunk(out: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    out.extend_from_slice(kind);
+    out.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    out.extend_from_slice(data);
+    if data.len() % 2 == 1 {
+        out.push(0);
+    }
+}
+
+fn write_webp(
+    out: &mut impl Write,
+    width: u32,
+    height: u32,
+    patches: &[Patch],
+) -> std::io::Result<()> {
+    let u24 = |value: u32| value.to_le_bytes()[..3].to_vec();
+    let mut body = b"WEBP".to_vec();
+    // animation flag, then the canvas size
+    let mut header = vec![0x02, 0, 0, 0];
+    header.extend(u24(width - 1));
+    header.extend(u24(height - 1));
+    riff_chunk(&mut body, b"VP8X", &header);
+    // background color and loop count
+    riff_chunk(&mut body, b"ANIM", &[0xff, 0xff, 0xff, 0xff, 1, 0]);
+    for patch in patches {
+        let mut frame = Vec::new();
+        frame.extend(u24(patch.x / 2));
+        frame.extend(u24(patch.y / 2));
+        frame.extend(u24(patch.width - 1));
+        frame.extend(u24(patch.height - 1));
+        frame.extend(u24(patch.delay as u32 * 10));
+        // do not blend: the patch replaces the pixels under it
+        frame.push(0x02);
+        riff_chunk(&mut frame, b"VP8L", &patch.data);
+        riff_chunk(&mut body, b"ANMF", &frame);
+    }
+    out.write_all(b"RIFF")?;
+    out.write_all(&(body.len() as u32).to_le_bytes())?;
+    out.write_all(&body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::codecs::gif::GifDecoder;
+    use image::codecs::png::PngDecoder;
+    use image::codecs::webp::WebPDecoder;
+    use image::AnimationDecoder;
+    use std::io::BufReader;
+
+    // frames of a 3 x 2 image: a plain one, a gradient of colors outside the
+    // palette, and the gradient with its last pixel changed
+    fn frames() -> Vec<Vec<u8>> {
+        let first = [10, 20, 30].repeat(6);
+        let second: Vec<u8> = (0..6).flat_map(|i| [i * 40, 255 - i * 40, 7]).collect();
+        let mut third = second.clone();
+        third[15..].copy_from_slice(&[1, 2, 3]);
+        vec![first, second, third]
+    }
+
+    fn decoded(path: &str) -> Vec<image::RgbaImage> {
+        let reader = BufReader::new(File::open(path).unwrap());
+        let frames = match AnimationFormat::from_path(path).unwrap() {
+            AnimationFormat::Gif => GifDecoder::new(reader).unwrap().into_frames(),
+            AnimationFormat::Apng => PngDecoder::new(reader)
+                .unwrap()
+                .apng()
+                .unwrap()
+                .into_frames(),
+            AnimationFormat::Webp => WebPDecoder::new(reader).unwrap().into_frames(),
+            _ => unreachable!("video frames are checked directly"),
+        };
+        frames.map(|frame| frame.unwrap().into_buffer()).collect()
+    }
+
+    #[test]
+    fn test_formats_round_trip_full_color() {
+        for extension in ["gif", "png", "webp"] {
+            let path = std::env::temp_dir()
+                .join(format!("animation_{}.{}", std::process::id(), extension))
+                .to_string_lossy()
+                .to_string();
+            let mut writer =
+                AnimationWriter::create(&path, 3, 2, &[10, 20, 30], &FrameTiming::new(5)).unwrap();
+            for frame in frames() {
+                writer.write_frame(&frame, 5).unwrap();
+            }
+            // repeated frames are merged into the one before, except in GIF
+            writer.write_frame(&frames()[2], 5).unwrap();
+            writer.finish().unwrap();
+
+            let decoded = decoded(&path);
+            let expected = if extension == "gif" { 4 } else { 3 };
+            assert_eq!(decoded.len(), expected, "{}", extension);
+            for (image, expected) in decoded.iter().zip(frames()) {
+                let rgb: Vec<u8> = image.pixels().flat_map(|p| [p[0], p[1], p[2]]).collect();
+                assert_eq!(rgb, expected, "{}", extension);
+            }
+            std::fs::remove_file(&path).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_gif_frames_only_store_changed_pixels() {
+        let path = std::env::temp_dir()
+            .join(format!("animation_dirty_{}.gif", std::process::id()))
+            .to_string_lossy()
+            .to_string();
+        let mut writer =
+            AnimationWriter::create(&path, 3, 2, &[10, 20, 30], &FrameTiming::new(5)).unwrap();
+        for frame in frames() {
+            writer.write_frame(&frame, 5).unwrap();
+        }
+        writer.write_frame(&frames()[2], 5).unwrap();
+        writer.finish().unwrap();
+
+        let mut options = gif::DecodeOptions::new();
+        options.set_color_output(gif::ColorOutput::Indexed);
+        let mut decoder = options.read_info(File::open(&path).unwrap()).unwrap();
+        let mut regions = Vec::new();
+        while let Some(frame) = decoder.read_next_frame().unwrap() {
+            let transparent = frame
+                .transparent
+                .filter(|index| frame.buffer.contains(index));
+            regions.push((
+                frame.left,
+                frame.top,
+                frame.width,
+                frame.height,
+                transparent.is_some(),
+            ));
+        }
+        // the third frame changes the last pixel only, the fourth nothing
+        assert_eq!(
+            regions,
+            [
+                (0, 0, 3, 2, false),
+                (0, 0, 3, 2, false),
+                (2, 1, 1, 1, false),
+                (0, 0, 1, 1, true)
+            ]
+        );
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_video_frames_follow_frame_rate_and_hold() {
+        // 5 + 5 + 5 hundredths plus a hold of 50 at 20 fps: frames cover video
+        // frames 0..1, 1..2 and 2..13
+        let timing = FrameTiming {
+            delay: 5,
+            hold_last: 50,
+            frame_rate: 20,
+        };
+        let base = std::env::temp_dir().join(format!("animation_video_{}", std::process::id()));
+        let y4m = base.with_extension("y4m").to_string_lossy().to_string();
+        let mut writer = AnimationWriter::create(&y4m, 3, 2, &[], &timing).unwrap();
+        for frame in frames() {
+            writer.write_frame(&frame, timing.delay).unwrap();
+        }
+        writer.finish().unwrap();
+        let bytes = std::fs::read(&y4m).unwrap();
+        let header = b"YUV4MPEG2 W3 H2 F20:1 Ip A1:1 C444\n";
+        assert!(bytes.starts_with(header));
+        assert_eq!(bytes.len(), header.len() + 13 * (6 + 3 * 6));
+        // white is the top of the studio luma range with neutral chroma
+        assert_eq!(rgb_to_yuv444(&[255, 255, 255]), [235, 128, 128]);
+        std::fs::remove_file(&y4m).unwrap();
+
+        let directory = base.with_extension("frames").to_string_lossy().to_string();
+        let mut writer = AnimationWriter::create(&directory, 3, 2, &[], &timing).unwrap();
+        for frame in frames() {
+            writer.write_frame(&frame, timing.delay).unwrap();
+        }
+        writer.finish().unwrap();
+        assert_eq!(std::fs::read_dir(&directory).unwrap().count(), 13);
+        let last = image::open(format!("{}/frame_000012.png", directory))
+            .unwrap()
+            .to_rgb8();
+        assert_eq!(last.into_raw(), frames()[2]);
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn test_pacer_spreads_steps_over_frames() {
+        let ends = |policy: AnimationPolicy, steps| {
+            let mut pacer = policy.pacer(steps);
+            (1..=steps)
+                .filter_map(|step| pacer.step().map(|delay| (step, delay)))
+                .collect::<Vec<_>>()
+        };
+        let mut policy = AnimationPolicy::new(FrameTiming::new(4));
+        policy.max_frames = 4;
+        assert_eq!(ends(policy, 3), [(1, 4), (2, 4), (3, 4)]);
+        assert_eq!(ends(policy, 100), [(25, 4), (50, 4), (75, 4), (100, 4)]);
+
+        policy.steps_per_frame = Some(3);
+        assert_eq!(ends(policy, 10), [(3, 4), (6, 4), (9, 4), (10, 4)]);
+
+        // early frames cover fewer steps, and the duration is split evenly
+        policy.steps_per_frame = None;
+        policy.easing = Easing::Quadratic;
+        policy.duration = Some(10);
+        assert_eq!(ends(policy, 100), [(7, 2), (25, 3), (57, 2), (100, 3)]);
+        policy.duration = Some(2);
+        assert_eq!(ends(policy, 100), [(25, 1), (100, 1)]);
+    }
+}
@@ -0,0 +1,412 @@
+//! Generating many square mazes at once for datasets. Items are spread over
+//! worker threads, each maze is carved from its own seed derived from the run's
+//! seed, and a JSON Lines manifest records every finished item so an
+//! interrupted run can pick up where it stopped. The settings the items depend
+//! on are recorded next to the manifest, and a run with other settings refuses
+//! to resume into the same directory.
+use std::collections::HashSet;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+use crate::colors;
+use crate::csr_graph::CsrGraph;
+use crate::generators::generator::{BlockGrid, MazeGenerator};
+use crate::mask::{MASK_OFF, MASK_ON};
+use crate::maze_image;
+use crate::random;
+use crate::solvers::solver::Solver;
+
+/// Manifest written to the output directory, one JSON object per maze.
+pub const MANIFEST_FILE: &str = "manifest.jsonl";
+
+/// Settings of the run that started the output directory, as one JSON object.
+pub const SETTINGS_FILE: &str = "batch.json";
+
+/// Settings shared by all mazes of a batch.
+#[derive(Debug, Clone)]
+pub struct BatchConfig {
+    pub count: usize,
+    pub seed: u64,
+    pub grid: BlockGrid,
+    pub imperfect_percentage: f32,
+    pub output_dir: PathBuf,
+    /// Worker threads
+    pub jobs: usize,
+    /// Pixels per cell of the saved images
+    pub scale: u32,
+}
+
+/// How many items a run produced and how many earlier runs had already finished.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BatchSummary {
+    pub generated: usize,
+    pub skipped: usize,
+}
+
+/// Generates items `0..count`, cycling through `generators`, and solves each
+/// with every solver. Item `i` is saved as `maze_<i>.txt` in the text format and
+/// `maze_<i>.png` showing the first solver's solution. Items already listed in
+/// the manifest with their maze file present are skipped, provided they were
+/// made with the same seed, generators, solvers, grid and imperfection;
+/// otherwise the run fails with `InvalidInput` before generating anything.
+pub fn run_batch(
+    config: &BatchConfig,
+    generators: &[(&str, &(dyn MazeGenerator + Sync))],
+    solvers: &[(&str, &(dyn Solver + Sync))],
+) -> io::Result<BatchSummary> {
+    if generators.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "a batch needs at least one generator",
+        ));
+    }
+    fs::create_dir_all(&config.output_dir)?;
+    let manifest_path = config.output_dir.join(MANIFEST_FILE);
+    check_settings(
+        &config.output_dir.join(SETTINGS_FILE),
+        &manifest_path,
+        &settings_json(config, generators, solvers),
+    )?;
+    let finished = finished_items(&manifest_path, &config.output_dir)?;
+    let manifest = Mutex::new(
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&manifest_path)?,
+    );
+
+    let next = AtomicUsize::new(0);
+    let workers = config.jobs.clamp(1, config.count.max(1));
+    // workers draw the images in the theme of the thread starting the batch
+    let theme = colors::theme();
+    let generated = thread::scope(|scope| {
+        let handles: Vec<_> = (0..workers)
+            .map(|_| {
+                scope.spawn(|| -> io::Result<usize> {
+                    colors::set_theme(theme);
+                    let mut generated = 0;
+                    loop {
+                        let index = next.fetch_add(1, Ordering::Relaxed);
+                        if index >= config.count {
+                            return Ok(generated);
+                        }
+                        if finished.contains(&index) {
+                            continue;
+                        }
+                        let line = generate_item(config, index, generators, solvers)?;
+                        let mut manifest = manifest.lock().unwrap();
+                        writeln!(manifest, "{}", line)?;
+                        manifest.flush()?;
+                        generated += 1;
+                    }
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .map(|handle| handle.join().expect("batch worker panicked"))
+            .sum::<io::Result<usize>>()
+    })?;
+
+    Ok(BatchSummary {
+        generated,
+        skipped: (0..config.count)
+            .filter(|index| finished.contains(index))
+            .count(),
+    })
+}
+
+/// File name stem of item `index`.
+pub fn item_name(index: usize) -> String {
+    format!("maze_{:06}", index)
+}
+
+// generates, solves and saves one item, returning its manifest line
+fn generate_item(
+    config: &BatchConfig,
+    index: usize,
+    generators: &[(&str, &(dyn MazeGenerator + Sync))],
+    solvers: &[(&str, &(dyn Solver + Sync))],
+) -> io::Result<String> {
+    let (generator_name, generator) = generators[index % generators.len()];
+    let seed = random::derive_seed(config.seed, index as u64);
+    random::seed(seed);
+    let maze = generator.generate_on(&config.grid, None, None, config.imperfect_percentage, None);
+
+    let name = item_name(index);
+    let image_name = format!("{}.png", name);
+    let mut stats = Vec::new();
+    let mut solution = None;
+    let graph = CsrGraph::from_maze(&maze);
+    for &(solver_name, solver) in solvers {
+        let result = solver.search(&graph, None).positions(&graph);
+        stats.push(format!(
+            "{{\"name\":\"{}\",\"length\":{},\"cost\":{},\"nodes_expanded\":{},\"max_frontier\":{},\"optimal\":{},\"elapsed_us\":{}}}",
+            solver_name,
+            json_number(result.path.as_ref().map(|path| path.len() - 1)),
+            json_number(result.cost),
+            result.nodes_expanded,
+            result.max_frontier,
+            result.optimal,
+            result.elapsed.as_micros()
+        ));
+        if solution.is_none() {
+            solution = result.path;
+        }
+    }
+
+    // the maze file goes last, marking the item as complete on resume
+    maze_image::save_maze_with_path_to_image_scaled(
+        &maze,
+        solution.as_deref().unwrap_or(&[]),
+        &config.output_dir.join(&image_name).to_string_lossy(),
+        config.scale,
+    )
+    .map_err(io::Error::other)?;
+    let maze_name = format!("{}.txt", name);
+    fs::write(config.output_dir.join(&maze_name), maze.to_string())?;
+
+    Ok(format!(
+        "{{\"index\":{},\"seed\":{},\"generator\":\"{}\",\"maze\":\"{}\",\"image\":\"{}\",\"width\":{},\"height\":{},\"solution_length\":{},\"solvers\":[{}]}}",
+        index,
+        seed,
+        generator_name,
+        maze_name,
+        image_name,
+        config.grid.width,
+        config.grid.height,
+        json_number(solution.map(|path| path.len() - 1)),
+        stats.join(",")
+    ))
+}
+
+fn json_number(value: Option<usize>) -> String {
+    value.map_or("null".to_string(), |value| value.to_string())
+}
+
+// everything the items of a batch depend on; the count may change between
+// runs to extend a batch, and the jobs and image scale do not alter any maze
+fn settings_json(
+    config: &BatchConfig,
+    generators: &[(&str, &(dyn MazeGenerator + Sync))],
+    solvers: &[(&str, &(dyn Solver + Sync))],
+) -> String {
+    let names = |names: Vec<&str>| {
+        names
+            .iter()
+            .map(|name| format!("\"{}\"", name))
+            .collect::<Vec<_>>()
+            .join(",")
+    };
+    let mask = config
+        .grid
+        .mask
+        .as_ref()
+        .map_or("null".to_string(), |mask| {
+            let rows: Vec<String> = (0..mask.rows())
+                .map(|row| {
+                    (0..mask.cols())
+                        .map(|col| {
+                            if mask.is_enabled(row, col) {
+                                MASK_ON
+                            } else {
+                                MASK_OFF
+                            }
+                        })
+                        .collect()
+                })
+                .collect();
+            format!("[{}]", names(rows.iter().map(String::as_str).collect()))
+        });
+    format!(
+        "{{\"seed\":{},\"generators\":[{}],\"solvers\":[{}],\"width\":{},\"height\":{},\"wrap\":\"{}\",\"imperfect_percentage\":{},\"mask\":{}}}",
+        config.seed,
+        names(generators.iter().map(|&(name, _)| name).collect()),
+        names(solvers.iter().map(|&(name, _)| name).collect()),
+        config.grid.width,
+        config.grid.height,
+        config.grid.wrap.name(),
+        config.imperfect_percentage,
+        mask
+    )
+}
+
+// records the settings of a new batch, or checks that an existing one was
+// started with the same; a manifest without recorded settings is not resumed
+fn check_settings(settings_path: &Path, manifest_path: &Path, settings: &str) -> io::Result<()> {
+    let mismatch = || {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "{} holds a batch made with other settings; use a new output directory",
+                settings_path.parent().unwrap_or(settings_path).display()
+            ),
+        )
+    };
+    match fs::read_to_string(settings_path) {
+        Ok(recorded) if recorded.trim_end() == settings => Ok(()),
+        Ok(_) => Err(mismatch()),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            if fs::metadata(manifest_path).is_ok_and(|manifest| manifest.len() > 0) {
+                return Err(mismatch());
+            }
+            fs::write(settings_path, format!("{}\n", settings))
+        }
+        Err(e) => Err(e),
+    }
+}
+
+// indices of complete manifest lines whose maze file exists; the manifest is
+// rewritten without anything else, such as a line cut off by an interruption
+fn finished_items(manifest_path: &Path, output_dir: &Path) -> io::Result<HashSet<usize>> {
+    let text = match fs::read_to_string(manifest_path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(HashSet::new()),
+        Err(e) => return Err(e),
+    };
+    let mut finished = HashSet::new();
+    let mut kept = String::new();
+    for line in text.lines() {
+        let index = line
+            .strip_prefix("{\"index\":")
+            .filter(|_| line.ends_with('}'))
+            .and_then(|rest| rest.split(',').next())
+            .and_then(|index| index.parse::<usize>().ok());
+        let Some(index) = index else {
+            continue;
+        };
+        let complete = output_dir
+            .join(format!("{}.txt", item_name(index)))
+            .exists();
+        if complete && finished.insert(index) {
+            kept.push_str(line);
+            kept.push('\n');
+        }
+    }
+    File::create(manifest_path)?.write_all(kept.as_bytes())?;
+    Ok(finished)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generators::kruskal::KruskalMazeGenerator;
+    use crate::generators::wilson::WilsonMazeGenerator;
+    use crate::solvers::astar::AstarSolver;
+    use crate::solvers::bfs_solver::BfsSolver;
+
+    #[test]
+    fn test_batch_is_reproducible_and_resumable() {
+        let output_dir = std::env::temp_dir().join(format!("maze_batch_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&output_dir);
+        let config = BatchConfig {
+            count: 6,
+            seed: 7,
+            grid: BlockGrid::new(15, 15).unwrap(),
+            imperfect_percentage: 5.0,
+            output_dir: output_dir.clone(),
+            jobs: 3,
+            scale: 2,
+        };
+        let generators: [(&str, &(dyn MazeGenerator + Sync)); 2] = [
+            ("kruskal", &KruskalMazeGenerator),
+            ("wilson", &WilsonMazeGenerator),
+        ];
+        let solvers: [(&str, &(dyn Solver + Sync)); 2] =
+            [("astar", &AstarSolver), ("bfs", &BfsSolver)];
+
+        let first = run_batch(&config, &generators, &solvers).unwrap();
+        assert_eq!(
+            first,
+            BatchSummary {
+                generated: 6,
+                skipped: 0
+            }
+        );
+        let manifest = fs::read_to_string(output_dir.join(MANIFEST_FILE)).unwrap();
+        assert_eq!(manifest.lines().count(), 6);
+        assert!(manifest.contains("\"generator\":\"wilson\""));
+
+        // an interrupted run: one item lost its maze file, another its line
+        let maze_3 = output_dir.join(format!("{}.txt", item_name(3)));
+        let original = fs::read_to_string(&maze_3).unwrap();
+        fs::remove_file(&maze_3).unwrap();
+        let cut: Vec<&str> = manifest
+            .lines()
+            .filter(|line| !line.starts_with("{\"index\":5,"))
+            .collect();
+        fs::write(
+            output_dir.join(MANIFEST_FILE),
+            cut.join("\n") + "\n{\"index\":5,\"se",
+        )
+        .unwrap();
+
+        let resumed = run_batch(&config, &generators, &solvers).unwrap();
+        assert_eq!(
+            resumed,
+            BatchSummary {
+                generated: 2,
+                skipped: 4
+            }
+        );
+        assert_eq!(fs::read_to_string(&maze_3).unwrap(), original);
+        let manifest = fs::read_to_string(output_dir.join(MANIFEST_FILE)).unwrap();
+        assert_eq!(manifest.lines().count(), 6);
+
+        fs::remove_dir_all(&output_dir).unwrap();
+    }
+
+    #[test]
+    fn test_batch_refuses_to_resume_other_settings() {
+        let output_dir =
+            std::env::temp_dir().join(format!("maze_batch_settings_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&output_dir);
+        let mut config = BatchConfig {
+            count: 2,
+            seed: 7,
+            grid: BlockGrid::new(11, 11).unwrap(),
+            imperfect_percentage: 0.0,
+            output_dir: output_dir.clone(),
+            jobs: 1,
+            scale: 1,
+        };
+        let kruskal: [(&str, &(dyn MazeGenerator + Sync)); 1] =
+            [("kruskal", &KruskalMazeGenerator)];
+        let wilson: [(&str, &(dyn MazeGenerator + Sync)); 1] = [("wilson", &WilsonMazeGenerator)];
+        let solvers: [(&str, &(dyn Solver + Sync)); 1] = [("bfs", &BfsSolver)];
+        run_batch(&config, &kruskal, &solvers).unwrap();
+
+        let refused = |config: &BatchConfig, generators| {
+            run_batch(config, generators, &solvers)
+                .is_err_and(|e| e.kind() == io::ErrorKind::InvalidInput)
+        };
+        assert!(refused(&config, &wilson));
+        config.seed = 8;
+        assert!(refused(&config, &kruskal));
+        config.seed = 7;
+        config.grid = BlockGrid::new(13, 11).unwrap();
+        assert!(refused(&config, &kruskal));
+        config.grid = BlockGrid::new(11, 11).unwrap();
+
+        // more items with the same settings extend the batch
+        config.count = 3;
+        let extended = run_batch(&config, &kruskal, &solvers).unwrap();
+        assert_eq!(
+            extended,
+            BatchSummary {
+                generated: 1,
+                skipped: 2
+            }
+        );
+
+        // a manifest left by a run that did not record its settings
+        fs::remove_file(output_dir.join(SETTINGS_FILE)).unwrap();
+        assert!(refused(&config, &kruskal));
+
+        fs::remove_dir_all(&output_dir).unwrap();
+    }
+}
@@ -0,0 +1,244 @@
+//! Compact storage for very large square mazes: one bit per cell in a single
+//! contiguous allocation, so a 100M-cell maze takes about 12 MB.
+use crate::cell::Cell;
+use crate::maze::Maze;
+use crate::maze_graph::MazeGraph;
+
+/// Bottom-right logical cell of a `width` x `height` block maze, the last odd
+/// row and column even when a side has even length.
+pub fn last_cell(width: usize, height: usize) -> (usize, usize) {
+    (
+        (height.max(3) - 1) / 2 * 2 - 1,
+        (width.max(3) - 1) / 2 * 2 - 1,
+    )
+}
+
+/// Fixed-size set of indices in `0..len`, one bit each.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BitSet {
+    words: Vec<u64>,
+    len: usize,
+}
+
+impl BitSet {
+    pub fn new(len: usize) -> Self {
+        BitSet {
+            words: vec![0; len.div_ceil(64)],
+            len,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn contains(&self, index: usize) -> bool {
+        self.words[index / 64] & (1 << (index % 64)) != 0
+    }
+
+    /// Adds `index`, returning whether it was not in the set yet.
+    pub fn insert(&mut self, index: usize) -> bool {
+        let word = &mut self.words[index / 64];
+        let bit = 1 << (index % 64);
+        let added = *word & bit == 0;
+        *word |= bit;
+        added
+    }
+
+    pub fn remove(&mut self, index: usize) {
+        self.words[index / 64] &= !(1 << (index % 64));
+    }
+
+    /// Number of indices in the set.
+    pub fn count(&self) -> usize {
+        self.words
+            .iter()
+            .map(|word| word.count_ones() as usize)
+            .sum()
+    }
+}
+
+/// Square maze of plain walls and passages packed one bit per cell, with
+/// row-major cell indices that double as graph nodes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BitGrid {
+    pub width: usize,
+    pub height: usize,
+    /// Open cells; everything else is wall
+    open: BitSet,
+    pub start: (usize, usize),
+    pub end: (usize, usize),
+}
+
+impl BitGrid {
+    /// Grid of walls only.
+    pub fn new(width: usize, height: usize, start: (usize, usize), end: (usize, usize)) -> Self {
+        BitGrid {
+            width,
+            height,
+            open: BitSet::new(width * height),
+            start,
+            end,
+        }
+    }
+
+    /// Packs a maze, keeping only whether each cell can be walked on: doors
+    /// become walls and all other special cells plain passages.
+    pub fn from_maze(maze: &Maze) -> Self {
+        let height = maze.cells.len();
+        let width = maze.cells[0].len();
+        let mut grid = BitGrid::new(width, height, maze.start, maze.end);
+        for (row, cells) in maze.cells.iter().enumerate() {
+            for (col, cell) in cells.iter().enumerate() {
+                grid.set_open(row, col, cell.is_open());
+            }
+        }
+        grid
+    }
+
+    /// Unpacks into a maze of `Cell::Wall` and `Cell::Path`.
+    pub fn to_maze(&self) -> Maze {
+        let cells = (0..self.height)
+            .map(|row| {
+                (0..self.width)
+                    .map(|col| {
+                        if self.is_open(row, col) {
+                            Cell::Path
+                        } else {
+                            Cell::Wall
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+        Maze::new(cells, self.start, self.end)
+    }
+
+    pub fn index(&self, row: usize, col: usize) -> usize {
+        row * self.width + col
+    }
+
+    pub fn is_open(&self, row: usize, col: usize) -> bool {
+        self.open.contains(self.index(row, col))
+    }
+
+    pub fn set_open(&mut self, row: usize, col: usize, open: bool) {
+        let index = self.index(row, col);
+        if open {
+            self.open.insert(index);
+        } else {
+            self.open.remove(index);
+        }
+    }
+
+    /// Number of open cells.
+    pub fn open_count(&self) -> usize {
+        self.open.count()
+    }
+}
+
+impl MazeGraph for BitGrid {
+    fn node_count(&self) -> usize {
+        self.width * self.height
+    }
+
+    fn start_node(&self) -> usize {
+        self.index(self.start.0, self.start.1)
+    }
+
+    fn end_node(&self) -> usize {
+        self.index(self.end.0, self.end.1)
+    }
+
+    fn neighbors(&self, node: usize) -> Vec<(usize, usize)> {
+        if !self.open.contains(node) {
+            return Vec::new();
+        }
+        let (row, col) = self.position(node);
+        Maze::get_neighbors(row, col, self.height, self.width)
+            .into_iter()
+            .filter(|&(row, col)| self.is_open(row, col))
+            .map(|(row, col)| (self.index(row, col), 1))
+            .collect()
+    }
+
+    fn heuristic(&self, node: usize) -> usize {
+        let (row, col) = self.position(node);
+        row.abs_diff(self.end.0) + col.abs_diff(self.end.1)
+    }
+
+    fn position(&self, node: usize) -> (usize, usize) {
+        (node / self.width, node % self.width)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generators::generator::MazeGenerator;
+    use crate::generators::recursive_backtracker::RecursiveBacktrackerMazeGenerator;
+    use crate::maze::MazeError;
+    use crate::solvers::astar::AstarSolver;
+    use crate::solvers::bfs_solver::BfsSolver;
+    use crate::solvers::solver::Solver;
+
+    #[test]
+    fn test_bit_set() {
+        let mut set = BitSet::new(130);
+        assert!(set.insert(0));
+        assert!(set.insert(129));
+        assert!(!set.insert(129));
+        assert!(set.contains(129) && !set.contains(64));
+        set.remove(0);
+        assert_eq!(set.count(), 1);
+    }
+
+    #[test]
+    fn test_round_trip_and_solve() {
+        let maze = RecursiveBacktrackerMazeGenerator
+            .generate(31, 21, None, None, 10.0, None)
+            .unwrap();
+        let grid = BitGrid::from_maze(&maze);
+        assert_eq!(grid.to_maze().cells, maze.cells);
+
+        let on_grid = AstarSolver.solve_graph(&grid, None).unwrap();
+        let on_maze = BfsSolver.solve(&maze, None).unwrap();
+        assert_eq!(on_grid.len(), on_maze.len());
+    }
+
+    #[test]
+    fn test_compact_generation_is_perfect() {
+        let grid = RecursiveBacktrackerMazeGenerator
+            .generate_compact(41, 31)
+            .unwrap();
+        // a spanning tree over the 20 x 15 logical cells opens them and one
+        // passage between each pair joined by the tree
+        assert_eq!(grid.open_count(), 20 * 15 + (20 * 15 - 1));
+        let path = BfsSolver.solve_graph(&grid, None).unwrap();
+        assert_eq!(grid.position(path[path.len() - 1]), grid.end);
+    }
+
+    #[test]
+    fn test_compact_generation_with_even_size() {
+        let grid = RecursiveBacktrackerMazeGenerator
+            .generate_compact(50, 40)
+            .unwrap();
+        assert_eq!(grid.end, (37, 47));
+        let path = BfsSolver.solve_graph(&grid, None).unwrap();
+        assert_eq!(grid.position(path[path.len() - 1]), grid.end);
+    }
+
+    #[test]
+    fn test_compact_generation_rejects_tiny_sizes() {
+        for (width, height) in [(0, 0), (2, 5), (5, 1)] {
+            assert!(matches!(
+                RecursiveBacktrackerMazeGenerator.generate_compact(width, height),
+                Err(MazeError::InvalidDimensions)
+            ));
+        }
+    }
+}
@@ -1,5 +1,140 @@
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Cell {
     Wall,
     Path,
+    /// Two corridors crossing; the one running along the axis passes over the other
+    Crossing(Axis),
+    /// Outside the maze's shape, neither wall nor passage
+    Void,
+    /// Key of the given color, picked up by walking over it
+    Key(u8),
+    /// Door of the given color, passable only while holding the matching key
+    Door(u8),
+    /// Portal with the given letter index, leading to the other portal of that letter
+    Portal(u8),
+    /// Passage that may only be walked through in the given direction
+    OneWay(Direction),
+    /// Checkpoint a tour has to visit on its way from the start to the end
+    Checkpoint,
+}
+
+/// Number of distinct key/door colors
+pub const KEY_COLORS: u8 = 4;
+/// Text format characters for keys, indexed by color
+pub const KEY_CHARS: [char; KEY_COLORS as usize] = ['1', '2', '3', '4'];
+/// Text format characters for doors, indexed by color
+pub const DOOR_CHARS: [char; KEY_COLORS as usize] = ['A', 'B', 'C', 'D'];
+/// Text format character for checkpoints
+pub const CHECKPOINT_CHAR: char = '*';
+/// Number of distinct portal letters, `a` to `z` in the text format except
+/// `v`, which is the south-pointing one-way arrow
+pub const PORTAL_LETTERS: u8 = 25;
+
+impl Cell {
+    /// Whether the cell can be walked through
+    pub fn is_open(self) -> bool {
+        matches!(
+            self,
+            Cell::Path
+                | Cell::Crossing(_)
+                | Cell::Key(_)
+                | Cell::Portal(_)
+                | Cell::OneWay(_)
+                | Cell::Checkpoint
+        )
+    }
+
+    /// Text format character of a portal letter index
+    pub fn portal_char(letter: u8) -> char {
+        let ch = b'a' + letter;
+        if ch >= b'v' {
+            (ch + 1) as char
+        } else {
+            ch as char
+        }
+    }
+
+    /// Portal letter index of a text format character, if it names a portal
+    pub fn portal_letter(ch: char) -> Option<u8> {
+        match ch {
+            'a'..='u' => Some(ch as u8 - b'a'),
+            'w'..='z' => Some(ch as u8 - b'a' - 1),
+            _ => None,
+        }
+    }
+}
+
+/// Direction a corridor runs in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Axis {
+    Horizontal,
+    Vertical,
+}
+
+impl Axis {
+    /// Axis of a single step between neighbouring cells (including steps across a wrapping edge)
+    pub fn of_step(from: (usize, usize), to: (usize, usize)) -> Self {
+        if from.0 == to.0 {
+            Axis::Horizontal
+        } else {
+            Axis::Vertical
+        }
+    }
+}
+
+/// Compass direction of a single step
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    North,
+    South,
+    West,
+    East,
+}
+
+impl Direction {
+    pub const ALL: [Direction; 4] = [
+        Direction::North,
+        Direction::South,
+        Direction::West,
+        Direction::East,
+    ];
+
+    /// Direction of a single step between neighbouring cells; a step that
+    /// jumps to the far side of a row or column crosses a wrapping edge.
+    pub fn of_step(from: (usize, usize), to: (usize, usize)) -> Self {
+        if from.0 == to.0 {
+            if to.1 == from.1 + 1 || (to.1 == 0 && from.1 > 1) {
+                Direction::East
+            } else {
+                Direction::West
+            }
+        } else if to.0 == from.0 + 1 || (to.0 == 0 && from.0 > 1) {
+            Direction::South
+        } else {
+            Direction::North
+        }
+    }
+
+    pub fn opposite(self) -> Self {
+        match self {
+            Direction::North => Direction::South,
+            Direction::South => Direction::North,
+            Direction::West => Direction::East,
+            Direction::East => Direction::West,
+        }
+    }
+
+    /// Arrow character of a one-way cell in the text format
+    pub fn arrow(self) -> char {
+        match self {
+            Direction::North => '^',
+            Direction::South => 'v',
+            Direction::West => '<',
+            Direction::East => '>',
+        }
+    }
+
+    pub fn from_arrow(ch: char) -> Option<Self> {
+        Direction::ALL.into_iter().find(|dir| dir.arrow() == ch)
+    }
 }
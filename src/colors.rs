@@ -1,17 +1,481 @@
-//! Color definitions (based on Matplotlib 'tab10' palette)
+//! Color themes shared by every renderer. The default theme is based on the
+//! Matplotlib 'tab10' palette; others are chosen by name or read from a TOML
+//! file, and each thread renders with its own current theme, set by `set_theme`.
 use image::Rgb;
+use std::cell::Cell;
+use std::error::Error;
+use std::fs;
 
-/// Wall (background) color: gray
-pub const WALL_COLOR: Rgb<u8> = Rgb([0x7f, 0x7f, 0x7f]);
-/// Open path (corridor) color: white
-pub const PATH_COLOR: Rgb<u8> = Rgb([0xff, 0xff, 0xff]);
-/// Start cell color: green
-pub const START_COLOR: Rgb<u8> = Rgb([0x2c, 0xa0, 0x2c]);
-/// End cell color: red
-pub const END_COLOR: Rgb<u8> = Rgb([0xd6, 0x27, 0x28]);
-/// Solution path color: blue
-pub const SOLUTION_PATH_COLOR: Rgb<u8> = Rgb([0x1f, 0x77, 0xb4]);
-/// Visited cell overlay color: orange
-pub const VISITED_COLOR: Rgb<u8> = Rgb([0xff, 0x7f, 0x0e]);
-/// Current cell highlight color: purple
-pub const CURRENT_COLOR: Rgb<u8> = Rgb([0x94, 0x67, 0xbd]);
+/// Colors of everything drawn in PNG, GIF, SVG and terminal output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Theme {
+    /// Wall color
+    pub wall: Rgb<u8>,
+    /// Open path (corridor) color
+    pub path: Rgb<u8>,
+    /// Start cell color
+    pub start: Rgb<u8>,
+    /// End cell color
+    pub end: Rgb<u8>,
+    /// Solution path color
+    pub solution_path: Rgb<u8>,
+    /// Visited cell overlay color
+    pub visited: Rgb<u8>,
+    /// Current cell highlight color
+    pub current: Rgb<u8>,
+    /// Stairs marker color
+    pub stairs: Rgb<u8>,
+    /// Wrap-around passage color
+    pub wrap: Rgb<u8>,
+    /// Checkpoint marker color
+    pub checkpoint: Rgb<u8>,
+    /// Background outside a shaped maze where transparency is unavailable
+    pub background: Rgb<u8>,
+    /// Key and door colors, indexed by key color
+    pub key_doors: [Rgb<u8>; 4],
+    /// Portal colors, cycled through by portal letter
+    pub portals: [Rgb<u8>; PORTAL_COLOR_COUNT],
+    /// Colors of routes overlaid on one maze, starting with the solution path
+    pub routes: [Rgb<u8>; 7],
+    /// Stops of the visit-order heatmap, from first to last visited
+    pub heatmap: [Rgb<u8>; 3],
+}
+
+/// Number of portal colors, cycled through by portal letter.
+pub const PORTAL_COLOR_COUNT: usize = 6;
+
+/// Names accepted by `Theme::from_name`.
+pub const THEME_NAMES: [&str; 5] = ["tab10", "high-contrast", "colorblind", "dark", "grayscale"];
+
+const fn rgb(hex: u32) -> Rgb<u8> {
+    Rgb([(hex >> 16) as u8, (hex >> 8) as u8, hex as u8])
+}
+
+const fn gray(level: u8) -> Rgb<u8> {
+    Rgb([level, level, level])
+}
+
+impl Theme {
+    /// Matplotlib 'tab10': gray walls, white corridors, green start, red end,
+    /// blue solution, orange visits and purple current cell.
+    pub const TAB10: Theme = Theme {
+        wall: rgb(0x7f7f7f),
+        path: rgb(0xffffff),
+        start: rgb(0x2ca02c),
+        end: rgb(0xd62728),
+        solution_path: rgb(0x1f77b4),
+        visited: rgb(0xff7f0e),
+        current: rgb(0x9467bd),
+        stairs: rgb(0x8c564b),
+        wrap: rgb(0x17becf),
+        checkpoint: rgb(0xffd700),
+        background: rgb(0xc7c7c7),
+        // pink, olive, light green, light purple
+        key_doors: [rgb(0xe377c2), rgb(0xbcbd22), rgb(0x98df8a), rgb(0xc5b0d5)],
+        // light blue, light orange, light red, light brown, light pink, light cyan
+        portals: [
+            rgb(0xaec7e8),
+            rgb(0xffbb78),
+            rgb(0xff9896),
+            rgb(0xc49c94),
+            rgb(0xf7b6d2),
+            rgb(0x9edae5),
+        ],
+        // blue, orange, purple, brown, pink, olive, cyan
+        routes: [
+            rgb(0x1f77b4),
+            rgb(0xff7f0e),
+            rgb(0x9467bd),
+            rgb(0x8c564b),
+            rgb(0xe377c2),
+            rgb(0xbcbd22),
+            rgb(0x17becf),
+        ],
+        // pale orange, orange, dark orange
+        heatmap: [rgb(0xffe0b0), rgb(0xff7f0e), rgb(0x9c3d00)],
+    };
+
+    /// Black walls on white with fully saturated markers.
+    pub const HIGH_CONTRAST: Theme = Theme {
+        wall: rgb(0x000000),
+        path: rgb(0xffffff),
+        start: rgb(0x00b000),
+        end: rgb(0xe00000),
+        solution_path: rgb(0x0040ff),
+        visited: rgb(0xff8c00),
+        current: rgb(0xb000ff),
+        stairs: rgb(0x804000),
+        wrap: rgb(0x00c0c0),
+        checkpoint: rgb(0xffd700),
+        background: rgb(0xd0d0d0),
+        key_doors: [rgb(0xff00c0), rgb(0xa0a000), rgb(0x60ff60), rgb(0x8080ff)],
+        portals: [
+            rgb(0x80c0ff),
+            rgb(0xffb060),
+            rgb(0xff8080),
+            rgb(0xc09060),
+            rgb(0xffa0e0),
+            rgb(0x80ffff),
+        ],
+        routes: [
+            rgb(0x0040ff),
+            rgb(0xff8c00),
+            rgb(0xb000ff),
+            rgb(0x804000),
+            rgb(0xff00c0),
+            rgb(0xa0a000),
+            rgb(0x00c0c0),
+        ],
+        heatmap: [rgb(0xffe890), rgb(0xff8c00), rgb(0x802000)],
+    };
+
+    /// Okabe-Ito colors, told apart with any common color vision deficiency.
+    pub const COLORBLIND: Theme = Theme {
+        wall: rgb(0x5a5a5a),
+        path: rgb(0xffffff),
+        start: rgb(0x009e73),
+        end: rgb(0xd55e00),
+        solution_path: rgb(0x0072b2),
+        visited: rgb(0xe69f00),
+        current: rgb(0xcc79a7),
+        stairs: rgb(0x8c6d31),
+        wrap: rgb(0x56b4e9),
+        checkpoint: rgb(0xf0e442),
+        background: rgb(0xc7c7c7),
+        key_doors: [rgb(0xe3a6c8), rgb(0xb8a800), rgb(0x66c5ab), rgb(0x9fc6e0)],
+        portals: [
+            rgb(0xa6d5f2),
+            rgb(0xf3cf80),
+            rgb(0xeaae80),
+            rgb(0xc6b698),
+            rgb(0xe6bcd3),
+            rgb(0x80cfb9),
+        ],
+        routes: [
+            rgb(0x0072b2),
+            rgb(0xe69f00),
+            rgb(0xcc79a7),
+            rgb(0x009e73),
+            rgb(0xd55e00),
+            rgb(0x56b4e9),
+            rgb(0xf0e442),
+        ],
+        heatmap: [rgb(0xfbe7b8), rgb(0xe69f00), rgb(0x7a5400)],
+    };
+
+    /// Light markers on dark corridors, with visits glowing brighter over time.
+    pub const DARK: Theme = Theme {
+        wall: rgb(0x101010),
+        path: rgb(0x3a3a3a),
+        start: rgb(0x4caf50),
+        end: rgb(0xef5350),
+        solution_path: rgb(0x42a5f5),
+        visited: rgb(0xffa726),
+        current: rgb(0xab47bc),
+        stairs: rgb(0xa1887f),
+        wrap: rgb(0x26c6da),
+        checkpoint: rgb(0xffd54f),
+        background: rgb(0x202020),
+        key_doors: [rgb(0xf48fb1), rgb(0xdce775), rgb(0xa5d6a7), rgb(0xce93d8)],
+        portals: [
+            rgb(0x90caf9),
+            rgb(0xffcc80),
+            rgb(0xef9a9a),
+            rgb(0xbcaaa4),
+            rgb(0xf8bbd0),
+            rgb(0x80deea),
+        ],
+        routes: [
+            rgb(0x42a5f5),
+            rgb(0xffa726),
+            rgb(0xab47bc),
+            rgb(0xa1887f),
+            rgb(0xf48fb1),
+            rgb(0xdce775),
+            rgb(0x26c6da),
+        ],
+        heatmap: [rgb(0x4e2a0a), rgb(0xffa726), rgb(0xfff3e0)],
+    };
+
+    /// Gray levels only, for printing; every marker still has a level of its
+    /// own so saved images load back.
+    pub const GRAYSCALE: Theme = Theme {
+        wall: gray(0x00),
+        path: gray(0xff),
+        start: gray(0x50),
+        end: gray(0x30),
+        solution_path: gray(0xa0),
+        visited: gray(0xc8),
+        current: gray(0x78),
+        stairs: gray(0x68),
+        wrap: gray(0xd8),
+        checkpoint: gray(0x90),
+        background: gray(0xf0),
+        key_doors: [gray(0x20), gray(0x40), gray(0x60), gray(0x80)],
+        portals: [
+            gray(0xa8),
+            gray(0xb0),
+            gray(0xb8),
+            gray(0xc0),
+            gray(0xe0),
+            gray(0xe8),
+        ],
+        routes: [
+            gray(0xa0),
+            gray(0xc8),
+            gray(0x78),
+            gray(0x68),
+            gray(0x20),
+            gray(0x40),
+            gray(0xd8),
+        ],
+        heatmap: [gray(0xe4), gray(0xb4), gray(0x70)],
+    };
+
+    /// Built-in theme by name, one of `THEME_NAMES`.
+    pub fn from_name(name: &str) -> Option<Theme> {
+        match name {
+            "tab10" => Some(Theme::TAB10),
+            "high-contrast" => Some(Theme::HIGH_CONTRAST),
+            "colorblind" => Some(Theme::COLORBLIND),
+            "dark" => Some(Theme::DARK),
+            "grayscale" => Some(Theme::GRAYSCALE),
+            _ => None,
+        }
+    }
+
+    /// Reads a theme from TOML text of `key = "#rrggbb"` lines, using the keys
+    /// of the struct fields and arrays of colors for the lists. Colors left out
+    /// keep those of the theme named by an optional `base` key, or tab10.
+    pub fn from_toml(text: &str) -> Result<Theme, String> {
+        let mut entries = Vec::new();
+        let mut pending = String::new();
+        for (number, line) in text.lines().enumerate() {
+            pending.push_str(strip_comment(line));
+            pending.push(' ');
+            // an array may span several lines until its bracket closes
+            if pending.matches('[').count() > pending.matches(']').count() {
+                continue;
+            }
+            let entry = std::mem::take(&mut pending);
+            let entry = entry.trim();
+            if entry.is_empty() {
+                continue;
+            }
+            let (key, value) = entry
+                .split_once('=')
+                .ok_or_else(|| format!("line {}: expected 'key = value'", number + 1))?;
+            entries.push((number + 1, key.trim().to_string(), value.trim().to_string()));
+        }
+        if !pending.trim().is_empty() {
+            return Err("unclosed array at the end of the theme".to_string());
+        }
+
+        let mut theme = Theme::TAB10;
+        if let Some((number, _, value)) = entries.iter().find(|(_, key, _)| key == "base") {
+            let name = parse_string(value).map_err(|e| format!("line {}: {}", number, e))?;
+            theme = Theme::from_name(&name)
+                .ok_or_else(|| format!("line {}: unknown base theme '{}'", number, name))?;
+        }
+        for (number, key, value) in &entries {
+            theme
+                .set(key, value)
+                .map_err(|e| format!("line {}: {}", number, e))?;
+        }
+        Ok(theme)
+    }
+
+    fn set(&mut self, key: &str, value: &str) -> Result<(), String> {
+        let color = || parse_color(&parse_string(value)?);
+        match key {
+            "base" => {}
+            "wall" => self.wall = color()?,
+            "path" => self.path = color()?,
+            "start" => self.start = color()?,
+            "end" => self.end = color()?,
+            "solution_path" => self.solution_path = color()?,
+            "visited" => self.visited = color()?,
+            "current" => self.current = color()?,
+            "stairs" => self.stairs = color()?,
+            "wrap" => self.wrap = color()?,
+            "checkpoint" => self.checkpoint = color()?,
+            "background" => self.background = color()?,
+            "key_doors" => self.key_doors = parse_colors(value)?,
+            "portals" => self.portals = parse_colors(value)?,
+            "routes" => self.routes = parse_colors(value)?,
+            "heatmap" => self.heatmap = parse_colors(value)?,
+            _ => return Err(format!("unknown theme key '{}'", key)),
+        }
+        Ok(())
+    }
+
+    /// Heatmap color at `t` in `0.0..=1.0`, in 64 steps so a frame of it still
+    /// fits a GIF palette.
+    pub fn heatmap_color(&self, t: f64) -> Rgb<u8> {
+        let stops = &self.heatmap;
+        let t = (t.clamp(0.0, 1.0) * 63.0).round() / 63.0 * (stops.len() - 1) as f64;
+        let stop = (t as usize).min(stops.len() - 2);
+        let (from, to, f) = (stops[stop], stops[stop + 1], t - stop as f64);
+        Rgb(std::array::from_fn(|i| {
+            (from.0[i] as f64 + (to.0[i] as f64 - from.0[i] as f64) * f).round() as u8
+        }))
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::TAB10
+    }
+}
+
+thread_local! {
+    static THEME: Cell<Theme> = const { Cell::new(Theme::TAB10) };
+}
+
+/// Theme the current thread renders with.
+pub fn theme() -> Theme {
+    THEME.with(Cell::get)
+}
+
+/// Renders everything drawn on the current thread from now on with `theme`.
+pub fn set_theme(theme: Theme) {
+    THEME.with(|current| current.set(theme));
+}
+
+/// Built-in theme by name, or else the theme file at `name_or_path`.
+pub fn load_theme(name_or_path: &str) -> Result<Theme, Box<dyn Error>> {
+    if let Some(theme) = Theme::from_name(name_or_path) {
+        return Ok(theme);
+    }
+    let text = fs::read_to_string(name_or_path).map_err(|e| {
+        format!(
+            "'{}' is neither a theme ({}) nor a readable file: {}",
+            name_or_path,
+            THEME_NAMES.join(", "),
+            e
+        )
+    })?;
+    Ok(Theme::from_toml(&text).map_err(|e| format!("{}: {}", name_or_path, e))?)
+}
+
+// drops a `#` comment, but not the `#` starting a color inside quotes
+fn strip_comment(line: &str) -> &str {
+    let mut quoted = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            '#' if !quoted => return &line[..i],
+            _ => {}
+        }
+    }
+    line
+}
+
+fn parse_string(value: &str) -> Result<String, String> {
+    value
+        .strip_prefix('"')
+        .and_then(|value| value.strip_suffix('"'))
+        .map(str::to_string)
+        .ok_or_else(|| format!("expected a quoted string, found '{}'", value))
+}
+
+fn parse_color(text: &str) -> Result<Rgb<u8>, String> {
+    let hex = text.strip_prefix('#').unwrap_or(text);
+    match u32::from_str_radix(hex, 16) {
+        Ok(value) if hex.len() == 6 => Ok(rgb(value)),
+        _ => Err(format!(
+            "expected a color like \"#1f77b4\", found '{}'",
+            text
+        )),
+    }
+}
+
+fn parse_colors<const N: usize>(value: &str) -> Result<[Rgb<u8>; N], String> {
+    let items = value
+        .strip_prefix('[')
+        .and_then(|value| value.strip_suffix(']'))
+        .ok_or_else(|| format!("expected an array of {} colors", N))?;
+    let colors = items
+        .split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(|item| parse_color(&parse_string(item)?))
+        .collect::<Result<Vec<_>, _>>()?;
+    let count = colors.len();
+    colors
+        .try_into()
+        .map_err(|_| format!("expected {} colors, found {}", N, count))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_theme_file_overrides_base() {
+        let theme = Theme::from_toml(
+            r##"
+            # night variant
+            base = "dark"
+            start = "#00ff00"   # bright green
+            portals = [
+                "#010203", "#040506", "#070809",
+                "#0a0b0c", "#0d0e0f", "#101112",
+            ]
+            "##,
+        )
+        .unwrap();
+        assert_eq!(theme.start, Rgb([0, 255, 0]));
+        assert_eq!(theme.portals[5], Rgb([0x10, 0x11, 0x12]));
+        assert_eq!(theme.wall, Theme::DARK.wall);
+
+        assert!(Theme::from_toml("wall = \"#12345\"").is_err());
+        assert!(Theme::from_toml("heatmap = [\"#000000\"]").is_err());
+        assert!(Theme::from_toml("walls = \"#000000\"").is_err());
+    }
+
+    #[test]
+    fn test_built_in_themes_keep_markers_apart() {
+        // colors that loading a saved image tells apart must all differ
+        for name in THEME_NAMES {
+            let theme = Theme::from_name(name).unwrap();
+            let mut markers = vec![
+                theme.wall,
+                theme.path,
+                theme.start,
+                theme.end,
+                theme.solution_path,
+                theme.wrap,
+                theme.checkpoint,
+            ];
+            markers.extend(theme.key_doors);
+            markers.extend(theme.portals);
+            for (i, color) in markers.iter().enumerate() {
+                assert!(!markers[i + 1..].contains(color), "{}: {:?}", name, color);
+            }
+        }
+    }
+
+    #[test]
+    fn test_saved_images_load_back_in_every_theme() {
+        use crate::generators::generator::MazeGenerator;
+        use crate::generators::kruskal::KruskalMazeGenerator;
+        use crate::maze_image::{load_maze_from_image_scaled, save_maze_to_image_scaled};
+
+        let mut maze = KruskalMazeGenerator
+            .generate(15, 11, None, None, 10.0, None)
+            .unwrap();
+        maze.cells[1][3] = crate::cell::Cell::Checkpoint;
+        let path = std::env::temp_dir().join(format!("maze_theme_{}.png", std::process::id()));
+        let path = path.to_string_lossy();
+        for name in THEME_NAMES {
+            set_theme(Theme::from_name(name).unwrap());
+            save_maze_to_image_scaled(&maze, &path, 4).unwrap();
+            let loaded = load_maze_from_image_scaled(&path, 4).unwrap();
+            assert_eq!(loaded.cells, maze.cells, "{}", name);
+            assert_eq!((loaded.start, loaded.end), (maze.start, maze.end));
+        }
+        set_theme(Theme::TAB10);
+        std::fs::remove_file(path.as_ref()).unwrap();
+    }
+}
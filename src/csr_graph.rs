@@ -0,0 +1,196 @@
+//! Adjacency built once per maze and shared by every solver run on it, instead
+//! of each solver asking the maze for fresh neighbour lists at every step.
+use std::collections::VecDeque;
+
+use crate::maze::Maze;
+use crate::maze_graph::{KeyDoorGraph, MazeGraph};
+
+/// Compressed sparse row copy of the part of a graph reachable from its start.
+/// Reachable nodes are renumbered densely in breadth-first order, so per-node
+/// solver arrays only cover cells that can matter, and the neighbours of a node
+/// are one contiguous slice visited without allocating.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CsrGraph {
+    /// Edges of node `i` are `targets[offsets[i]..offsets[i + 1]]`
+    offsets: Vec<u32>,
+    targets: Vec<u32>,
+    weights: Vec<u32>,
+    heuristics: Vec<u32>,
+    positions: Vec<(u32, u32)>,
+    /// Nodes that solve the maze; the end node comes first if reachable
+    goals: Vec<u32>,
+}
+
+impl CsrGraph {
+    /// Copies `graph`, visiting each reachable node's neighbours once.
+    pub fn new(graph: &dyn MazeGraph) -> Self {
+        let mut ids = vec![u32::MAX; graph.node_count()];
+        let mut nodes = VecDeque::from([graph.start_node()]);
+        ids[graph.start_node()] = 0;
+        let mut csr = CsrGraph {
+            offsets: vec![0],
+            targets: Vec::new(),
+            weights: Vec::new(),
+            heuristics: Vec::new(),
+            positions: Vec::new(),
+            goals: Vec::new(),
+        };
+
+        // ids are handed out in the order nodes are processed, so each node's
+        // edges are appended right after those of the node before it
+        let mut next_id = 1;
+        while let Some(node) = nodes.pop_front() {
+            let id = ids[node];
+            if graph.is_goal(node) {
+                csr.goals.push(id);
+            }
+            let (row, col) = graph.position(node);
+            csr.positions.push((row as u32, col as u32));
+            csr.heuristics.push(graph.heuristic(node) as u32);
+            for (neighbor, weight) in graph.neighbors(node) {
+                if ids[neighbor] == u32::MAX {
+                    assert!(
+                        next_id < u32::MAX,
+                        "graph too large for 32-bit node indices"
+                    );
+                    ids[neighbor] = next_id;
+                    next_id += 1;
+                    nodes.push_back(neighbor);
+                }
+                csr.targets.push(ids[neighbor]);
+                csr.weights.push(weight as u32);
+            }
+            csr.offsets.push(csr.targets.len() as u32);
+        }
+        // keep the end node itself as the end node when it is reachable
+        let end = ids[graph.end_node()];
+        if let Some(i) = csr.goals.iter().position(|&goal| goal == end) {
+            csr.goals.swap(0, i);
+        }
+        csr
+    }
+
+    /// Graph of a square maze as the solvers see it, through the key and door
+    /// state space if the maze has doors.
+    pub fn from_maze(maze: &Maze) -> Self {
+        if maze.has_doors() {
+            CsrGraph::new(&KeyDoorGraph::new(maze))
+        } else {
+            CsrGraph::new(maze)
+        }
+    }
+
+    /// Number of directed edges.
+    pub fn edge_count(&self) -> usize {
+        self.targets.len()
+    }
+
+    fn edges(&self, node: usize) -> std::ops::Range<usize> {
+        self.offsets[node] as usize..self.offsets[node + 1] as usize
+    }
+}
+
+impl MazeGraph for CsrGraph {
+    fn node_count(&self) -> usize {
+        self.positions.len()
+    }
+
+    fn start_node(&self) -> usize {
+        0
+    }
+
+    // an unreachable end gets no node of its own; any index past the last
+    // node keeps searches from ever finding it
+    fn end_node(&self) -> usize {
+        self.goals
+            .first()
+            .map_or(self.node_count(), |&goal| goal as usize)
+    }
+
+    fn is_goal(&self, node: usize) -> bool {
+        self.goals.contains(&(node as u32))
+    }
+
+    fn neighbors(&self, node: usize) -> Vec<(usize, usize)> {
+        self.edges(node)
+            .map(|edge| (self.targets[edge] as usize, self.weights[edge] as usize))
+            .collect()
+    }
+
+    fn for_each_neighbor(&self, node: usize, visit: &mut dyn FnMut(usize, usize)) {
+        for edge in self.edges(node) {
+            visit(self.targets[edge] as usize, self.weights[edge] as usize);
+        }
+    }
+
+    fn heuristic(&self, node: usize) -> usize {
+        self.heuristics[node] as usize
+    }
+
+    fn position(&self, node: usize) -> (usize, usize) {
+        let (row, col) = self.positions[node];
+        (row as usize, col as usize)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cell::Cell;
+    use crate::generators::generator::MazeGenerator;
+    use crate::generators::kruskal::KruskalMazeGenerator;
+    use crate::solvers::astar::AstarSolver;
+    use crate::solvers::bfs_solver::BfsSolver;
+    use crate::solvers::dfs_solver::DfsSolver;
+    use crate::solvers::dijkstra::DijkstraSolver;
+    use crate::solvers::solver::Solver;
+
+    #[test]
+    fn test_shared_graph_matches_maze() {
+        // every open cell of a perfect maze is reachable and gets one node
+        let perfect = KruskalMazeGenerator
+            .generate(41, 31, None, None, 0.0, None)
+            .unwrap();
+        let open = perfect
+            .cells
+            .iter()
+            .flatten()
+            .filter(|cell| cell.is_open())
+            .count();
+        assert_eq!(CsrGraph::from_maze(&perfect).node_count(), open);
+
+        let maze = KruskalMazeGenerator
+            .generate(41, 31, None, None, 15.0, None)
+            .unwrap();
+        let graph = CsrGraph::from_maze(&maze);
+        for solver in [
+            &AstarSolver as &dyn Solver,
+            &BfsSolver,
+            &DfsSolver,
+            &DijkstraSolver,
+        ] {
+            let shared = solver.search(&graph, None).positions(&graph);
+            let direct = solver.solve_with_result(&maze, None);
+            assert_eq!(shared.cost, direct.cost);
+            let path = shared.path.unwrap();
+            assert_eq!((path[0], path[path.len() - 1]), (maze.start, maze.end));
+        }
+    }
+
+    #[test]
+    fn test_doors_and_unreachable_end() {
+        let mut cells = vec![vec![Cell::Wall; 9]; 3];
+        cells[1][1..8].fill(Cell::Path);
+        cells[1][1] = Cell::Key(0);
+        cells[1][5] = Cell::Door(0);
+        let maze = Maze::new(cells, (1, 3), (1, 7));
+        let graph = CsrGraph::from_maze(&maze);
+        let path = BfsSolver.search(&graph, None).positions(&graph).path;
+        assert_eq!(path.map(|path| path.len()), Some(9));
+
+        let mut walled = maze.clone();
+        walled.cells[1][6] = Cell::Wall;
+        let graph = CsrGraph::from_maze(&walled);
+        assert!(AstarSolver.search(&graph, None).path.is_none());
+    }
+}
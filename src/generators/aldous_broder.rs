@@ -1,59 +1,51 @@
 use crate::cell::Cell;
-use crate::generators::generator::MazeGenerator;
+use crate::generators::generator::{
+    finish_maze, record_cell, BlockGrid, MazeGenerator, TopologyMazeGenerator,
+};
+use crate::linked_maze::Passages;
 use crate::maze::Maze;
 use crate::progress::ProgressTracker;
+use crate::topologies::topology::Topology;
+use rand::seq::IndexedRandom;
 use rand::Rng;
 
 pub struct AldousBroderMazeGenerator;
 
 impl MazeGenerator for AldousBroderMazeGenerator {
-    fn generate(
+    fn generate_on(
         &self,
-        height: usize,
-        width: usize,
+        grid: &BlockGrid,
         start: Option<(usize, usize)>,
         end: Option<(usize, usize)>,
         imperfect_percentage: f32,
         mut tracker: Option<&mut ProgressTracker>,
     ) -> Maze {
+        let (height, width) = (grid.height, grid.width);
         let mut cells = vec![vec![Cell::Wall; width]; height];
-        let mut rng = rand::thread_rng();
-        let mut unvisited = (height / 2) * (width / 2) - 1;
+        let mut rng = crate::random::rng();
 
-        let mut current_y = rng.gen_range(0..height / 2) * 2 + 1;
-        let mut current_x = rng.gen_range(0..width / 2) * 2 + 1;
+        let logical_cells = grid.logical_cells();
+        let mut unvisited = logical_cells.len() - 1;
+        let (mut current_y, mut current_x) = *logical_cells
+            .choose(&mut rng)
+            .expect("Grid must have at least one logical cell");
         cells[current_y][current_x] = Cell::Path;
         if let Some(ref mut t) = tracker {
             t.record(current_y, current_x, Cell::Path);
         }
 
         while unvisited > 0 {
-            let mut neighbors = Vec::new();
-            if current_y > 1 {
-                neighbors.push((current_y - 2, current_x));
-            }
-            if current_y < height - 2 {
-                neighbors.push((current_y + 2, current_x));
-            }
-            if current_x > 1 {
-                neighbors.push((current_y, current_x - 2));
-            }
-            if current_x < width - 2 {
-                neighbors.push((current_y, current_x + 2));
-            }
+            let neighbors = grid.logical_neighbors(current_y, current_x);
 
-            let (next_y, next_x) = neighbors[rng.gen_range(0..neighbors.len())];
+            let ((next_y, next_x), (between_y, between_x)) =
+                neighbors[rng.random_range(0..neighbors.len())];
 
             if cells[next_y][next_x] == Cell::Wall {
                 cells[next_y][next_x] = Cell::Path;
-                cells[(current_y + next_y) / 2][(current_x + next_x) / 2] = Cell::Path;
+                cells[between_y][between_x] = Cell::Path;
                 if let Some(ref mut t) = tracker {
                     t.record(next_y, next_x, Cell::Path);
-                    t.record(
-                        (current_y + next_y) / 2,
-                        (current_x + next_x) / 2,
-                        Cell::Path,
-                    );
+                    t.record(between_y, between_x, Cell::Path);
                 }
                 unvisited -= 1;
             }
@@ -61,22 +53,44 @@ impl MazeGenerator for AldousBroderMazeGenerator {
             current_x = next_x;
         }
 
-        let start_node = start.unwrap_or((1, 1));
-        let end_node = end.unwrap_or((height - 2, width - 2));
-        cells[start_node.0][start_node.1] = Cell::Path;
-        cells[end_node.0][end_node.1] = Cell::Path;
+        let start_node = start.unwrap_or(grid.default_start());
+        let end_node = end.unwrap_or(grid.default_end());
+
+        finish_maze(grid, cells, start_node, end_node, imperfect_percentage)
+    }
+}
+
+impl TopologyMazeGenerator for AldousBroderMazeGenerator {
+    fn carve(
+        &self,
+        topology: &dyn Topology,
+        mut tracker: Option<&mut ProgressTracker>,
+    ) -> Passages {
+        let cell_count = topology.cell_count();
+        let mut passages = Passages::new(cell_count);
+        let mut visited = vec![false; cell_count];
+        let mut rng = crate::random::rng();
 
-        let mut maze = Maze {
-            cells,
-            start: start_node,
-            end: end_node,
-        };
+        let mut current = rng.random_range(0..cell_count);
+        visited[current] = true;
+        record_cell(topology, &mut tracker, current);
+        let mut unvisited = cell_count - 1;
 
-        if imperfect_percentage > 0.0 {
-            maze.remove_walls(imperfect_percentage);
+        while unvisited > 0 {
+            let neighbors = topology.neighbors(current);
+            let Some(&next) = neighbors.choose(&mut rng) else {
+                break; // isolated cell, nothing left to walk to
+            };
+            if !visited[next] {
+                visited[next] = true;
+                passages.link(current, next);
+                record_cell(topology, &mut tracker, next);
+                unvisited -= 1;
+            }
+            current = next;
         }
 
-        maze
+        passages
     }
 }
 
@@ -84,15 +98,23 @@ impl MazeGenerator for AldousBroderMazeGenerator {
 mod tests {
     use super::*;
     use crate::cell::Cell;
-    use crate::generators::generator::MazeGenerator;
+    use crate::generators::generator::{assert_spanning_tree, MazeGenerator};
+    use crate::topologies::hex::HexTopology;
 
     #[test]
     fn test_generate_aldous_broder_maze() {
         let generator = AldousBroderMazeGenerator;
-        let maze = generator.generate(11, 11, None, None, 0.0, None);
+        let maze = generator.generate(11, 11, None, None, 0.0, None).unwrap();
         assert_eq!(maze.start, (1, 1));
         assert_eq!(maze.end, (9, 9));
         assert_eq!(maze.cells[maze.start.0][maze.start.1], Cell::Path);
         assert_eq!(maze.cells[maze.end.0][maze.end.1], Cell::Path);
     }
+
+    #[test]
+    fn test_carve_aldous_broder_hex_maze() {
+        let hex = HexTopology::new(6, 7);
+        let passages = AldousBroderMazeGenerator.carve(&hex, None);
+        assert_spanning_tree(&hex, &passages);
+    }
 }
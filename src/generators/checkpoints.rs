@@ -0,0 +1,114 @@
+use std::collections::VecDeque;
+
+use crate::cell::Cell;
+use crate::generators::generator::{BlockGrid, MazeGenerator};
+use crate::maze::Maze;
+use crate::maze_graph::MazeGraph;
+use crate::progress::ProgressTracker;
+use rand::seq::SliceRandom;
+
+/// Scatters checkpoints over mazes from another generator. Only cells reachable
+/// from the start that can still reach the end are used; without one-way cells
+/// that is enough for a tour through all of them to exist.
+pub struct CheckpointMazeGenerator<'a> {
+    pub base: &'a dyn MazeGenerator,
+    /// Number of checkpoints to place
+    pub count: usize,
+}
+
+impl<'a> CheckpointMazeGenerator<'a> {
+    pub fn new(base: &'a dyn MazeGenerator, count: usize) -> Self {
+        Self { base, count }
+    }
+}
+
+impl MazeGenerator for CheckpointMazeGenerator<'_> {
+    fn generate_on(
+        &self,
+        grid: &BlockGrid,
+        start: Option<(usize, usize)>,
+        end: Option<(usize, usize)>,
+        imperfect_percentage: f32,
+        mut tracker: Option<&mut ProgressTracker>,
+    ) -> Maze {
+        let mut maze = self.base.generate_on(
+            grid,
+            start,
+            end,
+            imperfect_percentage,
+            tracker.as_deref_mut(),
+        );
+
+        let node_count = maze.node_count();
+        let mut predecessors = vec![Vec::new(); node_count];
+        for node in 0..node_count {
+            for (next, _) in maze.neighbors(node) {
+                predecessors[next].push(node);
+            }
+        }
+        let from_start = reachable(maze.start_node(), node_count, |node| {
+            maze.neighbors(node)
+                .into_iter()
+                .map(|(next, _)| next)
+                .collect()
+        });
+        let to_end = reachable(maze.end_node(), node_count, |node| {
+            predecessors[node].clone()
+        });
+
+        let mut candidates: Vec<(usize, usize)> = (0..node_count)
+            .filter(|&node| from_start[node] && to_end[node])
+            .map(|node| maze.position(node))
+            .filter(|&pos| {
+                maze.cells[pos.0][pos.1] == Cell::Path && pos != maze.start && pos != maze.end
+            })
+            .collect();
+        candidates.shuffle(&mut crate::random::rng());
+
+        for &(row, col) in candidates.iter().take(self.count) {
+            maze.cells[row][col] = Cell::Checkpoint;
+            if let Some(ref mut t) = tracker {
+                t.record(row, col, Cell::Checkpoint);
+            }
+        }
+
+        maze
+    }
+}
+
+// nodes reachable from `source` following `next`
+fn reachable(source: usize, node_count: usize, next: impl Fn(usize) -> Vec<usize>) -> Vec<bool> {
+    let mut seen = vec![false; node_count];
+    seen[source] = true;
+    let mut queue = VecDeque::from([source]);
+    while let Some(node) = queue.pop_front() {
+        for neighbor in next(node) {
+            if !seen[neighbor] {
+                seen[neighbor] = true;
+                queue.push_back(neighbor);
+            }
+        }
+    }
+    seen
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generators::prims::PrimMazeGenerator;
+    use crate::solvers::astar::AstarSolver;
+    use crate::solvers::tour::solve_tour;
+
+    #[test]
+    fn test_checkpoints_can_all_be_toured() {
+        let maze = CheckpointMazeGenerator::new(&PrimMazeGenerator, 5)
+            .generate(21, 21, None, None, 10.0, None)
+            .unwrap();
+        assert_eq!(maze.checkpoints().len(), 5);
+
+        let tour = solve_tour(&maze, &AstarSolver, None).expect("Tour must exist");
+        for checkpoint in maze.checkpoints() {
+            assert!(tour.path.contains(&checkpoint));
+        }
+    }
+}
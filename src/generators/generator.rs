@@ -1,7 +1,182 @@
-use crate::maze::Maze;
+use crate::cell::Cell;
+use crate::linked_maze::Passages;
+use crate::mask::Mask;
+use crate::maze::{Maze, MazeError, Wrap};
 use crate::progress::ProgressTracker;
+use crate::topologies::topology::Topology;
+use std::collections::HashMap;
+
+/// Block grid a `MazeGenerator` carves into. Logical cells sit on odd
+/// coordinates and the cells between them are walls or passages. A wrapping
+/// axis should have even length so the wall between the last and the first
+/// logical cell is the border cell at index 0. An optional mask restricts
+/// carving to the logical cells it enables.
+#[derive(Debug, Clone)]
+pub struct BlockGrid {
+    pub height: usize,
+    pub width: usize,
+    pub wrap: Wrap,
+    pub mask: Option<Mask>,
+}
+
+impl BlockGrid {
+    /// Plain grid of the given size; either side shorter than 3 leaves no room
+    /// for a logical cell and is rejected.
+    pub fn new(height: usize, width: usize) -> Result<Self, MazeError> {
+        if height < 3 || width < 3 {
+            return Err(MazeError::InvalidDimensions);
+        }
+        Ok(Self {
+            height,
+            width,
+            wrap: Wrap::None,
+            mask: None,
+        })
+    }
+
+    /// Grid sized to hold one logical cell per mask cell
+    pub fn masked(mask: Mask) -> Self {
+        Self {
+            height: 2 * mask.rows() + 1,
+            width: 2 * mask.cols() + 1,
+            wrap: Wrap::None,
+            mask: Some(mask),
+        }
+    }
+
+    pub fn with_wrap(mut self, wrap: Wrap) -> Self {
+        self.wrap = wrap;
+        self
+    }
+
+    pub fn logical_cells(&self) -> Vec<(usize, usize)> {
+        let mut cells = Vec::new();
+        for r in (1..self.height).step_by(2) {
+            for c in (1..self.width).step_by(2) {
+                if self.is_enabled(r, c) {
+                    cells.push((r, c));
+                }
+            }
+        }
+        cells
+    }
+
+    // whether the logical cell at block position (y, x) may be carved
+    fn is_enabled(&self, y: usize, x: usize) -> bool {
+        self.mask
+            .as_ref()
+            .is_none_or(|mask| mask.is_enabled(y / 2, x / 2))
+    }
+
+    /// Whether a block cell lies inside the mask's shape: every logical cell it
+    /// touches along a row or column is enabled.
+    pub fn contains(&self, y: usize, x: usize) -> bool {
+        if y >= self.height || x >= self.width {
+            return false;
+        }
+        let touched = |pos: usize, len: usize| -> Vec<usize> {
+            if pos % 2 == 1 {
+                vec![pos]
+            } else {
+                [pos.wrapping_sub(1), pos + 1]
+                    .into_iter()
+                    .filter(|&p| p < len)
+                    .collect()
+            }
+        };
+        let rows = touched(y, self.height);
+        let cols = touched(x, self.width);
+        !rows.is_empty()
+            && !cols.is_empty()
+            && rows
+                .iter()
+                .all(|&r| cols.iter().all(|&c| self.is_enabled(r, c)))
+    }
+
+    /// Whether a block cell is outside the shape without even bordering it, so it
+    /// is left out of the maze altogether rather than drawn as a wall.
+    pub fn is_void(&self, y: usize, x: usize) -> bool {
+        self.mask.is_some()
+            && !(y.saturating_sub(1)..=y + 1).any(|r| {
+                (x.saturating_sub(1)..=x + 1).any(|c| {
+                    r % 2 == 1
+                        && c % 2 == 1
+                        && r < self.height
+                        && c < self.width
+                        && self.is_enabled(r, c)
+                })
+            })
+    }
+
+    /// Checks that a start or end cell lies inside the mask.
+    pub fn check_inside(&self, pos: (usize, usize)) -> Result<(), MazeError> {
+        if self.contains(pos.0, pos.1) {
+            Ok(())
+        } else {
+            Err(MazeError::OutsideMask(pos.0, pos.1))
+        }
+    }
+
+    /// Logical cells two steps away from (y, x), each paired with the cell between them.
+    pub fn logical_neighbors(&self, y: usize, x: usize) -> Vec<((usize, usize), (usize, usize))> {
+        let mut neighbors = Vec::new();
+        for (dy, dx) in [(-2isize, 0isize), (2, 0), (0, -2), (0, 2)] {
+            let row = step(y, dy, self.height, self.wrap.vertical());
+            let col = step(x, dx, self.width, self.wrap.horizontal());
+            if let (Some((ny, wy)), Some((nx, wx))) = (row, col) {
+                if (ny, nx) != (y, x) && self.is_enabled(ny, nx) {
+                    neighbors.push(((ny, nx), (wy, wx)));
+                }
+            }
+        }
+        neighbors
+    }
+
+    /// First logical cell, the default start of a generated maze.
+    pub fn default_start(&self) -> (usize, usize) {
+        self.logical_cells().first().copied().unwrap_or((1, 1))
+    }
+
+    /// Last logical cell, the default end of a generated maze. Grids made by
+    /// `new` and `masked` are large enough to have one.
+    pub fn default_end(&self) -> (usize, usize) {
+        if self.mask.is_some() {
+            return self.logical_cells().last().copied().unwrap_or((1, 1));
+        }
+        let last = |len: usize, wraps: bool| if wraps { len - 1 } else { len - 2 };
+        (
+            last(self.height, self.wrap.vertical()),
+            last(self.width, self.wrap.horizontal()),
+        )
+    }
+}
+
+// moves `delta` along an axis, returning the target and the cell halfway there
+fn step(pos: usize, delta: isize, len: usize, wraps: bool) -> Option<(usize, usize)> {
+    let target = pos as isize + delta;
+    let between = pos as isize + delta / 2;
+    if wraps {
+        Some((
+            target.rem_euclid(len as isize) as usize,
+            between.rem_euclid(len as isize) as usize,
+        ))
+    } else if target > 0 && target < len as isize {
+        Some((target as usize, between as usize))
+    } else {
+        None
+    }
+}
 
 pub trait MazeGenerator {
+    fn generate_on(
+        &self,
+        grid: &BlockGrid,
+        start: Option<(usize, usize)>,
+        end: Option<(usize, usize)>,
+        imperfect_percentage: f32,
+        tracker: Option<&mut ProgressTracker>,
+    ) -> Maze;
+
     fn generate(
         &self,
         height: usize,
@@ -10,5 +185,112 @@ pub trait MazeGenerator {
         end: Option<(usize, usize)>,
         imperfect_percentage: f32,
         tracker: Option<&mut ProgressTracker>,
-    ) -> Maze;
+    ) -> Result<Maze, MazeError> {
+        Ok(self.generate_on(
+            &BlockGrid::new(height, width)?,
+            start,
+            end,
+            imperfect_percentage,
+            tracker,
+        ))
+    }
+}
+
+/// Turns carved cells into a `Maze`: opens start and end, leaves cells outside
+/// the grid's mask out as `Cell::Void` and knocks down walls for imperfect mazes.
+pub(crate) fn finish_maze(
+    grid: &BlockGrid,
+    mut cells: Vec<Vec<Cell>>,
+    start: (usize, usize),
+    end: (usize, usize),
+    imperfect_percentage: f32,
+) -> Maze {
+    cells[start.0][start.1] = Cell::Path;
+    cells[end.0][end.1] = Cell::Path;
+    if grid.mask.is_some() {
+        for (y, row) in cells.iter_mut().enumerate() {
+            for (x, cell) in row.iter_mut().enumerate() {
+                if grid.is_void(y, x) {
+                    *cell = Cell::Void;
+                }
+            }
+        }
+    }
+
+    let mut maze = Maze {
+        cells,
+        start,
+        end,
+        wrap: grid.wrap,
+        portals: HashMap::new(),
+    };
+
+    if imperfect_percentage > 0.0 {
+        maze.remove_walls(imperfect_percentage);
+    }
+
+    maze
+}
+
+/// Generator able to carve a perfect maze on any `Topology`.
+pub trait TopologyMazeGenerator {
+    fn carve(&self, topology: &dyn Topology, tracker: Option<&mut ProgressTracker>) -> Passages;
+}
+
+/// Records a newly carved cell of a topology in the progress tracker.
+pub(crate) fn record_cell(
+    topology: &dyn Topology,
+    tracker: &mut Option<&mut ProgressTracker>,
+    cell: usize,
+) {
+    if let Some(t) = tracker {
+        let (row, col) = topology.position(cell);
+        t.record(row, col, Cell::Path);
+    }
+}
+
+/// Asserts that carved passages form a spanning tree over the topology.
+#[cfg(test)]
+pub(crate) fn assert_spanning_tree(topology: &dyn Topology, passages: &Passages) {
+    let cell_count = topology.cell_count();
+    let edges: usize = (0..cell_count)
+        .map(|c| passages.links(c).len())
+        .sum::<usize>()
+        / 2;
+    assert_eq!(edges, cell_count - 1, "Spanning tree must have n - 1 edges");
+
+    let mut visited = vec![false; cell_count];
+    let mut stack = vec![0];
+    visited[0] = true;
+    while let Some(cell) = stack.pop() {
+        for &n in passages.links(cell) {
+            assert!(
+                topology.neighbors(cell).contains(&n),
+                "Passage between non-adjacent cells"
+            );
+            if !visited[n] {
+                visited[n] = true;
+                stack.push(n);
+            }
+        }
+    }
+    assert!(visited.iter().all(|&v| v), "All cells must be reachable");
+}
+
+#[cfg(test)]
+pub(crate) fn assert_perfect_block_maze(maze: &Maze) {
+    let graph = maze.to_graph();
+    let edges: usize = graph.values().map(|adj| adj.len()).sum::<usize>() / 2;
+    assert_eq!(edges, graph.len() - 1, "Perfect maze must be a tree");
+
+    let mut visited = std::collections::HashSet::from([maze.start]);
+    let mut stack = vec![maze.start];
+    while let Some(pos) = stack.pop() {
+        for &(next, _) in &graph[&pos] {
+            if visited.insert(next) {
+                stack.push(next);
+            }
+        }
+    }
+    assert_eq!(visited.len(), graph.len(), "All cells must be reachable");
 }
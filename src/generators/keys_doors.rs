@@ -0,0 +1,213 @@
+use std::collections::{HashSet, VecDeque};
+
+use crate::cell::{Cell, KEY_COLORS};
+use crate::generators::generator::{BlockGrid, MazeGenerator};
+use crate::maze::Maze;
+use crate::maze_graph::MazeGraph;
+use crate::progress::ProgressTracker;
+use crate::solvers::bfs_solver::BfsSolver;
+use crate::solvers::solver::Solver;
+use rand::seq::SliceRandom;
+
+/// Turns mazes from another generator into keys-and-doors puzzles: doors are
+/// spread along the solution path and each door's key is hidden in a side
+/// branch reachable from the previous door without passing that door or any
+/// later one, and from which the door can be reached again, so one-way cells
+/// never trap a player holding a key.
+pub struct KeyDoorMazeGenerator<'a> {
+    pub base: &'a dyn MazeGenerator,
+    /// Number of doors to place, at most `KEY_COLORS`
+    pub doors: usize,
+}
+
+impl<'a> KeyDoorMazeGenerator<'a> {
+    pub fn new(base: &'a dyn MazeGenerator, doors: usize) -> Self {
+        Self { base, doors }
+    }
+}
+
+impl MazeGenerator for KeyDoorMazeGenerator<'_> {
+    fn generate_on(
+        &self,
+        grid: &BlockGrid,
+        start: Option<(usize, usize)>,
+        end: Option<(usize, usize)>,
+        imperfect_percentage: f32,
+        mut tracker: Option<&mut ProgressTracker>,
+    ) -> Maze {
+        let mut maze = self.base.generate_on(
+            grid,
+            start,
+            end,
+            imperfect_percentage,
+            tracker.as_deref_mut(),
+        );
+        let Some(path) = BfsSolver.solve(&maze, None) else {
+            return maze;
+        };
+        let on_path: HashSet<(usize, usize)> = path.iter().copied().collect();
+        let mut rng = crate::random::rng();
+
+        let doors = self.doors.min(KEY_COLORS as usize);
+        let mut next_index = 1;
+        // where a player holding every key placed so far stands
+        let mut origin = maze.start_node();
+        for color in 0..doors as u8 {
+            let target = path.len() * (color as usize + 1) / (doors + 1);
+            let mut placed = false;
+            let first = target.max(next_index);
+            let last = path.len().saturating_sub(1);
+            for (index, &(row, col)) in path.iter().enumerate().take(last).skip(first) {
+                if maze.cells[row][col] != Cell::Path {
+                    continue;
+                }
+                maze.cells[row][col] = Cell::Door(color);
+                let door = maze.node_index(row, col);
+                let back_to_door = reachable_cells(&maze, door, true, |other| other <= color);
+                let mut hiding_spots: Vec<_> =
+                    reachable_cells(&maze, origin, false, |other| other < color)
+                        .into_iter()
+                        .filter(|&(y, x)| {
+                            maze.cells[y][x] == Cell::Path
+                                && !on_path.contains(&(y, x))
+                                && (y, x) != maze.end
+                                && back_to_door.contains(&(y, x))
+                        })
+                        .collect();
+                // dead ends first, each group in random order
+                hiding_spots.shuffle(&mut rng);
+                hiding_spots.sort_by_key(|&(y, x)| open_neighbors(&maze, y, x) != 1);
+                // the door may cut a route an earlier key relied on
+                let choice = hiding_spots.into_iter().find(|&(key_row, key_col)| {
+                    maze.cells[key_row][key_col] = Cell::Key(color);
+                    let solvable = BfsSolver.solve(&maze, None).is_some();
+                    maze.cells[key_row][key_col] = Cell::Path;
+                    solvable
+                });
+                if let Some((key_row, key_col)) = choice {
+                    maze.cells[key_row][key_col] = Cell::Key(color);
+                    if let Some(ref mut t) = tracker {
+                        t.record(row, col, Cell::Door(color));
+                        t.record(key_row, key_col, Cell::Key(color));
+                    }
+                    next_index = index + 1;
+                    origin = door;
+                    placed = true;
+                    break;
+                }
+                maze.cells[row][col] = Cell::Path;
+            }
+            if !placed {
+                break;
+            }
+        }
+
+        maze
+    }
+}
+
+// cells reachable from `source` passing only the doors `opens` accepts, or
+// with `reverse` the cells from which `source` is reachable that way
+fn reachable_cells(
+    maze: &Maze,
+    source: usize,
+    reverse: bool,
+    opens: impl Fn(u8) -> bool,
+) -> HashSet<(usize, usize)> {
+    let passable = |cell| match cell {
+        Cell::Door(door) => opens(door),
+        cell => cell.is_open(),
+    };
+    let mut predecessors = vec![Vec::new(); maze.node_count()];
+    if reverse {
+        for node in 0..maze.node_count() {
+            for (next, _) in maze.graph_neighbors(node, passable) {
+                predecessors[next].push(node);
+            }
+        }
+    }
+
+    let mut seen = HashSet::from([source]);
+    let mut queue = VecDeque::from([source]);
+    let mut cells = HashSet::new();
+    while let Some(node) = queue.pop_front() {
+        cells.insert(MazeGraph::position(maze, node));
+        let next_nodes = if reverse {
+            predecessors[node].clone()
+        } else {
+            maze.graph_neighbors(node, passable)
+                .into_iter()
+                .map(|(next, _)| next)
+                .collect()
+        };
+        for next in next_nodes {
+            if seen.insert(next) {
+                queue.push_back(next);
+            }
+        }
+    }
+    cells
+}
+
+fn open_neighbors(maze: &Maze, row: usize, col: usize) -> usize {
+    Maze::get_wrapped_neighbors(row, col, maze.cells.len(), maze.cells[0].len(), maze.wrap)
+        .into_iter()
+        .filter(|&(n_row, n_col)| !matches!(maze.cells[n_row][n_col], Cell::Wall | Cell::Void))
+        .count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generators::one_way::OneWayMazeGenerator;
+    use crate::generators::recursive_backtracker::RecursiveBacktrackerMazeGenerator;
+    use crate::solvers::astar::AstarSolver;
+
+    #[test]
+    fn test_keys_are_collected_before_doors() {
+        let generator = KeyDoorMazeGenerator::new(&RecursiveBacktrackerMazeGenerator, 3);
+        let maze = generator.generate(31, 31, None, None, 0.0, None).unwrap();
+        let count = |wanted: fn(Cell) -> bool| {
+            maze.cells
+                .iter()
+                .flatten()
+                .filter(|&&cell| wanted(cell))
+                .count()
+        };
+        let doors = count(|cell| matches!(cell, Cell::Door(_)));
+        assert!(doors > 0);
+        assert_eq!(doors, count(|cell| matches!(cell, Cell::Key(_))));
+
+        for solver in [&AstarSolver as &dyn Solver, &BfsSolver] {
+            let path = solver.solve(&maze, None).expect("Puzzle must be solvable");
+            assert_eq!(path.last(), Some(&maze.end));
+            let mut held = Vec::new();
+            for &(row, col) in &path {
+                match maze.cells[row][col] {
+                    Cell::Key(color) => held.push(color),
+                    Cell::Door(color) => assert!(held.contains(&color), "Door passed without key"),
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_doors_over_one_way_cells_stay_solvable() {
+        let one_way = OneWayMazeGenerator::new(&RecursiveBacktrackerMazeGenerator, 1.0);
+        let generator = KeyDoorMazeGenerator::new(&one_way, 3);
+        for _ in 0..20 {
+            let maze = generator.generate(21, 21, None, None, 20.0, None).unwrap();
+            assert!(maze
+                .cells
+                .iter()
+                .flatten()
+                .any(|cell| matches!(cell, Cell::Door(_))));
+            assert!(
+                BfsSolver.solve(&maze, None).is_some(),
+                "Puzzle must be solvable:\n{}",
+                maze
+            );
+        }
+    }
+}
@@ -1,23 +1,27 @@
 use crate::cell::Cell;
-use crate::generators::generator::MazeGenerator;
+use crate::generators::generator::{
+    finish_maze, record_cell, BlockGrid, MazeGenerator, TopologyMazeGenerator,
+};
+use crate::linked_maze::Passages;
 use crate::maze::Maze;
 use crate::progress::ProgressTracker;
+use crate::topologies::topology::Topology;
 use rand::seq::SliceRandom;
 
 pub struct KruskalMazeGenerator;
 
-struct DisjointSet {
+pub(crate) struct DisjointSet {
     parent: Vec<usize>,
 }
 
 impl DisjointSet {
-    fn new(size: usize) -> Self {
+    pub(crate) fn new(size: usize) -> Self {
         Self {
             parent: (0..size).collect(),
         }
     }
 
-    fn find(&mut self, i: usize) -> usize {
+    pub(crate) fn find(&mut self, i: usize) -> usize {
         if self.parent[i] == i {
             return i;
         }
@@ -25,7 +29,7 @@ impl DisjointSet {
         self.parent[i]
     }
 
-    fn union(&mut self, i: usize, j: usize) {
+    pub(crate) fn union(&mut self, i: usize, j: usize) {
         let root_i = self.find(i);
         let root_j = self.find(j);
         if root_i != root_j {
@@ -35,70 +39,89 @@ impl DisjointSet {
 }
 
 impl MazeGenerator for KruskalMazeGenerator {
-    fn generate(
+    fn generate_on(
         &self,
-        height: usize,
-        width: usize,
+        grid: &BlockGrid,
         start: Option<(usize, usize)>,
         end: Option<(usize, usize)>,
         imperfect_percentage: f32,
         mut tracker: Option<&mut ProgressTracker>,
     ) -> Maze {
+        let (height, width) = (grid.height, grid.width);
         let mut cells = vec![vec![Cell::Wall; width]; height];
-        let mut rng = rand::thread_rng();
+        let mut rng = crate::random::rng();
         let mut walls = Vec::new();
 
-        for r in (1..height).step_by(2) {
-            for c in (1..width).step_by(2) {
-                cells[r][c] = Cell::Path;
-                if r + 2 < height {
-                    walls.push((r, c, r + 2, c));
-                }
-                if c + 2 < width {
-                    walls.push((r, c, r, c + 2));
+        // Each wall (r1, c1, r2, c2, wall_r, wall_c) is listed once, from the cell above or left of it
+        let logical_cells = grid.logical_cells();
+        for &(r, c) in &logical_cells {
+            cells[r][c] = Cell::Path;
+            for ((nr, nc), (wr, wc)) in grid.logical_neighbors(r, c) {
+                if (wr, wc) == ((r + 1) % height, c) || (wr, wc) == (r, (c + 1) % width) {
+                    walls.push((r, c, nr, nc, wr, wc));
                 }
             }
         }
 
         if let Some(ref mut t) = tracker {
-            for r in (1..height).step_by(2) {
-                for c in (1..width).step_by(2) {
-                    t.record(r, c, Cell::Path);
-                }
+            for &(r, c) in &logical_cells {
+                t.record(r, c, Cell::Path);
             }
         }
 
         let mut dset = DisjointSet::new(height * width);
         walls.shuffle(&mut rng);
 
-        for (r1, c1, r2, c2) in walls {
+        for (r1, c1, r2, c2, wr, wc) in walls {
             let idx1 = r1 * width + c1;
             let idx2 = r2 * width + c2;
             if dset.find(idx1) != dset.find(idx2) {
                 dset.union(idx1, idx2);
-                cells[(r1 + r2) / 2][(c1 + c2) / 2] = Cell::Path;
+                cells[wr][wc] = Cell::Path;
                 if let Some(ref mut t) = tracker {
-                    t.record((r1 + r2) / 2, (c1 + c2) / 2, Cell::Path);
+                    t.record(wr, wc, Cell::Path);
                 }
             }
         }
 
-        let start_node = start.unwrap_or((1, 1));
-        let end_node = end.unwrap_or((height - 2, width - 2));
-        cells[start_node.0][start_node.1] = Cell::Path;
-        cells[end_node.0][end_node.1] = Cell::Path;
+        let start_node = start.unwrap_or(grid.default_start());
+        let end_node = end.unwrap_or(grid.default_end());
+
+        finish_maze(grid, cells, start_node, end_node, imperfect_percentage)
+    }
+}
+
+impl TopologyMazeGenerator for KruskalMazeGenerator {
+    fn carve(
+        &self,
+        topology: &dyn Topology,
+        mut tracker: Option<&mut ProgressTracker>,
+    ) -> Passages {
+        let cell_count = topology.cell_count();
+        let mut passages = Passages::new(cell_count);
+        let mut rng = crate::random::rng();
 
-        let mut maze = Maze {
-            cells,
-            start: start_node,
-            end: end_node,
-        };
+        let mut walls = Vec::new();
+        for cell in 0..cell_count {
+            record_cell(topology, &mut tracker, cell);
+            for neighbor in topology.neighbors(cell) {
+                if neighbor > cell {
+                    walls.push((cell, neighbor));
+                }
+            }
+        }
+        walls.shuffle(&mut rng);
 
-        if imperfect_percentage > 0.0 {
-            maze.remove_walls(imperfect_percentage);
+        let mut dset = DisjointSet::new(cell_count);
+        for (a, b) in walls {
+            if dset.find(a) != dset.find(b) {
+                dset.union(a, b);
+                passages.link(a, b);
+                record_cell(topology, &mut tracker, b);
+            }
         }
 
-        maze
+        passages
     }
 }
 
@@ -106,15 +129,35 @@ impl MazeGenerator for KruskalMazeGenerator {
 mod tests {
     use super::*;
     use crate::cell::Cell;
-    use crate::generators::generator::MazeGenerator;
+    use crate::generators::generator::{
+        assert_perfect_block_maze, assert_spanning_tree, BlockGrid, MazeGenerator,
+    };
+    use crate::maze::Wrap;
+    use crate::topologies::hex::HexTopology;
 
     #[test]
     fn test_generate_kruskal_maze() {
         let generator = KruskalMazeGenerator;
-        let maze = generator.generate(11, 11, None, None, 0.0, None);
+        let maze = generator.generate(11, 11, None, None, 0.0, None).unwrap();
         assert_eq!(maze.start, (1, 1));
         assert_eq!(maze.end, (9, 9));
         assert_eq!(maze.cells[maze.start.0][maze.start.1], Cell::Path);
         assert_eq!(maze.cells[maze.end.0][maze.end.1], Cell::Path);
     }
+
+    #[test]
+    fn test_carve_kruskal_hex_maze() {
+        let hex = HexTopology::new(6, 7);
+        let passages = KruskalMazeGenerator.carve(&hex, None);
+        assert_spanning_tree(&hex, &passages);
+    }
+
+    #[test]
+    fn test_generate_kruskal_torus_maze() {
+        let grid = BlockGrid::new(10, 12).unwrap().with_wrap(Wrap::Torus);
+        let maze = KruskalMazeGenerator.generate_on(&grid, None, None, 0.0, None);
+        assert_eq!(maze.wrap, Wrap::Torus);
+        assert_eq!(maze.end, grid.default_end());
+        assert_perfect_block_maze(&maze);
+    }
 }
@@ -1,5 +1,11 @@
 pub mod aldous_broder;
+pub mod checkpoints;
 pub mod generator;
+pub mod keys_doors;
 pub mod kruskal;
+pub mod one_way;
 pub mod prims;
 pub mod recursive_backtracker;
+pub mod tiled;
+pub mod weave;
+pub mod wilson;
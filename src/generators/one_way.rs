@@ -0,0 +1,127 @@
+use std::collections::HashMap;
+
+use crate::cell::{Cell, Direction};
+use crate::generators::generator::{BlockGrid, MazeGenerator};
+use crate::maze::Maze;
+use crate::progress::ProgressTracker;
+use crate::solvers::bfs_solver::BfsSolver;
+use crate::solvers::solver::Solver;
+use rand::seq::{IndexedRandom, SliceRandom};
+
+/// Turns mazes from another generator into mazes with one-way passages: a
+/// fraction of the straight corridor cells only let you through in one
+/// direction. Cells on a shortest solution path point the way it runs, so the
+/// end stays reachable from the start.
+pub struct OneWayMazeGenerator<'a> {
+    pub base: &'a dyn MazeGenerator,
+    /// Fraction of straight corridor cells (0.0 to 1.0) made one-way
+    pub density: f32,
+}
+
+impl<'a> OneWayMazeGenerator<'a> {
+    pub fn new(base: &'a dyn MazeGenerator, density: f32) -> Self {
+        Self { base, density }
+    }
+}
+
+impl MazeGenerator for OneWayMazeGenerator<'_> {
+    fn generate_on(
+        &self,
+        grid: &BlockGrid,
+        start: Option<(usize, usize)>,
+        end: Option<(usize, usize)>,
+        imperfect_percentage: f32,
+        mut tracker: Option<&mut ProgressTracker>,
+    ) -> Maze {
+        let mut maze = self.base.generate_on(
+            grid,
+            start,
+            end,
+            imperfect_percentage,
+            tracker.as_deref_mut(),
+        );
+        let Some(path) = BfsSolver.solve(&maze, None) else {
+            return maze;
+        };
+        let path_directions: HashMap<(usize, usize), Direction> = path
+            .windows(2)
+            .map(|step| (step[1], Direction::of_step(step[0], step[1])))
+            .collect();
+
+        let mut corridors: Vec<_> = (0..maze.cells.len())
+            .flat_map(|row| (0..maze.cells[0].len()).map(move |col| (row, col)))
+            .filter_map(|pos| corridor_direction(&maze, pos).map(|direction| (pos, direction)))
+            .collect();
+        let mut rng = crate::random::rng();
+        corridors.shuffle(&mut rng);
+        let count = (corridors.len() as f32 * self.density.clamp(0.0, 1.0)) as usize;
+
+        for &((row, col), direction) in corridors.iter().take(count) {
+            let direction = match path_directions.get(&(row, col)) {
+                Some(&along_path) => along_path,
+                None => *[direction, direction.opposite()].choose(&mut rng).unwrap(),
+            };
+            maze.cells[row][col] = Cell::OneWay(direction);
+            if let Some(ref mut t) = tracker {
+                t.record(row, col, Cell::OneWay(direction));
+            }
+        }
+
+        maze
+    }
+}
+
+// for a plain cell with exactly two open neighbours on opposite sides, the
+// direction towards one of them
+fn corridor_direction(maze: &Maze, (row, col): (usize, usize)) -> Option<Direction> {
+    if maze.cells[row][col] != Cell::Path || (row, col) == maze.start || (row, col) == maze.end {
+        return None;
+    }
+    let open: Vec<_> =
+        Maze::get_wrapped_neighbors(row, col, maze.cells.len(), maze.cells[0].len(), maze.wrap)
+            .into_iter()
+            .filter(|&(n_row, n_col)| !matches!(maze.cells[n_row][n_col], Cell::Wall | Cell::Void))
+            .map(|next| Direction::of_step((row, col), next))
+            .collect();
+    match open[..] {
+        [first, second] if first == second.opposite() => Some(first),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generators::recursive_backtracker::RecursiveBacktrackerMazeGenerator;
+    use crate::solvers::astar::AstarSolver;
+    use crate::solvers::dfs_solver::DfsSolver;
+    use crate::solvers::dijkstra::DijkstraSolver;
+
+    #[test]
+    fn test_one_way_maze_stays_solvable() {
+        let generator = OneWayMazeGenerator::new(&RecursiveBacktrackerMazeGenerator, 1.0);
+        let maze = generator.generate(31, 31, None, None, 10.0, None).unwrap();
+        assert!(maze
+            .cells
+            .iter()
+            .flatten()
+            .any(|cell| matches!(cell, Cell::OneWay(_))));
+
+        for solver in [
+            &AstarSolver as &dyn Solver,
+            &BfsSolver,
+            &DfsSolver,
+            &DijkstraSolver,
+        ] {
+            let path = solver.solve(&maze, None).expect("End must stay reachable");
+            assert_eq!(path.last(), Some(&maze.end));
+            for step in path.windows(2) {
+                assert!(
+                    maze.allows_step(step[0], step[1]),
+                    "Step {:?} goes against a one-way cell",
+                    step
+                );
+            }
+        }
+    }
+}
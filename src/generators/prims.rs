@@ -1,53 +1,53 @@
 use crate::cell::Cell;
-use crate::generators::generator::MazeGenerator;
+use crate::generators::generator::{
+    finish_maze, record_cell, BlockGrid, MazeGenerator, TopologyMazeGenerator,
+};
+use crate::linked_maze::Passages;
 use crate::maze::Maze;
 use crate::progress::ProgressTracker;
+use crate::topologies::topology::Topology;
+use rand::seq::IndexedRandom;
 use rand::Rng;
 
 /// Generates a maze using Prim's algorithm.
 pub struct PrimMazeGenerator;
 
 impl MazeGenerator for PrimMazeGenerator {
-    fn generate(
+    fn generate_on(
         &self,
-        height: usize,
-        width: usize,
+        grid: &BlockGrid,
         start: Option<(usize, usize)>,
         end: Option<(usize, usize)>,
         imperfect_percentage: f32,
         mut tracker: Option<&mut ProgressTracker>,
     ) -> Maze {
-        let mut rng = rand::thread_rng();
+        let (height, width) = (grid.height, grid.width);
+        let mut rng = crate::random::rng();
         let mut cells = vec![vec![Cell::Wall; width]; height];
 
-        // List of walls. Each wall is (y, x, parent_y, parent_x)
+        // List of walls. Each wall is (y, x, between_y, between_x), where
+        // (between_y, between_x) connects (y, x) to an already carved cell
         let mut walls = Vec::new();
 
-        // Start at a random odd position
-        let start_pos = (
-            rng.gen_range(1..height / 2) * 2 + 1,
-            rng.gen_range(1..width / 2) * 2 + 1,
-        );
+        // Start at a random logical cell
+        let start_pos = *grid
+            .logical_cells()
+            .choose(&mut rng)
+            .expect("Grid must have at least one logical cell");
         cells[start_pos.0][start_pos.1] = Cell::Path;
         if let Some(ref mut t) = tracker {
             t.record(start_pos.0, start_pos.1, Cell::Path);
         }
 
-        for (dy, dx) in [(-2i32, 0), (2, 0), (0, -2), (0, 2)] {
-            let ny = start_pos.0 as i32 + dy;
-            let nx = start_pos.1 as i32 + dx;
-            if ny > 0 && ny < height as i32 && nx > 0 && nx < width as i32 {
-                walls.push((ny as usize, nx as usize, start_pos.0, start_pos.1));
-            }
+        for ((ny, nx), (by, bx)) in grid.logical_neighbors(start_pos.0, start_pos.1) {
+            walls.push((ny, nx, by, bx));
         }
 
         while !walls.is_empty() {
-            let idx = rng.gen_range(0..walls.len());
-            let (wy, wx, py, px) = walls.swap_remove(idx);
+            let idx = rng.random_range(0..walls.len());
+            let (wy, wx, between_y, between_x) = walls.swap_remove(idx);
             if cells[wy][wx] == Cell::Wall {
-                // Find the cell on the opposite side
-                let between_y = (wy + py) / 2;
-                let between_x = (wx + px) / 2;
+                // Check the cell connecting it to the maze
                 if cells[between_y][between_x] == Cell::Wall {
                     cells[wy][wx] = Cell::Path;
                     cells[between_y][between_x] = Cell::Path;
@@ -56,13 +56,9 @@ impl MazeGenerator for PrimMazeGenerator {
                         t.record(between_y, between_x, Cell::Path);
                     }
                     // Add neighboring walls
-                    for (dy, dx) in [(-2i32, 0), (2, 0), (0, -2), (0, 2)] {
-                        let ny = wy as i32 + dy;
-                        let nx = wx as i32 + dx;
-                        if ny > 0 && ny < height as i32 && nx > 0 && nx < width as i32 {
-                            if cells[ny as usize][nx as usize] == Cell::Wall {
-                                walls.push((ny as usize, nx as usize, wy, wx));
-                            }
+                    for ((ny, nx), (by, bx)) in grid.logical_neighbors(wy, wx) {
+                        if cells[ny][nx] == Cell::Wall {
+                            walls.push((ny, nx, by, bx));
                         }
                     }
                 }
@@ -70,18 +66,51 @@ impl MazeGenerator for PrimMazeGenerator {
         }
 
         // Set start and end
-        let start = start.unwrap_or((1, 1));
-        let end = end.unwrap_or((height - 2, width - 2));
-        cells[start.0][start.1] = Cell::Path;
-        cells[end.0][end.1] = Cell::Path;
+        let start = start.unwrap_or(grid.default_start());
+        let end = end.unwrap_or(grid.default_end());
+
+        finish_maze(grid, cells, start, end, imperfect_percentage)
+    }
+}
+
+impl TopologyMazeGenerator for PrimMazeGenerator {
+    fn carve(
+        &self,
+        topology: &dyn Topology,
+        mut tracker: Option<&mut ProgressTracker>,
+    ) -> Passages {
+        let cell_count = topology.cell_count();
+        let mut passages = Passages::new(cell_count);
+        let mut in_maze = vec![false; cell_count];
+        let mut rng = crate::random::rng();
 
-        let mut maze = Maze { cells, start, end };
+        // Frontier of (cell, parent) candidates
+        let start_cell = rng.random_range(0..cell_count);
+        in_maze[start_cell] = true;
+        record_cell(topology, &mut tracker, start_cell);
+        let mut frontier: Vec<(usize, usize)> = topology
+            .neighbors(start_cell)
+            .into_iter()
+            .map(|n| (n, start_cell))
+            .collect();
 
-        if imperfect_percentage > 0.0 {
-            maze.remove_walls(imperfect_percentage);
+        while !frontier.is_empty() {
+            let idx = rng.random_range(0..frontier.len());
+            let (cell, parent) = frontier.swap_remove(idx);
+            if in_maze[cell] {
+                continue;
+            }
+            in_maze[cell] = true;
+            passages.link(parent, cell);
+            record_cell(topology, &mut tracker, cell);
+            for neighbor in topology.neighbors(cell) {
+                if !in_maze[neighbor] {
+                    frontier.push((neighbor, cell));
+                }
+            }
         }
 
-        maze
+        passages
     }
 }
 
@@ -89,13 +118,14 @@ impl MazeGenerator for PrimMazeGenerator {
 mod tests {
     use super::*;
     use crate::cell::Cell;
-    use crate::generators::generator::MazeGenerator;
+    use crate::generators::generator::{assert_spanning_tree, MazeGenerator};
+    use crate::topologies::hex::HexTopology;
 
     #[test]
     fn test_generate_prim_maze() {
         // Generate a small maze
         let generator = PrimMazeGenerator;
-        let maze = generator.generate(9, 9, None, None, 0.0, None);
+        let maze = generator.generate(9, 9, None, None, 0.0, None).unwrap();
         // Ensure start and end are correct
         assert_eq!(maze.start, (1, 1));
         assert_eq!(maze.end, (7, 7));
@@ -131,7 +161,9 @@ mod tests {
         let start = (3, 3);
         let end = (5, 5);
         let generator = PrimMazeGenerator;
-        let maze = generator.generate(9, 9, Some(start), Some(end), 0.0, None);
+        let maze = generator
+            .generate(9, 9, Some(start), Some(end), 0.0, None)
+            .unwrap();
         // Ensure start and end are correct
         assert_eq!(maze.start, start);
         assert_eq!(maze.end, end);
@@ -139,4 +171,11 @@ mod tests {
         assert_eq!(maze.cells[maze.start.0][maze.start.1], Cell::Path);
         assert_eq!(maze.cells[maze.end.0][maze.end.1], Cell::Path);
     }
+
+    #[test]
+    fn test_carve_prim_hex_maze() {
+        let hex = HexTopology::new(6, 7);
+        let passages = PrimMazeGenerator.carve(&hex, None);
+        assert_spanning_tree(&hex, &passages);
+    }
 }
@@ -1,26 +1,75 @@
+use crate::bit_grid::{last_cell, BitGrid};
 use crate::cell::Cell;
-use crate::generators::generator::MazeGenerator;
-use crate::maze::Maze;
+use crate::generators::generator::{
+    finish_maze, record_cell, BlockGrid, MazeGenerator, TopologyMazeGenerator,
+};
+use crate::linked_maze::Passages;
+use crate::maze::{Maze, MazeError};
 use crate::progress::ProgressTracker;
+use crate::topologies::topology::Topology;
 use rand::seq::IndexedRandom;
+use rand::Rng;
 
 pub struct RecursiveBacktrackerMazeGenerator;
 
+impl RecursiveBacktrackerMazeGenerator {
+    /// Carves a perfect maze straight into a bit-packed grid, for mazes too
+    /// large to hold as `Cell`s. Logical cells sit on odd rows and columns;
+    /// the start is (1, 1) and the end the last logical cell. Either side
+    /// shorter than 3 leaves no room for a cell and is rejected.
+    pub fn generate_compact(&self, width: usize, height: usize) -> Result<BitGrid, MazeError> {
+        if width < 3 || height < 3 {
+            return Err(MazeError::InvalidDimensions);
+        }
+        let mut grid = BitGrid::new(width, height, (1, 1), last_cell(width, height));
+        let mut rng = crate::random::rng();
+        // cell indices fit in 32 bits, halving the stack for huge mazes
+        let mut stack: Vec<u32> = vec![grid.index(1, 1) as u32];
+        grid.set_open(1, 1, true);
+
+        while let Some(&cell) = stack.last() {
+            let (y, x) = (cell as usize / width, cell as usize % width);
+            let mut neighbors = [(0, 0); 4];
+            let mut count = 0;
+            for (dy, dx) in [(-2, 0), (2, 0), (0, -2), (0, 2)] {
+                let (ny, nx) = (y as isize + dy, x as isize + dx);
+                if ny > 0
+                    && nx > 0
+                    && (ny as usize) < height - 1
+                    && (nx as usize) < width - 1
+                    && !grid.is_open(ny as usize, nx as usize)
+                {
+                    neighbors[count] = (ny as usize, nx as usize);
+                    count += 1;
+                }
+            }
+
+            if let Some(&(next_y, next_x)) = neighbors[..count].choose(&mut rng) {
+                grid.set_open(next_y, next_x, true);
+                grid.set_open((y + next_y) / 2, (x + next_x) / 2, true);
+                stack.push(grid.index(next_y, next_x) as u32);
+            } else {
+                stack.pop();
+            }
+        }
+        Ok(grid)
+    }
+}
+
 impl MazeGenerator for RecursiveBacktrackerMazeGenerator {
-    fn generate(
+    fn generate_on(
         &self,
-        height: usize,
-        width: usize,
+        grid: &BlockGrid,
         start: Option<(usize, usize)>,
         end: Option<(usize, usize)>,
         imperfect_percentage: f32,
         mut tracker: Option<&mut ProgressTracker>,
     ) -> Maze {
-        let mut cells = vec![vec![Cell::Wall; width]; height];
-        let mut rng = rand::thread_rng();
+        let mut cells = vec![vec![Cell::Wall; grid.width]; grid.height];
+        let mut rng = crate::random::rng();
         let mut stack = Vec::new();
 
-        let start_node = start.unwrap_or((1, 1));
+        let start_node = start.unwrap_or(grid.default_start());
         cells[start_node.0][start_node.1] = Cell::Path;
         if let Some(ref mut t) = tracker {
             t.record(start_node.0, start_node.1, Cell::Path);
@@ -28,33 +77,21 @@ impl MazeGenerator for RecursiveBacktrackerMazeGenerator {
         stack.push(start_node);
 
         while let Some(&(y, x)) = stack.last() {
-            let mut neighbors = Vec::new();
-            // Check neighbors (2 cells away)
-            // North
-            if y > 1 && cells[y - 2][x] == Cell::Wall {
-                neighbors.push((y - 2, x));
-            }
-            // South
-            if y < height - 2 && cells[y + 2][x] == Cell::Wall {
-                neighbors.push((y + 2, x));
-            }
-            // West
-            if x > 1 && cells[y][x - 2] == Cell::Wall {
-                neighbors.push((y, x - 2));
-            }
-            // East
-            if x < width - 2 && cells[y][x + 2] == Cell::Wall {
-                neighbors.push((y, x + 2));
-            }
+            // Check unvisited neighbors (2 cells away)
+            let neighbors: Vec<_> = grid
+                .logical_neighbors(y, x)
+                .into_iter()
+                .filter(|&((ny, nx), _)| cells[ny][nx] == Cell::Wall)
+                .collect();
 
-            if let Some(&next_cell) = neighbors.choose(&mut rng) {
+            if let Some(&(next_cell, (between_y, between_x))) = neighbors.choose(&mut rng) {
                 let (next_y, next_x) = next_cell;
                 // Carve path to neighbor
                 cells[next_y][next_x] = Cell::Path;
-                cells[(y + next_y) / 2][(x + next_x) / 2] = Cell::Path;
+                cells[between_y][between_x] = Cell::Path;
                 if let Some(ref mut t) = tracker {
                     t.record(next_y, next_x, Cell::Path);
-                    t.record((y + next_y) / 2, (x + next_x) / 2, Cell::Path);
+                    t.record(between_y, between_x, Cell::Path);
                 }
                 stack.push(next_cell);
             } else {
@@ -62,21 +99,46 @@ impl MazeGenerator for RecursiveBacktrackerMazeGenerator {
             }
         }
 
-        let end_node = end.unwrap_or((height - 2, width - 2));
-        cells[start_node.0][start_node.1] = Cell::Path;
-        cells[end_node.0][end_node.1] = Cell::Path;
+        let end_node = end.unwrap_or(grid.default_end());
 
-        let mut maze = Maze {
-            cells,
-            start: start_node,
-            end: end_node,
-        };
+        finish_maze(grid, cells, start_node, end_node, imperfect_percentage)
+    }
+}
+
+impl TopologyMazeGenerator for RecursiveBacktrackerMazeGenerator {
+    fn carve(
+        &self,
+        topology: &dyn Topology,
+        mut tracker: Option<&mut ProgressTracker>,
+    ) -> Passages {
+        let cell_count = topology.cell_count();
+        let mut passages = Passages::new(cell_count);
+        let mut visited = vec![false; cell_count];
+        let mut rng = crate::random::rng();
+
+        let start_cell = rng.random_range(0..cell_count);
+        visited[start_cell] = true;
+        record_cell(topology, &mut tracker, start_cell);
+        let mut stack = vec![start_cell];
 
-        if imperfect_percentage > 0.0 {
-            maze.remove_walls(imperfect_percentage);
+        while let Some(&cell) = stack.last() {
+            let neighbors: Vec<usize> = topology
+                .neighbors(cell)
+                .into_iter()
+                .filter(|&n| !visited[n])
+                .collect();
+
+            if let Some(&next_cell) = neighbors.choose(&mut rng) {
+                passages.link(cell, next_cell);
+                visited[next_cell] = true;
+                record_cell(topology, &mut tracker, next_cell);
+                stack.push(next_cell);
+            } else {
+                stack.pop();
+            }
         }
 
-        maze
+        passages
     }
 }
 
@@ -84,15 +146,33 @@ impl MazeGenerator for RecursiveBacktrackerMazeGenerator {
 mod tests {
     use super::*;
     use crate::cell::Cell;
-    use crate::generators::generator::MazeGenerator;
+    use crate::generators::generator::{assert_spanning_tree, MazeGenerator};
+    use crate::topologies::hex::HexTopology;
 
     #[test]
     fn test_generate_recursive_backtracker_maze() {
         let generator = RecursiveBacktrackerMazeGenerator;
-        let maze = generator.generate(11, 11, None, None, 0.0, None);
+        let maze = generator.generate(11, 11, None, None, 0.0, None).unwrap();
         assert_eq!(maze.start, (1, 1));
         assert_eq!(maze.end, (9, 9));
         assert_eq!(maze.cells[maze.start.0][maze.start.1], Cell::Path);
         assert_eq!(maze.cells[maze.end.0][maze.end.1], Cell::Path);
     }
+
+    #[test]
+    fn test_generate_rejects_tiny_sizes() {
+        for (height, width) in [(0, 0), (1, 1), (2, 11), (11, 2)] {
+            assert!(matches!(
+                RecursiveBacktrackerMazeGenerator.generate(height, width, None, None, 0.0, None),
+                Err(MazeError::InvalidDimensions)
+            ));
+        }
+    }
+
+    #[test]
+    fn test_carve_recursive_backtracker_hex_maze() {
+        let hex = HexTopology::new(6, 7);
+        let passages = RecursiveBacktrackerMazeGenerator.carve(&hex, None);
+        assert_spanning_tree(&hex, &passages);
+    }
 }
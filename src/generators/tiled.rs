@@ -0,0 +1,297 @@
+use rand::{Rng, RngCore};
+use std::io::{self, Write};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+use crate::bit_grid::{last_cell, BitGrid, BitSet};
+use crate::colors::theme;
+use crate::generators::generator::TopologyMazeGenerator;
+use crate::random;
+use crate::topologies::square::SquareTopology;
+use crate::topologies::topology::Topology;
+
+/// Splits a huge square maze into tiles of `tile_size` x `tile_size` logical
+/// cells, carves the tiles of each band concurrently with any topology
+/// generator and stitches them together. Tiles are joined by single passages
+/// chosen band by band with Eller's algorithm on the grid of tiles, so the
+/// joins form a spanning tree of tiles and the whole maze stays perfect while
+/// only one band is ever held in memory.
+pub struct TiledMazeGenerator<'a> {
+    generator: &'a (dyn TopologyMazeGenerator + Sync),
+    tile_size: usize,
+    jobs: usize,
+}
+
+impl<'a> TiledMazeGenerator<'a> {
+    pub fn new(
+        generator: &'a (dyn TopologyMazeGenerator + Sync),
+        tile_size: usize,
+        jobs: usize,
+    ) -> Self {
+        Self {
+            generator,
+            tile_size: tile_size.max(1),
+            jobs: jobs.max(1),
+        }
+    }
+
+    /// Generates a `width` x `height` block maze laid out like `generate_compact`,
+    /// starting in the top-left cell and ending in the bottom-right one.
+    pub fn generate(&self, width: usize, height: usize) -> BitGrid {
+        let mut grid = BitGrid::new(width, height, (1, 1), last_cell(width, height));
+        self.generate_bands(width, height, |first_row, rows| {
+            for (y, row) in rows.iter().enumerate() {
+                for x in 0..width {
+                    if row.contains(x) {
+                        grid.set_open(first_row + y, x, true);
+                    }
+                }
+            }
+            Ok(())
+        })
+        .expect("collecting bands cannot fail");
+        grid
+    }
+
+    /// Streams the maze into a PNG without ever holding the full image, with
+    /// `scale` pixels per block and start and end colored like other images.
+    pub fn write_png<W: Write>(
+        &self,
+        width: usize,
+        height: usize,
+        scale: u32,
+        writer: W,
+    ) -> io::Result<()> {
+        let scale = scale.max(1) as usize;
+        let (start, end) = ((1, 1), last_cell(width, height));
+        let theme = theme();
+        let mut encoder =
+            png::Encoder::new(writer, (width * scale) as u32, (height * scale) as u32);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut png = encoder.write_header().map_err(io::Error::other)?;
+        let mut stream = png.stream_writer().map_err(io::Error::other)?;
+
+        let mut pixels = Vec::with_capacity(width * scale * 3);
+        self.generate_bands(width, height, |first_row, rows| {
+            for (y, row) in rows.iter().enumerate() {
+                pixels.clear();
+                for x in 0..width {
+                    let color = match (first_row + y, x) {
+                        position if position == start => theme.start,
+                        position if position == end => theme.end,
+                        _ if row.contains(x) => theme.path,
+                        _ => theme.wall,
+                    };
+                    for _ in 0..scale {
+                        pixels.extend_from_slice(&color.0);
+                    }
+                }
+                for _ in 0..scale {
+                    stream.write_all(&pixels)?;
+                }
+            }
+            Ok(())
+        })?;
+        stream.finish().map_err(io::Error::other)?;
+        png.finish().map_err(io::Error::other)
+    }
+
+    /// Produces the block rows of the maze from top to bottom, handing each
+    /// band of tiles to `sink` with the index of its first row. A band starts
+    /// with the wall row above its cells, holding the passages from the band
+    /// above; the last band also ends with the bottom wall.
+    pub fn generate_bands<F>(&self, width: usize, height: usize, mut sink: F) -> io::Result<()>
+    where
+        F: FnMut(usize, &[BitSet]) -> io::Result<()>,
+    {
+        let rows = (height.max(1) - 1) / 2;
+        let cols = (width.max(1) - 1) / 2;
+        if rows == 0 || cols == 0 {
+            return sink(0, &vec![BitSet::new(width); height]);
+        }
+        let tile = self.tile_size;
+        let tiles_x = cols.div_ceil(tile);
+        let tiles_y = rows.div_ceil(tile);
+        let mut rng = random::rng();
+        let base_seed = rng.next_u64();
+
+        // Eller's algorithm over tiles: the set of every tile in the current
+        // band, and the block columns opened into the next band
+        let mut next_set = 0;
+        let mut sets: Vec<Option<usize>> = vec![None; tiles_x];
+        let mut openings_down: Vec<usize> = Vec::new();
+
+        for band in 0..tiles_y {
+            let top = band * tile;
+            let bottom = ((band + 1) * tile).min(rows);
+            let last = band + 1 == tiles_y;
+            let row_count = if last {
+                height - 2 * top
+            } else {
+                2 * (bottom - top)
+            };
+            let mut band_rows = vec![BitSet::new(width); row_count];
+            for &x in &openings_down {
+                band_rows[0].insert(x);
+            }
+
+            let tiles = self.carve_band(band, tiles_x, cols, top, bottom, base_seed);
+            for (tile_x, cells) in tiles.iter().enumerate() {
+                let left = tile_x * tile;
+                let inner_width = 2 * ((left + tile).min(cols) - left) - 1;
+                for y in 0..2 * (bottom - top) - 1 {
+                    for x in 0..inner_width {
+                        if cells.contains(y * inner_width + x) {
+                            band_rows[y + 1].insert(2 * left + 1 + x);
+                        }
+                    }
+                }
+            }
+
+            for set in sets.iter_mut().filter(|set| set.is_none()) {
+                *set = Some(next_set);
+                next_set += 1;
+            }
+            // join neighboring tiles of different sets, all of them in the last band
+            for tile_x in 0..tiles_x - 1 {
+                let (a, b) = (sets[tile_x], sets[tile_x + 1]);
+                if a != b && (last || rng.random_bool(0.5)) {
+                    for set in sets.iter_mut().filter(|set| **set == b) {
+                        *set = a;
+                    }
+                    let row = rng.random_range(top..bottom);
+                    band_rows[2 * (row - top) + 1].insert(2 * (tile_x + 1) * tile);
+                }
+            }
+            if !last {
+                // every set continues below through at least one of its tiles
+                openings_down.clear();
+                let mut below = vec![None; tiles_x];
+                let mut continued = Vec::new();
+                for set in sets.clone() {
+                    if continued.contains(&set) {
+                        continue;
+                    }
+                    continued.push(set);
+                    let members: Vec<usize> = (0..tiles_x).filter(|&t| sets[t] == set).collect();
+                    let chosen = members[rng.random_range(0..members.len())];
+                    for tile_x in members {
+                        if tile_x == chosen || rng.random_bool(0.3) {
+                            below[tile_x] = set;
+                            let left = tile_x * tile;
+                            let col = rng.random_range(left..(left + tile).min(cols));
+                            openings_down.push(2 * col + 1);
+                        }
+                    }
+                }
+                sets = below;
+            }
+            sink(2 * top, &band_rows)?;
+        }
+        Ok(())
+    }
+
+    // carves every tile of a band on the worker threads, each from its own seed,
+    // returning their inner blocks (cells and the walls between them) row by row
+    fn carve_band(
+        &self,
+        band: usize,
+        tiles_x: usize,
+        cols: usize,
+        top: usize,
+        bottom: usize,
+        base_seed: u64,
+    ) -> Vec<BitSet> {
+        let tile = self.tile_size;
+        let next = AtomicUsize::new(0);
+        let carved = Mutex::new(vec![BitSet::new(0); tiles_x]);
+        thread::scope(|scope| {
+            for _ in 0..self.jobs.min(tiles_x) {
+                scope.spawn(|| loop {
+                    let tile_x = next.fetch_add(1, Ordering::Relaxed);
+                    if tile_x >= tiles_x {
+                        break;
+                    }
+                    random::seed(random::derive_seed(
+                        base_seed,
+                        (band * tiles_x + tile_x) as u64,
+                    ));
+                    let left = tile_x * tile;
+                    let topology =
+                        SquareTopology::new(bottom - top, (left + tile).min(cols) - left);
+                    let passages = self.generator.carve(&topology, None);
+
+                    let inner_width = 2 * topology.cols - 1;
+                    let mut cells = BitSet::new((2 * topology.rows - 1) * inner_width);
+                    for cell in 0..topology.cell_count() {
+                        let (row, col) = topology.position(cell);
+                        cells.insert(2 * row * inner_width + 2 * col);
+                        for &neighbor in passages.links(cell) {
+                            let (n_row, n_col) = topology.position(neighbor);
+                            cells.insert((row + n_row) * inner_width + col + n_col);
+                        }
+                    }
+                    carved.lock().unwrap()[tile_x] = cells;
+                });
+            }
+        });
+        carved.into_inner().unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generators::generator::assert_spanning_tree;
+    use crate::generators::kruskal::KruskalMazeGenerator;
+    use crate::generators::wilson::WilsonMazeGenerator;
+    use crate::linked_maze::LinkedMaze;
+    use crate::maze_graph::MazeGraph;
+    use crate::solvers::bfs_solver::BfsSolver;
+    use crate::solvers::solver::Solver;
+
+    #[test]
+    fn test_stitched_tiles_form_perfect_maze() {
+        for generator in [
+            &KruskalMazeGenerator as &(dyn TopologyMazeGenerator + Sync),
+            &WilsonMazeGenerator,
+        ] {
+            // 30 x 20 logical cells in 4 x 4 tiles, with narrower tiles on the edges
+            let grid = TiledMazeGenerator::new(generator, 4, 3).generate(61, 41);
+            let linked = LinkedMaze::from_block_maze(&grid.to_maze()).unwrap();
+            assert_spanning_tree(&linked.topology, &linked.passages);
+            let path = BfsSolver.solve_graph(&grid, None).unwrap();
+            assert_eq!(grid.position(path[path.len() - 1]), (39, 59));
+        }
+    }
+
+    #[test]
+    fn test_streamed_png_matches_grid() {
+        let tiled = TiledMazeGenerator::new(&KruskalMazeGenerator, 5, 2);
+        random::seed(9);
+        let grid = tiled.generate(33, 23);
+        random::seed(9);
+        let mut png = Vec::new();
+        tiled.write_png(33, 23, 2, &mut png).unwrap();
+
+        let image = image::load_from_memory(&png).unwrap().to_rgb8();
+        assert_eq!(image.dimensions(), (66, 46));
+        for row in 0..23 {
+            for col in 0..33 {
+                let pixel = *image.get_pixel(2 * col as u32 + 1, 2 * row as u32);
+                let expected = if (row, col) == grid.start {
+                    theme().start
+                } else if (row, col) == grid.end {
+                    theme().end
+                } else if grid.is_open(row, col) {
+                    theme().path
+                } else {
+                    theme().wall
+                };
+                assert_eq!(pixel, expected, "block ({}, {})", row, col);
+            }
+        }
+    }
+}
@@ -0,0 +1,202 @@
+use crate::cell::{Axis, Cell};
+use crate::generators::generator::{finish_maze, BlockGrid, MazeGenerator};
+use crate::generators::kruskal::DisjointSet;
+use crate::maze::Maze;
+use crate::progress::ProgressTracker;
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+/// Kruskal's algorithm with crossings: before any wall is knocked down, random
+/// logical cells become crossings where a north-south and an east-west corridor
+/// pass over each other. Each crossing joins its opposite neighbours, so the
+/// remaining Kruskal pass still yields a perfect maze.
+pub struct WeaveMazeGenerator {
+    /// Fraction of logical cells (0.0 to 1.0) tried as crossings
+    pub density: f32,
+}
+
+impl WeaveMazeGenerator {
+    pub fn new(density: f32) -> Self {
+        Self { density }
+    }
+}
+
+impl MazeGenerator for WeaveMazeGenerator {
+    fn generate_on(
+        &self,
+        grid: &BlockGrid,
+        start: Option<(usize, usize)>,
+        end: Option<(usize, usize)>,
+        imperfect_percentage: f32,
+        mut tracker: Option<&mut ProgressTracker>,
+    ) -> Maze {
+        let (height, width) = (grid.height, grid.width);
+        let mut cells = vec![vec![Cell::Wall; width]; height];
+        let mut rng = crate::random::rng();
+        let start_node = start.unwrap_or(grid.default_start());
+        let end_node = end.unwrap_or(grid.default_end());
+
+        let logical_cells = grid.logical_cells();
+        for &(r, c) in &logical_cells {
+            cells[r][c] = Cell::Path;
+            if let Some(ref mut t) = tracker {
+                t.record(r, c, Cell::Path);
+            }
+        }
+
+        let mut dset = DisjointSet::new(height * width);
+        let index = |(r, c): (usize, usize)| r * width + c;
+
+        let mut crossings = Vec::new();
+        let mut candidates = logical_cells.clone();
+        candidates.shuffle(&mut rng);
+        let attempts = (candidates.len() as f32 * self.density.clamp(0.0, 1.0)) as usize;
+        for &(r, c) in candidates.iter().take(attempts) {
+            if (r, c) == start_node || (r, c) == end_node {
+                continue;
+            }
+            // neighbours in north, south, west, east order
+            let neighbors = grid.logical_neighbors(r, c);
+            if neighbors.len() != 4 {
+                continue;
+            }
+            // corridors may not touch another crossing or reuse a carved passage
+            if neighbors.iter().any(|&((ny, nx), (wy, wx))| {
+                cells[wy][wx] != Cell::Wall || cells[ny][nx] != Cell::Path
+            }) {
+                continue;
+            }
+            let mut roots: Vec<usize> = neighbors
+                .iter()
+                .map(|&(n, _)| dset.find(index(n)))
+                .collect();
+            roots.sort_unstable();
+            roots.dedup();
+            if roots.len() != 4 {
+                continue;
+            }
+
+            let over = if rng.random_bool(0.5) {
+                Axis::Horizontal
+            } else {
+                Axis::Vertical
+            };
+            cells[r][c] = Cell::Crossing(over);
+            if let Some(ref mut t) = tracker {
+                t.record(r, c, Cell::Crossing(over));
+            }
+            for &(_, (wy, wx)) in &neighbors {
+                cells[wy][wx] = Cell::Path;
+                if let Some(ref mut t) = tracker {
+                    t.record(wy, wx, Cell::Path);
+                }
+            }
+            dset.union(index(neighbors[0].0), index(neighbors[1].0));
+            dset.union(index(neighbors[2].0), index(neighbors[3].0));
+            crossings.push(((r, c), neighbors[0].0, neighbors[2].0));
+        }
+
+        // Each wall (r1, c1, r2, c2, wall_r, wall_c) is listed once, from the cell above or left of it
+        let mut walls = Vec::new();
+        for &(r, c) in &logical_cells {
+            for ((nr, nc), (wr, wc)) in grid.logical_neighbors(r, c) {
+                if (wr, wc) == ((r + 1) % height, c) || (wr, wc) == (r, (c + 1) % width) {
+                    walls.push((r, c, nr, nc, wr, wc));
+                }
+            }
+        }
+        walls.shuffle(&mut rng);
+
+        for (r1, c1, r2, c2, wr, wc) in walls {
+            // all passages of a crossing are already carved
+            if cells[r1][c1] != Cell::Path || cells[r2][c2] != Cell::Path {
+                continue;
+            }
+            let idx1 = index((r1, c1));
+            let idx2 = index((r2, c2));
+            if dset.find(idx1) != dset.find(idx2) {
+                dset.union(idx1, idx2);
+                cells[wr][wc] = Cell::Path;
+                if let Some(ref mut t) = tracker {
+                    t.record(wr, wc, Cell::Path);
+                }
+            }
+        }
+
+        // Where the shape leaves no way around a crossing, its two corridors end up
+        // in separate trees. Turning it back into a plain junction joins them and
+        // keeps the maze perfect.
+        for ((r, c), north, west) in crossings {
+            if dset.find(index(north)) != dset.find(index(west)) {
+                dset.union(index(north), index(west));
+                cells[r][c] = Cell::Path;
+                if let Some(ref mut t) = tracker {
+                    t.record(r, c, Cell::Path);
+                }
+            }
+        }
+
+        finish_maze(grid, cells, start_node, end_node, imperfect_percentage)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generators::generator::assert_perfect_block_maze;
+    use crate::solvers::astar::AstarSolver;
+    use crate::solvers::bfs_solver::BfsSolver;
+    use crate::solvers::solver::Solver;
+
+    fn crossings(maze: &Maze) -> Vec<(usize, usize)> {
+        let mut found = Vec::new();
+        for (r, row) in maze.cells.iter().enumerate() {
+            for (c, cell) in row.iter().enumerate() {
+                if matches!(cell, Cell::Crossing(_)) {
+                    found.push((r, c));
+                }
+            }
+        }
+        found
+    }
+
+    #[test]
+    fn test_generate_weave_maze_is_perfect() {
+        let maze = WeaveMazeGenerator::new(0.5)
+            .generate(21, 21, None, None, 0.0, None)
+            .unwrap();
+        assert!(!crossings(&maze).is_empty());
+        assert_perfect_block_maze(&maze);
+    }
+
+    #[test]
+    fn test_weave_density_zero_has_no_crossings() {
+        let maze = WeaveMazeGenerator::new(0.0)
+            .generate(21, 21, None, None, 0.0, None)
+            .unwrap();
+        assert!(crossings(&maze).is_empty());
+        assert_perfect_block_maze(&maze);
+    }
+
+    #[test]
+    fn test_solvers_go_straight_through_crossings() {
+        let maze = WeaveMazeGenerator::new(1.0)
+            .generate(31, 31, None, None, 0.0, None)
+            .unwrap();
+        for solver in [&AstarSolver as &dyn Solver, &BfsSolver] {
+            let path = solver
+                .solve(&maze, None)
+                .expect("Weave maze must be solvable");
+            for window in path.windows(3) {
+                if let Cell::Crossing(_) = maze.cells[window[1].0][window[1].1] {
+                    assert_eq!(
+                        Axis::of_step(window[0], window[1]),
+                        Axis::of_step(window[1], window[2]),
+                        "Path turns inside a crossing at {:?}",
+                        window[1]
+                    );
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,176 @@
+use crate::cell::Cell;
+use crate::generators::generator::{
+    finish_maze, record_cell, BlockGrid, MazeGenerator, TopologyMazeGenerator,
+};
+use crate::linked_maze::Passages;
+use crate::maze::Maze;
+use crate::progress::ProgressTracker;
+use crate::topologies::topology::Topology;
+use rand::seq::{IndexedRandom, SliceRandom};
+use std::collections::HashMap;
+
+/// Generates a uniform spanning tree maze using Wilson's loop-erased random walks.
+pub struct WilsonMazeGenerator;
+
+impl MazeGenerator for WilsonMazeGenerator {
+    fn generate_on(
+        &self,
+        grid: &BlockGrid,
+        start: Option<(usize, usize)>,
+        end: Option<(usize, usize)>,
+        imperfect_percentage: f32,
+        mut tracker: Option<&mut ProgressTracker>,
+    ) -> Maze {
+        let mut cells = vec![vec![Cell::Wall; grid.width]; grid.height];
+        let mut in_maze = vec![vec![false; grid.width]; grid.height];
+        let mut rng = crate::random::rng();
+
+        let mut pending = grid.logical_cells();
+        pending.shuffle(&mut rng);
+
+        // Seed the maze with a single cell
+        let (first_y, first_x) = pending[0];
+        in_maze[first_y][first_x] = true;
+        cells[first_y][first_x] = Cell::Path;
+        if let Some(ref mut t) = tracker {
+            t.record(first_y, first_x, Cell::Path);
+        }
+
+        for &walk_start in &pending[1..] {
+            if in_maze[walk_start.0][walk_start.1] {
+                continue;
+            }
+
+            // Random walk until the maze is hit, erasing loops as they form.
+            // Each walk entry is a cell and the cell between it and the previous one.
+            let mut walk = vec![(walk_start, walk_start)];
+            let mut walk_index = HashMap::from([(walk_start, 0)]);
+            let (mut current_y, mut current_x) = walk_start;
+            while !in_maze[current_y][current_x] {
+                let neighbors = grid.logical_neighbors(current_y, current_x);
+                let (next, between) = *neighbors.choose(&mut rng).unwrap();
+                if let Some(&idx) = walk_index.get(&next) {
+                    for (erased, _) in walk.drain(idx + 1..) {
+                        walk_index.remove(&erased);
+                    }
+                } else {
+                    walk_index.insert(next, walk.len());
+                    walk.push((next, between));
+                }
+                (current_y, current_x) = next;
+            }
+
+            // Carve the loop-erased walk into the maze
+            for pair in walk.windows(2) {
+                let ((y1, x1), _) = pair[0];
+                let (_, (between_y, between_x)) = pair[1];
+                in_maze[y1][x1] = true;
+                cells[y1][x1] = Cell::Path;
+                cells[between_y][between_x] = Cell::Path;
+                if let Some(ref mut t) = tracker {
+                    t.record(y1, x1, Cell::Path);
+                    t.record(between_y, between_x, Cell::Path);
+                }
+            }
+        }
+
+        let start_node = start.unwrap_or(grid.default_start());
+        let end_node = end.unwrap_or(grid.default_end());
+
+        finish_maze(grid, cells, start_node, end_node, imperfect_percentage)
+    }
+}
+
+impl TopologyMazeGenerator for WilsonMazeGenerator {
+    fn carve(
+        &self,
+        topology: &dyn Topology,
+        mut tracker: Option<&mut ProgressTracker>,
+    ) -> Passages {
+        let cell_count = topology.cell_count();
+        let mut passages = Passages::new(cell_count);
+        let mut in_maze = vec![false; cell_count];
+        let mut rng = crate::random::rng();
+
+        let mut pending: Vec<usize> = (0..cell_count).collect();
+        pending.shuffle(&mut rng);
+        in_maze[pending[0]] = true;
+        record_cell(topology, &mut tracker, pending[0]);
+
+        for &walk_start in &pending[1..] {
+            if in_maze[walk_start] {
+                continue;
+            }
+
+            let mut walk = vec![walk_start];
+            let mut walk_index = HashMap::from([(walk_start, 0)]);
+            let mut current = walk_start;
+            while !in_maze[current] {
+                let neighbors = topology.neighbors(current);
+                let next = *neighbors
+                    .choose(&mut rng)
+                    .expect("topology cells must have neighbors");
+                if let Some(&idx) = walk_index.get(&next) {
+                    for erased in walk.drain(idx + 1..) {
+                        walk_index.remove(&erased);
+                    }
+                } else {
+                    walk_index.insert(next, walk.len());
+                    walk.push(next);
+                }
+                current = next;
+            }
+
+            for pair in walk.windows(2) {
+                in_maze[pair[0]] = true;
+                passages.link(pair[0], pair[1]);
+                record_cell(topology, &mut tracker, pair[0]);
+            }
+        }
+
+        passages
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cell::Cell;
+    use crate::generators::generator::{
+        assert_perfect_block_maze, assert_spanning_tree, BlockGrid, MazeGenerator,
+    };
+    use crate::maze::Wrap;
+    use crate::topologies::hex::HexTopology;
+
+    #[test]
+    fn test_generate_wilson_maze() {
+        let generator = WilsonMazeGenerator;
+        let maze = generator.generate(11, 11, None, None, 0.0, None).unwrap();
+        assert_eq!(maze.start, (1, 1));
+        assert_eq!(maze.end, (9, 9));
+        assert_eq!(maze.cells[maze.start.0][maze.start.1], Cell::Path);
+        assert_eq!(maze.cells[maze.end.0][maze.end.1], Cell::Path);
+        // every logical cell is carved
+        for r in (1..11).step_by(2) {
+            for c in (1..11).step_by(2) {
+                assert_eq!(maze.cells[r][c], Cell::Path);
+            }
+        }
+    }
+
+    #[test]
+    fn test_carve_wilson_hex_spanning_tree() {
+        let hex = HexTopology::new(6, 7);
+        let passages = WilsonMazeGenerator.carve(&hex, None);
+        assert_spanning_tree(&hex, &passages);
+    }
+
+    #[test]
+    fn test_generate_wilson_cylinder_maze() {
+        let grid = BlockGrid::new(11, 12).unwrap().with_wrap(Wrap::Cylinder);
+        let maze = WilsonMazeGenerator.generate_on(&grid, None, None, 0.0, None);
+        assert_eq!(maze.wrap, Wrap::Cylinder);
+        assert_eq!(maze.end, grid.default_end());
+        assert_perfect_block_maze(&maze);
+    }
+}
@@ -1,42 +1,47 @@
+use crate::animation::{AnimationPolicy, AnimationWriter, FrameTiming};
 use crate::cell::Cell;
-use crate::colors::{
-    CURRENT_COLOR, END_COLOR, PATH_COLOR, SOLUTION_PATH_COLOR, START_COLOR, VISITED_COLOR,
-    WALL_COLOR,
-};
+use crate::colors::{theme, Theme, PORTAL_COLOR_COUNT};
 use crate::maze::Maze;
-use gif::{Encoder, Frame, Repeat};
+use crate::maze_image::{cell_overlay, is_door_frame, is_key_disc, Overlay};
+use crate::solvers::dstar_lite::{CellChange, DStarLite};
 use image::Rgb;
-use std::collections::HashSet;
-use std::fs::File;
+use std::collections::{HashMap, HashSet};
 
 /// Palette index for visited-cell overlay.
 const VISITED_INDEX: u8 = 5;
 /// Palette index for current-cell highlight.
 const CURRENT_INDEX: u8 = 6;
 
-const PALETTE: &[u8] = &[
-    WALL_COLOR.0[0],
-    WALL_COLOR.0[1],
-    WALL_COLOR.0[2], // 0: wall
-    PATH_COLOR.0[0],
-    PATH_COLOR.0[1],
-    PATH_COLOR.0[2], // 1: corridor
-    START_COLOR.0[0],
-    START_COLOR.0[1],
-    START_COLOR.0[2], // 2: start
-    END_COLOR.0[0],
-    END_COLOR.0[1],
-    END_COLOR.0[2], // 3: end
-    SOLUTION_PATH_COLOR.0[0],
-    SOLUTION_PATH_COLOR.0[1],
-    SOLUTION_PATH_COLOR.0[2], // 4: solution path
-    VISITED_COLOR.0[0],
-    VISITED_COLOR.0[1],
-    VISITED_COLOR.0[2], // 5: visited cells
-    CURRENT_COLOR.0[0],
-    CURRENT_COLOR.0[1],
-    CURRENT_COLOR.0[2], // 6: current cell
-];
+// colors of the indexed frame buffers in `theme`, in palette index order
+fn palette(theme: &Theme) -> Vec<u8> {
+    [
+        theme.wall,          // 0: wall
+        theme.path,          // 1: corridor
+        theme.start,         // 2: start
+        theme.end,           // 3: end
+        theme.solution_path, // 4: solution path
+        theme.visited,       // 5: visited cells
+        theme.current,       // 6: current cell
+        theme.wrap,          // 7: wrap-around passage
+        theme.background,    // 8: outside a shaped maze
+    ]
+    .iter()
+    .chain(&theme.key_doors) // 9-12: key/door colors
+    .chain(&theme.portals) // 13-18: portal colors
+    .chain([&theme.checkpoint]) // 19: checkpoint
+    .chain(&theme.routes) // 20-26: route/agent colors
+    .flat_map(|color| color.0)
+    .collect()
+}
+
+/// Palette index of the first key/door color.
+const KEY_DOOR_INDEX: u8 = 9;
+/// Palette index of the first portal color.
+const PORTAL_INDEX: u8 = 13;
+/// Palette index of the checkpoint color.
+const CHECKPOINT_INDEX: u8 = 19;
+/// Palette index of the first route color, used for agents.
+const ROUTE_INDEX: u8 = 20;
 
 /// Animate maze generation history and append final maze (including wall removals)
 pub fn save_history_to_gif(
@@ -44,54 +49,58 @@ pub fn save_history_to_gif(
     history: &[(usize, usize, Cell)],
     file_path: &str,
     scale: u32,
-    delay: u16,
+    policy: &AnimationPolicy,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    // Build an empty maze for animation, preserving start/end
+    // Build an empty maze for animation, preserving start/end and the maze's shape
     let mut anim = Maze {
-        cells: vec![vec![Cell::Wall; maze.cells[0].len()]; maze.cells.len()],
+        cells: maze
+            .cells
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|&cell| if cell == Cell::Void { cell } else { Cell::Wall })
+                    .collect()
+            })
+            .collect(),
         start: maze.start,
         end: maze.end,
+        wrap: maze.wrap,
+        portals: maze.portals.clone(),
     };
 
-    let mut image_file = File::create(file_path)?;
     let w = maze.cells[0].len() as u32;
     let h = maze.cells.len() as u32;
-    let mut encoder = Encoder::new(
-        &mut image_file,
-        (w * scale) as u16,
-        (h * scale) as u16,
-        PALETTE,
-    )?;
-    encoder.set_repeat(Repeat::Finite(0))?;
+    let timing = &policy.timing;
+    let theme = theme();
+    let palette = palette(&theme);
+    let mut writer = AnimationWriter::create(file_path, w * scale, h * scale, &palette, timing)?;
 
     let mut frame_buffer = maze_to_indexed_buffer(&anim, scale, &HashSet::new());
 
-    // Emit one frame per generation history step
+    // Emit frames for the generation history steps as the policy paces them
+    let mut pacer = policy.pacer(history.len());
+    let mut frame = to_rgb(&frame_buffer, &palette);
     for (y, x, cell) in history {
         anim.cells[*y][*x] = *cell;
         update_frame_buffer(&mut frame_buffer, &anim, *y, *x, scale, &HashSet::new());
-        let mut frame = Frame::from_indexed_pixels(
-            (w * scale) as u16,
-            (h * scale) as u16,
-            &frame_buffer[..],
-            None,
+        copy_cell(
+            &mut frame,
+            &frame_buffer,
+            &palette,
+            w * scale,
+            *y,
+            *x,
+            scale,
         );
-        frame.delay = delay;
-        encoder.write_frame(&frame)?;
+        if let Some(delay) = pacer.step() {
+            writer.write_frame(&frame, delay)?;
+        }
     }
 
     // Final frame: render the passed-in maze (with wall removals)
     frame_buffer = maze_to_indexed_buffer(maze, scale, &HashSet::new());
-    let mut final_frame = Frame::from_indexed_pixels(
-        (w * scale) as u16,
-        (h * scale) as u16,
-        &frame_buffer[..],
-        None,
-    );
-    final_frame.delay = delay;
-    encoder.write_frame(&final_frame)?;
-
-    Ok(())
+    writer.write_frame(&to_rgb(&frame_buffer, &palette), timing.delay)?;
+    writer.finish()
 }
 
 pub fn save_maze_with_path_to_gif(
@@ -99,116 +108,247 @@ pub fn save_maze_with_path_to_gif(
     path_to_solve: &[(usize, usize)],
     file_path: &str,
     scale: u32,
-    delay: u16,
+    policy: &AnimationPolicy,
 ) -> Result<(), Box<dyn std::error::Error>> {
     let width = maze.cells[0].len() as u32 * scale;
     let height = maze.cells.len() as u32 * scale;
 
-    let mut image_file = File::create(file_path)?;
-    let mut encoder = Encoder::new(&mut image_file, width as u16, height as u16, PALETTE)?;
-    encoder.set_repeat(Repeat::Finite(0))?;
+    let timing = &policy.timing;
+    let theme = theme();
+    let palette = palette(&theme);
+    let mut writer = AnimationWriter::create(file_path, width, height, &palette, timing)?;
 
     let mut path_set = HashSet::new();
     let mut frame_buffer = maze_to_indexed_buffer(maze, scale, &path_set);
 
-    let mut frame =
-        Frame::from_indexed_pixels(width as u16, height as u16, &frame_buffer[..], None);
-    frame.delay = delay;
-    encoder.write_frame(&frame)?;
+    writer.write_frame(&to_rgb(&frame_buffer, &palette), timing.delay)?;
 
+    let mut pacer = policy.pacer(path_to_solve.len());
+    let mut frame = to_rgb(&frame_buffer, &palette);
     for (y, x) in path_to_solve {
         path_set.insert((*y, *x));
         update_frame_buffer(&mut frame_buffer, maze, *y, *x, scale, &path_set);
-        let mut frame =
-            Frame::from_indexed_pixels(width as u16, height as u16, &frame_buffer[..], None);
-        frame.delay = delay;
-        encoder.write_frame(&frame)?;
+        copy_cell(&mut frame, &frame_buffer, &palette, width, *y, *x, scale);
+        if let Some(delay) = pacer.step() {
+            writer.write_frame(&frame, delay)?;
+        }
     }
 
-    Ok(())
+    writer.finish()
 }
 
-/// Saves a GIF animating the solver's exploration history and then the solution path.
+/// Saves a GIF animating the solver's exploration history and then the solution
+/// path, both paced as one sequence of steps by `policy`.
 pub fn save_solver_history_to_gif(
     maze: &Maze,
     history: &[(usize, usize, Cell)],
     path_to_solve: &[(usize, usize)],
     file_path: &str,
     scale: u32,
-    delay: u16,
+    policy: &AnimationPolicy,
 ) -> Result<(), Box<dyn std::error::Error>> {
     let width = maze.cells[0].len() as u32 * scale;
     let height = maze.cells.len() as u32 * scale;
 
-    let mut image_file = File::create(file_path)?;
-    let mut encoder = Encoder::new(&mut image_file, width as u16, height as u16, PALETTE)?;
-    encoder.set_repeat(Repeat::Finite(0))?;
+    let timing = &policy.timing;
+    let theme = theme();
+    let palette = palette(&theme);
+    let mut writer = AnimationWriter::create(file_path, width, height, &palette, timing)?;
 
     // Initial frame: the maze before exploration
     let mut frame_buffer = maze_to_indexed_buffer(maze, scale, &HashSet::new());
-    let mut frame =
-        Frame::from_indexed_pixels(width as u16, height as u16, &frame_buffer[..], None);
-    frame.delay = delay;
-    encoder.write_frame(&frame)?;
+    writer.write_frame(&to_rgb(&frame_buffer, &palette), timing.delay)?;
 
-    // Throttle solver frames to at most MAX_VISITS for speed and size
-    const MAX_VISITS: usize = 500;
+    let mut pacer = policy.pacer(history.len() + path_to_solve.len());
     let total_visits = history.len();
-    let step = if total_visits > MAX_VISITS {
-        (total_visits + MAX_VISITS - 1) / MAX_VISITS
-    } else {
-        1
-    };
-    let mut visited_set = HashSet::new();
+    // visited cells are shaded by when they were first reached, painting only
+    // the cells each step changes
+    let mut frame = to_rgb(&frame_buffer, &palette);
+    let mut first_visits = HashMap::new();
     for (i, &(y, x, _)) in history.iter().enumerate() {
-        // record all visits so none are dropped
-        visited_set.insert((y, x));
-        // only emit frames at sampling steps
-        if i % step != 0 {
+        let first = *first_visits.entry((y, x)).or_insert(i);
+        let endpoint = (y, x) == maze.start || (y, x) == maze.end;
+        let heat = theme.heatmap_color(first as f64 / total_visits.max(1) as f64);
+        if !endpoint {
+            fill_cell(&mut frame, width, y, x, scale, heat);
+        }
+        // only emit frames at the steps the policy shows
+        let Some(delay) = pacer.step() else {
             continue;
+        };
+        // highlight current cell differently, skip start/end
+        if endpoint {
+            writer.write_frame(&frame, delay)?;
+        } else {
+            fill_cell(&mut frame, width, y, x, scale, theme.current);
+            writer.write_frame(&frame, delay)?;
+            fill_cell(&mut frame, width, y, x, scale, heat);
         }
-        // rebuild background and overlay visited cells
-        frame_buffer = maze_to_indexed_buffer(maze, scale, &HashSet::new());
-        for &(vy, vx) in &visited_set {
-            // do not recolor start/end
-            if (vy, vx) == maze.start || (vy, vx) == maze.end {
+    }
+
+    // Solution path: overlay using the solution path color
+    let mut path_set = HashSet::new();
+    for &(y, x) in path_to_solve {
+        path_set.insert((y, x));
+        update_frame_buffer(&mut frame_buffer, maze, y, x, scale, &path_set);
+        copy_cell(&mut frame, &frame_buffer, &palette, width, y, x, scale);
+        if let Some(delay) = pacer.step() {
+            writer.write_frame(&frame, delay)?;
+        }
+    }
+
+    writer.finish()
+}
+
+/// Animates several agents moving through a maze in step, one frame per time
+/// step of the timeline. Each agent is a disc in its own color and its end is
+/// framed in the same color.
+pub fn save_agents_to_gif(
+    maze: &Maze,
+    timeline: &[Vec<(usize, usize)>],
+    file_path: &str,
+    scale: u32,
+    timing: &FrameTiming,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let width = maze.cells[0].len() as u32 * scale;
+    let height = maze.cells.len() as u32 * scale;
+
+    let theme = theme();
+    let palette = palette(&theme);
+    let mut writer = AnimationWriter::create(file_path, width, height, &palette, timing)?;
+
+    let background = maze_to_indexed_buffer(maze, scale, &HashSet::new());
+    let steps = timeline.iter().map(Vec::len).max().unwrap_or(0);
+    for time in 0..steps {
+        let mut frame_buffer = background.clone();
+        for (agent, route) in timeline.iter().enumerate() {
+            let color_index = ROUTE_INDEX + (agent % theme.routes.len()) as u8;
+            let (Some(&(end_y, end_x)), Some(&(y, x))) = (route.last(), route.get(time)) else {
                 continue;
-            }
-            for dy in 0..scale {
-                for dx in 0..scale {
-                    let idx =
-                        ((vy as u32 * scale + dy) * width + (vx as u32 * scale + dx)) as usize;
-                    frame_buffer[idx] = VISITED_INDEX;
+            };
+            for i in 0..scale {
+                for j in 0..scale {
+                    if scale >= 3 && is_door_frame(i, j, scale) {
+                        let index = ((end_y as u32 * scale + i) * width
+                            + (end_x as u32 * scale + j))
+                            as usize;
+                        frame_buffer[index] = color_index;
+                    }
+                    if scale < 3 || is_key_disc(i, j, scale) {
+                        let index =
+                            ((y as u32 * scale + i) * width + (x as u32 * scale + j)) as usize;
+                        frame_buffer[index] = color_index;
+                    }
                 }
             }
         }
-        // highlight current cell differently, skip start/end
-        if (y, x) != maze.start && (y, x) != maze.end {
-            for dy in 0..scale {
-                for dx in 0..scale {
-                    let idx = ((y as u32 * scale + dy) * width + (x as u32 * scale + dx)) as usize;
-                    frame_buffer[idx] = CURRENT_INDEX;
+        writer.write_frame(&to_rgb(&frame_buffer, &palette), timing.delay)?;
+    }
+
+    writer.finish()
+}
+
+/// Animates an agent walking from the start to the end while cells change
+/// under it: each frame shows the agent, its current shortest path and the
+/// cells changed on that step outlined, with D* Lite repairing the path after
+/// every change. Changes that would cut the agent off from the end are undone.
+pub fn save_replanning_to_gif(
+    maze: &Maze,
+    changes: &[CellChange],
+    file_path: &str,
+    scale: u32,
+    timing: &FrameTiming,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let width = maze.cells[0].len() as u32 * scale;
+    let height = maze.cells.len() as u32 * scale;
+
+    let theme = theme();
+    let palette = palette(&theme);
+    let mut writer = AnimationWriter::create(file_path, width, height, &palette, timing)?;
+
+    let mut planner = DStarLite::new(maze.clone());
+    let mut step = 0;
+    loop {
+        let mut changed = Vec::new();
+        for change in changes.iter().filter(|change| change.step == step) {
+            let (row, col) = change.position;
+            let old = planner.maze().cells[row][col];
+            planner.notify_change(row, col, change.cell);
+            if planner.path().is_none() {
+                planner.notify_change(row, col, old);
+            } else {
+                changed.push(change.position);
+            }
+        }
+
+        let path = planner.path();
+        let path_set: HashSet<(usize, usize)> = path.iter().flatten().copied().collect();
+        let mut frame_buffer = maze_to_indexed_buffer(planner.maze(), scale, &path_set);
+        let (agent_y, agent_x) = planner.agent();
+        for i in 0..scale {
+            for j in 0..scale {
+                for &(y, x) in &changed {
+                    if scale < 3 || is_door_frame(i, j, scale) {
+                        let index =
+                            ((y as u32 * scale + i) * width + (x as u32 * scale + j)) as usize;
+                        frame_buffer[index] = VISITED_INDEX;
+                    }
+                }
+                if scale < 3 || is_key_disc(i, j, scale) {
+                    let index = ((agent_y as u32 * scale + i) * width
+                        + (agent_x as u32 * scale + j)) as usize;
+                    frame_buffer[index] = CURRENT_INDEX;
                 }
             }
         }
-        let mut frame =
-            Frame::from_indexed_pixels(width as u16, height as u16, &frame_buffer[..], None);
-        frame.delay = delay;
-        encoder.write_frame(&frame)?;
+        writer.write_frame(&to_rgb(&frame_buffer, &palette), timing.delay)?;
+
+        if path.is_none() || planner.advance().is_none() {
+            break;
+        }
+        step += 1;
     }
 
-    // Solution path: overlay using SOLUTION_PATH_COLOR
-    let mut path_set = HashSet::new();
-    for &(y, x) in path_to_solve {
-        path_set.insert((y, x));
-        update_frame_buffer(&mut frame_buffer, maze, y, x, scale, &path_set);
-        let mut frame =
-            Frame::from_indexed_pixels(width as u16, height as u16, &frame_buffer[..], None);
-        frame.delay = delay;
-        encoder.write_frame(&frame)?;
+    writer.finish()
+}
+
+// RGB pixels of an indexed frame buffer
+fn to_rgb(frame_buffer: &[u8], palette: &[u8]) -> Vec<u8> {
+    frame_buffer
+        .iter()
+        .flat_map(|&index| {
+            let index = index as usize * 3;
+            [palette[index], palette[index + 1], palette[index + 2]]
+        })
+        .collect()
+}
+
+fn fill_cell(frame: &mut [u8], width: u32, y: usize, x: usize, scale: u32, color: Rgb<u8>) {
+    for dy in 0..scale {
+        for dx in 0..scale {
+            let index = ((y as u32 * scale + dy) * width + (x as u32 * scale + dx)) as usize * 3;
+            frame[index..index + 3].copy_from_slice(&color.0);
+        }
     }
+}
 
-    Ok(())
+// converts the pixels of one cell of an indexed frame buffer into an RGB frame
+fn copy_cell(
+    frame: &mut [u8],
+    frame_buffer: &[u8],
+    palette: &[u8],
+    width: u32,
+    y: usize,
+    x: usize,
+    scale: u32,
+) {
+    for dy in 0..scale {
+        for dx in 0..scale {
+            let index = ((y as u32 * scale + dy) * width + (x as u32 * scale + dx)) as usize;
+            let color = frame_buffer[index] as usize * 3;
+            frame[index * 3..index * 3 + 3].copy_from_slice(&palette[color..color + 3]);
+        }
+    }
 }
 
 fn maze_to_indexed_buffer(maze: &Maze, scale: u32, path: &HashSet<(usize, usize)>) -> Vec<u8> {
@@ -222,7 +362,7 @@ fn maze_to_indexed_buffer(maze: &Maze, scale: u32, path: &HashSet<(usize, usize)
             for i in 0..scale {
                 for j in 0..scale {
                     let index = ((y as u32 * scale + i) * width + (x as u32 * scale + j)) as usize;
-                    buffer[index] = color_index;
+                    buffer[index] = pixel_index(cell, i, j, scale, color_index);
                 }
             }
         }
@@ -231,7 +371,7 @@ fn maze_to_indexed_buffer(maze: &Maze, scale: u32, path: &HashSet<(usize, usize)
 }
 
 fn update_frame_buffer(
-    frame_buffer: &mut Vec<u8>,
+    frame_buffer: &mut [u8],
     maze: &Maze,
     y: usize,
     x: usize,
@@ -244,11 +384,22 @@ fn update_frame_buffer(
     for i in 0..scale {
         for j in 0..scale {
             let index = ((y as u32 * scale + i) * width + (x as u32 * scale + j)) as usize;
-            frame_buffer[index] = color_index;
+            frame_buffer[index] = pixel_index(cell, i, j, scale, color_index);
         }
     }
 }
 
+// palette index of pixel (i, j) of a cell, drawing details over its base color
+fn pixel_index(cell: &Cell, i: u32, j: u32, scale: u32, color_index: u8) -> u8 {
+    match cell_overlay(cell, i, j, scale) {
+        Some(Overlay::Rail | Overlay::Arrow) => 0,
+        Some(Overlay::Checkpoint) => CHECKPOINT_INDEX,
+        Some(Overlay::KeyColor(color)) => KEY_DOOR_INDEX + color,
+        Some(Overlay::PortalColor(letter)) => PORTAL_INDEX + letter % PORTAL_COLOR_COUNT as u8,
+        None => color_index,
+    }
+}
+
 fn get_color_index(
     maze: &Maze,
     y: usize,
@@ -265,7 +416,15 @@ fn get_color_index(
     } else {
         match cell {
             Cell::Wall => 0,
-            Cell::Path => 1,
+            Cell::Path if maze.is_wrap_seam(y, x) => 7,
+            Cell::Path
+            | Cell::Crossing(_)
+            | Cell::Key(_)
+            | Cell::Door(_)
+            | Cell::Portal(_)
+            | Cell::OneWay(_)
+            | Cell::Checkpoint => 1,
+            Cell::Void => 8,
         }
     }
 }
@@ -0,0 +1,24 @@
+pub mod animation;
+pub mod batch;
+pub mod bit_grid;
+pub mod cell;
+pub mod colors;
+pub mod csr_graph;
+pub mod generators;
+pub mod gif_generator;
+pub mod linked_maze;
+pub mod linked_maze_image;
+pub mod mask;
+pub mod maze;
+pub mod maze_graph;
+pub mod maze_image;
+pub mod maze_pprint;
+pub mod maze_svg;
+pub mod priority_queue;
+pub mod progress;
+pub mod queue;
+pub mod random;
+pub mod solvers;
+pub mod stack;
+pub mod topologies;
+pub mod validation;
@@ -0,0 +1,56 @@
+use crate::topologies::topology::Topology;
+
+/// Set of carved passages between logical cells of a topology.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Passages {
+    links: Vec<Vec<usize>>,
+}
+
+impl Passages {
+    pub fn new(cell_count: usize) -> Self {
+        Self {
+            links: vec![Vec::new(); cell_count],
+        }
+    }
+
+    pub fn link(&mut self, a: usize, b: usize) {
+        if !self.is_linked(a, b) {
+            self.links[a].push(b);
+            self.links[b].push(a);
+        }
+    }
+
+    pub fn unlink(&mut self, a: usize, b: usize) {
+        self.links[a].retain(|&n| n != b);
+        self.links[b].retain(|&n| n != a);
+    }
+
+    pub fn is_linked(&self, a: usize, b: usize) -> bool {
+        self.links[a].contains(&b)
+    }
+
+    pub fn links(&self, cell: usize) -> &[usize] {
+        &self.links[cell]
+    }
+}
+
+/// Maze on an arbitrary topology, storing passages between logical cells
+/// instead of wall blocks.
+#[derive(Debug, Clone)]
+pub struct LinkedMaze<T: Topology> {
+    pub topology: T,
+    pub passages: Passages,
+    pub start: usize,
+    pub end: usize,
+}
+
+impl<T: Topology> LinkedMaze<T> {
+    pub fn new(topology: T, passages: Passages, start: usize, end: usize) -> Self {
+        Self {
+            topology,
+            passages,
+            start,
+            end,
+        }
+    }
+}
@@ -0,0 +1,343 @@
+use crate::colors::theme;
+use crate::linked_maze::LinkedMaze;
+use crate::topologies::topology::{PassageMarker, Topology, Wall, WallShape};
+use image::{ImageBuffer, Rgb, RgbImage};
+use std::fmt::Write;
+use std::io;
+
+/// Blank border around the maze, in canvas units.
+const MARGIN: f64 = 0.5;
+
+pub fn save_linked_maze_to_image_scaled<T: Topology>(
+    maze: &LinkedMaze<T>,
+    file_path: &str,
+    scale: u32,
+) -> Result<(), image::ImageError> {
+    save_linked_maze_with_path_to_image_scaled(maze, &[], file_path, scale)
+}
+
+pub fn save_linked_maze_with_path_to_image_scaled<T: Topology>(
+    maze: &LinkedMaze<T>,
+    path: &[usize],
+    file_path: &str,
+    scale: u32,
+) -> Result<(), image::ImageError> {
+    let theme = theme();
+    let scale = scale as f64;
+    let (width, height) = maze.topology.bounds();
+    let mut img: RgbImage = ImageBuffer::from_pixel(
+        ((width + 2.0 * MARGIN) * scale).ceil() as u32,
+        ((height + 2.0 * MARGIN) * scale).ceil() as u32,
+        theme.path,
+    );
+    let to_pixels = |(x, y): (f64, f64)| ((x + MARGIN) * scale, (y + MARGIN) * scale);
+
+    let wall_thickness = (scale * 0.1).max(1.0);
+    for wall in closed_walls(maze) {
+        // keep arc segments around two pixels long
+        let points = wall.shape.to_polyline(2.0 / scale);
+        for pair in points.windows(2) {
+            draw_segment(
+                &mut img,
+                to_pixels(pair[0]),
+                to_pixels(pair[1]),
+                wall_thickness,
+                theme.wall,
+            );
+        }
+    }
+
+    for (cell, marker) in passage_markers(maze) {
+        let [a, b, c] = marker_triangle(maze.topology.center(cell), marker);
+        fill_triangle(
+            &mut img,
+            [to_pixels(a), to_pixels(b), to_pixels(c)],
+            theme.stairs,
+        );
+    }
+
+    let path_thickness = (scale * 0.25).max(1.0);
+    for pair in drawn_path_segments(maze, path) {
+        draw_segment(
+            &mut img,
+            to_pixels(maze.topology.center(pair[0])),
+            to_pixels(maze.topology.center(pair[1])),
+            path_thickness,
+            theme.solution_path,
+        );
+    }
+
+    let marker_radius = (scale * 0.3).max(1.0);
+    for (cell, color) in [(maze.start, theme.start), (maze.end, theme.end)] {
+        let center = to_pixels(maze.topology.center(cell));
+        draw_segment(&mut img, center, center, marker_radius * 2.0, color);
+    }
+
+    img.save(file_path)
+}
+
+pub fn save_linked_maze_to_svg<T: Topology>(
+    maze: &LinkedMaze<T>,
+    file_path: &str,
+    scale: u32,
+) -> io::Result<()> {
+    save_linked_maze_with_path_to_svg(maze, &[], file_path, scale)
+}
+
+pub fn save_linked_maze_with_path_to_svg<T: Topology>(
+    maze: &LinkedMaze<T>,
+    path: &[usize],
+    file_path: &str,
+    scale: u32,
+) -> io::Result<()> {
+    std::fs::write(file_path, linked_maze_to_svg(maze, path, scale))
+}
+
+/// Renders the maze, and optionally a path through it, as an SVG document.
+pub fn linked_maze_to_svg<T: Topology>(maze: &LinkedMaze<T>, path: &[usize], scale: u32) -> String {
+    let theme = theme();
+    let scale = scale as f64;
+    let (width, height) = maze.topology.bounds();
+    let to_pixels = |(x, y): (f64, f64)| ((x + MARGIN) * scale, (y + MARGIN) * scale);
+    let mut svg = String::new();
+
+    let _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w:.0}" height="{h:.0}" viewBox="0 0 {w:.2} {h:.2}">"#,
+        w = (width + 2.0 * MARGIN) * scale,
+        h = (height + 2.0 * MARGIN) * scale,
+    );
+    let _ = writeln!(
+        svg,
+        r#"<rect width="100%" height="100%" fill="{}"/>"#,
+        svg_color(theme.path)
+    );
+
+    let mut walls = String::new();
+    for wall in closed_walls(maze) {
+        match wall.shape {
+            WallShape::Line { from, to } => {
+                let (x1, y1) = to_pixels(from);
+                let (x2, y2) = to_pixels(to);
+                let _ = write!(walls, "M{:.2} {:.2}L{:.2} {:.2}", x1, y1, x2, y2);
+            }
+            WallShape::Arc {
+                center,
+                radius,
+                start_angle,
+                end_angle,
+            } => {
+                let point = |angle: f64| {
+                    to_pixels((
+                        center.0 + radius * angle.cos(),
+                        center.1 + radius * angle.sin(),
+                    ))
+                };
+                let (x1, y1) = point(start_angle);
+                let (x2, y2) = point(end_angle);
+                let large_arc = u8::from(end_angle - start_angle > std::f64::consts::PI);
+                let _ = write!(
+                    walls,
+                    "M{:.2} {:.2}A{r:.2} {r:.2} 0 {} 1 {:.2} {:.2}",
+                    x1,
+                    y1,
+                    large_arc,
+                    x2,
+                    y2,
+                    r = radius * scale
+                );
+            }
+        }
+    }
+    let _ = writeln!(
+        svg,
+        r#"<path d="{}" stroke="{}" stroke-width="{:.2}" stroke-linecap="round" fill="none"/>"#,
+        walls,
+        svg_color(theme.wall),
+        (scale * 0.1).max(1.0)
+    );
+
+    for (cell, marker) in passage_markers(maze) {
+        let points: Vec<String> = marker_triangle(maze.topology.center(cell), marker)
+            .into_iter()
+            .map(|point| {
+                let (x, y) = to_pixels(point);
+                format!("{:.2},{:.2}", x, y)
+            })
+            .collect();
+        let _ = writeln!(
+            svg,
+            r#"<polygon points="{}" fill="{}"/>"#,
+            points.join(" "),
+            svg_color(theme.stairs)
+        );
+    }
+
+    let mut path_data = String::new();
+    for pair in drawn_path_segments(maze, path) {
+        let (x1, y1) = to_pixels(maze.topology.center(pair[0]));
+        let (x2, y2) = to_pixels(maze.topology.center(pair[1]));
+        let _ = write!(path_data, "M{:.2} {:.2}L{:.2} {:.2}", x1, y1, x2, y2);
+    }
+    if !path_data.is_empty() {
+        let _ = writeln!(
+            svg,
+            r#"<path d="{}" stroke="{}" stroke-width="{:.2}" stroke-linecap="round" fill="none"/>"#,
+            path_data,
+            svg_color(theme.solution_path),
+            (scale * 0.25).max(1.0)
+        );
+    }
+
+    for (cell, color) in [(maze.start, theme.start), (maze.end, theme.end)] {
+        let (x, y) = to_pixels(maze.topology.center(cell));
+        let _ = writeln!(
+            svg,
+            r#"<circle cx="{:.2}" cy="{:.2}" r="{:.2}" fill="{}"/>"#,
+            x,
+            y,
+            (scale * 0.3).max(1.0),
+            svg_color(color)
+        );
+    }
+
+    svg.push_str("</svg>\n");
+    svg
+}
+
+// walls that are not opened by a passage
+fn closed_walls<T: Topology>(maze: &LinkedMaze<T>) -> Vec<Wall> {
+    (0..maze.topology.cell_count())
+        .flat_map(|cell| {
+            maze.topology.walls(cell).into_iter().filter(move |wall| {
+                !wall
+                    .neighbor
+                    .is_some_and(|n| maze.passages.is_linked(cell, n))
+            })
+        })
+        .collect()
+}
+
+// markers for passages that are not drawn as openings, such as stairs
+fn passage_markers<T: Topology>(maze: &LinkedMaze<T>) -> Vec<(usize, PassageMarker)> {
+    (0..maze.topology.cell_count())
+        .flat_map(|cell| {
+            maze.passages
+                .links(cell)
+                .iter()
+                .filter_map(move |&n| maze.topology.passage_marker(cell, n).map(|m| (cell, m)))
+        })
+        .collect()
+}
+
+// path steps between cells drawn next to each other; steps through markers are skipped
+fn drawn_path_segments<'a, T: Topology>(
+    maze: &'a LinkedMaze<T>,
+    path: &'a [usize],
+) -> impl Iterator<Item = &'a [usize]> {
+    path.windows(2)
+        .filter(|pair| maze.topology.passage_marker(pair[0], pair[1]).is_none())
+}
+
+// triangle pointing up in the upper half of the cell, or down in the lower half
+fn marker_triangle(center: (f64, f64), marker: PassageMarker) -> [(f64, f64); 3] {
+    let (cx, cy) = center;
+    let dir = match marker {
+        PassageMarker::Up => -1.0,
+        PassageMarker::Down => 1.0,
+    };
+    [
+        (cx, cy + 0.4 * dir),
+        (cx - 0.25, cy + 0.05 * dir),
+        (cx + 0.25, cy + 0.05 * dir),
+    ]
+}
+
+pub(crate) fn svg_color(color: Rgb<u8>) -> String {
+    format!("#{:02x}{:02x}{:02x}", color.0[0], color.0[1], color.0[2])
+}
+
+/// Draws a segment with round caps by filling every pixel whose center lies
+/// within `thickness / 2` of it. A zero-length segment draws a disc.
+pub(crate) fn draw_segment(
+    img: &mut RgbImage,
+    from: (f64, f64),
+    to: (f64, f64),
+    thickness: f64,
+    color: Rgb<u8>,
+) {
+    let radius = thickness / 2.0;
+    let min_x = (from.0.min(to.0) - radius).floor().max(0.0) as u32;
+    let min_y = (from.1.min(to.1) - radius).floor().max(0.0) as u32;
+    let max_x = ((from.0.max(to.0) + radius).ceil() as u32).min(img.width());
+    let max_y = ((from.1.max(to.1) + radius).ceil() as u32).min(img.height());
+
+    let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+    let length_sq = dx * dx + dy * dy;
+    for y in min_y..max_y {
+        for x in min_x..max_x {
+            let (px, py) = (x as f64 + 0.5, y as f64 + 0.5);
+            let t = if length_sq > 0.0 {
+                (((px - from.0) * dx + (py - from.1) * dy) / length_sq).clamp(0.0, 1.0)
+            } else {
+                0.0
+            };
+            let (cx, cy) = (from.0 + t * dx, from.1 + t * dy);
+            if (px - cx).powi(2) + (py - cy).powi(2) <= radius * radius {
+                img.put_pixel(x, y, color);
+            }
+        }
+    }
+}
+
+/// Fills every pixel whose center lies inside the triangle.
+pub(crate) fn fill_triangle(img: &mut RgbImage, corners: [(f64, f64); 3], color: Rgb<u8>) {
+    let [a, b, c] = corners;
+    let min_x = a.0.min(b.0).min(c.0).floor().max(0.0) as u32;
+    let min_y = a.1.min(b.1).min(c.1).floor().max(0.0) as u32;
+    let max_x = (a.0.max(b.0).max(c.0).ceil() as u32).min(img.width());
+    let max_y = (a.1.max(b.1).max(c.1).ceil() as u32).min(img.height());
+
+    let edge = |p: (f64, f64), q: (f64, f64), r: (f64, f64)| {
+        (q.0 - p.0) * (r.1 - p.1) - (q.1 - p.1) * (r.0 - p.0)
+    };
+    for y in min_y..max_y {
+        for x in min_x..max_x {
+            let p = (x as f64 + 0.5, y as f64 + 0.5);
+            let (e1, e2, e3) = (edge(a, b, p), edge(b, c, p), edge(c, a, p));
+            let inside =
+                (e1 >= 0.0 && e2 >= 0.0 && e3 >= 0.0) || (e1 <= 0.0 && e2 <= 0.0 && e3 <= 0.0);
+            if inside {
+                img.put_pixel(x, y, color);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::linked_maze::Passages;
+    use crate::topologies::hex::HexTopology;
+
+    #[test]
+    fn test_linked_maze_svg_skips_open_walls() {
+        let hex = HexTopology::new(1, 2);
+        let closed = LinkedMaze::new(hex, Passages::new(2), 0, 1);
+        let mut passages = Passages::new(2);
+        passages.link(0, 1);
+        let open = LinkedMaze::new(hex, passages, 0, 1);
+
+        let closed_svg = linked_maze_to_svg(&closed, &[], 10);
+        let open_svg = linked_maze_to_svg(&open, &[0, 1], 10);
+        let wall_count = |svg: &str| {
+            svg.lines()
+                .find(|line| line.contains(&svg_color(theme().wall)) && line.starts_with("<path"))
+                .map_or(0, |line| line.matches('M').count())
+        };
+        assert_eq!(wall_count(&closed_svg), wall_count(&open_svg) + 1);
+        let path_color = svg_color(theme().solution_path);
+        assert!(open_svg.contains(&path_color));
+        assert!(!closed_svg.contains(&path_color));
+    }
+}
@@ -1,23 +1,37 @@
-mod cell;
-mod colors;
-mod generators;
-mod gif_generator;
-mod maze;
-mod maze_image;
-mod maze_pprint;
-mod priority_queue;
-mod progress;
-mod queue;
-mod solvers;
-mod stack;
-
-use crate::generators::aldous_broder::AldousBroderMazeGenerator;
-use crate::generators::generator::MazeGenerator;
-use crate::generators::kruskal::KruskalMazeGenerator;
-use crate::generators::prims::PrimMazeGenerator;
-use crate::generators::recursive_backtracker::RecursiveBacktrackerMazeGenerator;
-use crate::solvers::solver::Solver;
-use clap::Parser;
+use clap::{Parser, Subcommand};
+use maze_rust::animation::{AnimationFormat, AnimationPolicy, Easing, FrameTiming};
+use maze_rust::batch::{run_batch, BatchConfig};
+use maze_rust::bit_grid::BitGrid;
+use maze_rust::csr_graph::CsrGraph;
+use maze_rust::generators::aldous_broder::AldousBroderMazeGenerator;
+use maze_rust::generators::checkpoints::CheckpointMazeGenerator;
+use maze_rust::generators::generator::{BlockGrid, MazeGenerator, TopologyMazeGenerator};
+use maze_rust::generators::keys_doors::KeyDoorMazeGenerator;
+use maze_rust::generators::kruskal::KruskalMazeGenerator;
+use maze_rust::generators::one_way::OneWayMazeGenerator;
+use maze_rust::generators::prims::PrimMazeGenerator;
+use maze_rust::generators::recursive_backtracker::RecursiveBacktrackerMazeGenerator;
+use maze_rust::generators::tiled::TiledMazeGenerator;
+use maze_rust::generators::weave::WeaveMazeGenerator;
+use maze_rust::generators::wilson::WilsonMazeGenerator;
+use maze_rust::linked_maze::LinkedMaze;
+use maze_rust::mask::Mask;
+use maze_rust::maze::{Maze, MazeError, Wrap};
+use maze_rust::maze_graph::{KeyDoorGraph, MazeGraph};
+use maze_rust::solvers::dstar_lite::random_changes;
+use maze_rust::solvers::multi_agent::{plan_agents, random_agents};
+use maze_rust::solvers::routes::{count_shortest_paths, k_shortest_paths};
+use maze_rust::solvers::solver::{SolveResult, Solver};
+use maze_rust::solvers::tour::solve_tour;
+use maze_rust::topologies::grid3d::Grid3dTopology;
+use maze_rust::topologies::hex::HexTopology;
+use maze_rust::topologies::polar::PolarTopology;
+use maze_rust::topologies::square::SquareTopology;
+use maze_rust::topologies::topology::Topology;
+use maze_rust::validation::{parse_path, validate_path};
+use maze_rust::{
+    colors, gif_generator, linked_maze_image, maze_image, maze_pprint, maze_svg, progress, solvers,
+};
 use std::time::Instant;
 
 /// Maze CLI arguments
@@ -56,14 +70,48 @@ struct Args {
     #[arg(long, default_value_t = true)]
     generate_gifs: bool,
 
-    /// Delay in ms for generator GIF frames
+    /// Animation format: "gif" (palette of 256 colors per frame), "apng" or "webp"
+    /// (full color, lossless), or for video editors "frames" (a directory of
+    /// numbered PNG files) or "y4m" (raw YUV video)
+    #[arg(long, default_value = "gif")]
+    animation_format: String,
+
+    /// Delay in hundredths of a second for generator animation frames
     #[arg(long, default_value_t = 1)]
     gif_generator_delay: u16,
 
-    /// Delay in ms for solver GIF frames
+    /// Delay in hundredths of a second for solver animation frames
     #[arg(long, default_value_t = 10)]
     gif_solver_delay: u16,
 
+    /// Frames per second of "frames" and "y4m" animations; each animation frame
+    /// is repeated for as many video frames as its delay lasts
+    #[arg(long, default_value_t = 30)]
+    frame_rate: u32,
+
+    /// Seconds the last frame of every animation stays up
+    #[arg(long, default_value_t = 0.0)]
+    hold_last: f32,
+
+    /// Most frames the steps of a generator or solver animation are spread over
+    #[arg(long, default_value_t = 500)]
+    max_frames: usize,
+
+    /// Steps of a generator or solver animation shown per frame, instead of
+    /// fitting them into --max-frames
+    #[arg(long)]
+    steps_per_frame: Option<usize>,
+
+    /// Target length in seconds of the stepped part of generator and solver
+    /// animations, replacing their frame delays
+    #[arg(long)]
+    duration: Option<f32>,
+
+    /// Spread of steps over frames: "linear", or "quadratic" and "cubic" to show
+    /// early steps more slowly than late ones
+    #[arg(long, default_value = "linear")]
+    easing: String,
+
     /// Output directory for images/GIFs
     #[arg(long, default_value = "mazes")]
     output_dir: String,
@@ -83,34 +131,294 @@ struct Args {
     /// Solver algorithms to run (comma-delimited)
     #[arg(long, value_delimiter = ',', default_value = "astar,bfs,dfs,dijkstra")]
     solvers: Vec<String>,
+
+    /// Grid topology: "square" (block maze), "hex" (width x height hexagonal cells),
+    /// "polar" (height concentric rings, rows are rings and columns are cells in a ring)
+    /// "3d" (levels floors of width x height cells connected by stairs) or "thin"
+    /// (width x height square cells joined by passages, drawn with thin walls)
+    #[arg(long, default_value = "square")]
+    topology: String,
+
+    /// Number of floors for the "3d" topology
+    #[arg(long, default_value_t = 3)]
+    levels: usize,
+
+    /// Edge wrapping for the "square" topology: "plane", "cylinder" (left/right edges
+    /// wrap) or "torus" (all edges wrap); wrapped axes need an even size
+    #[arg(long, default_value = "plane")]
+    wrap: String,
+
+    /// Fraction of cells (0.0 to 1.0) the "weave" generator tries to turn into
+    /// crossings where one corridor tunnels under another
+    #[arg(long, default_value_t = 0.3)]
+    weave_density: f32,
+
+    /// Shape for "square" mazes: a PNG with one pixel per cell (black = outside) or
+    /// an ASCII file (`X` = outside, `.` = inside); sets the maze size
+    #[arg(long)]
+    mask: Option<String>,
+
+    /// Number of colored doors (up to 4) to place on the solution path of "square"
+    /// mazes, each with its key hidden in a side branch
+    #[arg(long, default_value_t = 0)]
+    doors: usize,
+
+    /// Fraction of straight corridor cells (0.0 to 1.0) of "square" mazes turned into
+    /// one-way passages, keeping the end reachable from the start
+    #[arg(long, default_value_t = 0.0)]
+    one_way: f32,
+
+    /// Number of checkpoints to scatter over "square" mazes; solvers then find the
+    /// shortest tour from the start through all of them to the end
+    #[arg(long, default_value_t = 0)]
+    checkpoints: usize,
+
+    /// Number of shortest distinct routes of "square" mazes to overlay in one image,
+    /// also reporting how many shortest routes there are
+    #[arg(long, default_value_t = 0)]
+    routes: usize,
+
+    /// Number of agents sharing each "square" maze: the first runs from start to end,
+    /// the others between random cells; their collision-free moves are saved as a GIF
+    #[arg(long, default_value_t = 0)]
+    agents: usize,
+
+    /// Number of cells of each "square" maze to change while an agent walks it,
+    /// saved as a GIF of D* Lite repairing the agent's path
+    #[arg(long, default_value_t = 0)]
+    replan: usize,
+
+    /// Generate a "square" maze with the recursive backtracker straight into a
+    /// bit-packed grid and only solve it, for mazes too large for images
+    #[arg(long, default_value_t = false)]
+    compact: bool,
+
+    /// Generate a "square" maze in tiles of this many cells per side, carved in
+    /// parallel by each chosen generator and stitched into one perfect maze
+    /// (0 = off); the result is solved like a `--compact` maze
+    #[arg(long, default_value_t = 0)]
+    tile_size: usize,
+
+    /// With `--tile-size`, stream the tiles straight into a PNG instead of keeping
+    /// the maze in memory, skipping the solvers
+    #[arg(long, default_value_t = false)]
+    stream_png: bool,
+
+    /// Color theme of images, animations and terminal output: "tab10",
+    /// "high-contrast", "colorblind", "dark", "grayscale" or a TOML theme file
+    #[arg(long, default_value = "tab10")]
+    theme: String,
+
+    /// Print each solved square maze in the terminal, colored with the theme
+    #[arg(long, default_value_t = false)]
+    print: bool,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Check that a path file solves a "square" maze instead of generating mazes
+    Verify {
+        /// Maze in the text format, or a PNG saved with the same `--scale`
+        maze: String,
+
+        /// Path file with one `row,col` cell per line
+        path: String,
+
+        /// Also require the path to be as short as possible
+        #[arg(long)]
+        shortest: bool,
+    },
+    /// Generate many "square" mazes into the output directory in parallel, solving
+    /// each with every solver and listing them in a `manifest.jsonl`; rerunning the
+    /// same command resumes an interrupted batch
+    Batch {
+        /// Number of mazes to generate
+        #[arg(long)]
+        count: usize,
+
+        /// Seed every maze is derived from, so a batch can be generated again
+        #[arg(long, default_value_t = 0)]
+        seed: u64,
+
+        /// Worker threads (defaults to the number of CPUs)
+        #[arg(long)]
+        jobs: Option<usize>,
+    },
 }
 
 fn main() {
     let args = Args::parse();
+    match colors::load_theme(&args.theme) {
+        Ok(theme) => colors::set_theme(theme),
+        Err(e) => {
+            eprintln!("Failed to load theme: {}", e);
+            std::process::exit(1);
+        }
+    }
+    if let Some(Command::Verify {
+        maze,
+        path,
+        shortest,
+    }) = &args.command
+    {
+        verify(maze, path, *shortest, args.scale);
+        return;
+    }
     let width = args.width;
     let height = args.height;
-    let start = (args.start_row.unwrap_or(1), args.start_col.unwrap_or(1));
-    let end = (
-        args.end_row.unwrap_or(height - 2),
-        args.end_col.unwrap_or(width - 2),
-    );
+    let Some(wrap) = Wrap::from_name(&args.wrap) else {
+        eprintln!("Unknown wrap '{}'", args.wrap);
+        std::process::exit(1);
+    };
+    if (wrap.horizontal() && !width.is_multiple_of(2))
+        || (wrap.vertical() && !height.is_multiple_of(2))
+    {
+        eprintln!("Wrapped axes need an even size to line up cells across the seam");
+        std::process::exit(1);
+    }
+    if args.stream_png && args.tile_size == 0 {
+        eprintln!("--stream-png needs --tile-size to generate the maze in tiles");
+        std::process::exit(1);
+    }
     let scale = args.scale;
     let generate_gifs = args.generate_gifs;
-    let gif_generator_delay = args.gif_generator_delay;
-    let gif_solver_delay = args.gif_solver_delay;
-    let output_dir = args.output_dir;
+    let Some(animation_format) = AnimationFormat::from_name(&args.animation_format) else {
+        eprintln!("Unknown animation format '{}'", args.animation_format);
+        std::process::exit(1);
+    };
+    let animation_extension = animation_format.extension();
+    let frame_timing = |delay| FrameTiming {
+        delay,
+        hold_last: (args.hold_last.max(0.0) * 100.0)
+            .round()
+            .min(u16::MAX as f32) as u16,
+        frame_rate: args.frame_rate,
+    };
+    let Some(easing) = Easing::from_name(&args.easing) else {
+        eprintln!("Unknown easing '{}'", args.easing);
+        std::process::exit(1);
+    };
+    let animation_policy = |delay| AnimationPolicy {
+        timing: frame_timing(delay),
+        max_frames: args.max_frames,
+        steps_per_frame: args.steps_per_frame,
+        duration: args
+            .duration
+            .map(|seconds| (seconds.max(0.0) * 100.0).round() as u32),
+        easing,
+    };
+    let generator_policy = &animation_policy(args.gif_generator_delay);
+    let solver_policy = &animation_policy(args.gif_solver_delay);
+    let solver_timing = &solver_policy.timing;
+    let output_dir = args.output_dir.clone();
     // Ensure output directory exists
     if let Err(e) = std::fs::create_dir_all(&output_dir) {
         eprintln!("Failed to create output directory '{}': {}", output_dir, e);
         std::process::exit(1);
     }
+    if let Some(Command::Batch { count, seed, jobs }) = &args.command {
+        run_batch_command(&args, square_grid(&args, wrap), *count, *seed, *jobs);
+        return;
+    }
+
+    match args.topology.as_str() {
+        "square" => {}
+        "hex" => {
+            let (start, end) = grid_endpoints(&args, height, width);
+            let hex = HexTopology::new(height, width);
+            let (start, end) = (hex.index(start.0, start.1), hex.index(end.0, end.1));
+            run_topology_mazes(&args, "hex", hex, start, end);
+            return;
+        }
+        "polar" => {
+            // start in the center, end on the rim by default
+            if height == 0 {
+                eprintln!("A polar maze needs a height of at least 1 ring");
+                std::process::exit(1);
+            }
+            let polar = PolarTopology::new(height);
+            let start = (args.start_row.unwrap_or(0), args.start_col.unwrap_or(0));
+            let end = (
+                args.end_row.unwrap_or(polar.rings() - 1),
+                args.end_col.unwrap_or(0),
+            );
+            // rows are rings and columns positions around a ring
+            for (name, (ring, position)) in [("Start", start), ("End", end)] {
+                if ring >= polar.rings() || position >= polar.ring_size(ring) {
+                    eprintln!("{} ({}, {}) lies outside the maze", name, ring, position);
+                    std::process::exit(1);
+                }
+            }
+            let start = polar.index(start.0, start.1);
+            let end = polar.index(end.0, end.1);
+            run_topology_mazes(&args, "polar", polar, start, end);
+            return;
+        }
+        "3d" => {
+            // start on the bottom floor, end on the top floor
+            if args.levels == 0 {
+                eprintln!("A 3d maze needs at least one level");
+                std::process::exit(1);
+            }
+            let (start, end) = grid_endpoints(&args, height, width);
+            let grid = Grid3dTopology::new(args.levels, height, width);
+            let start = grid.index(0, start.0, start.1);
+            let end = grid.index(args.levels - 1, end.0, end.1);
+            run_topology_mazes(&args, "3d", grid, start, end);
+            return;
+        }
+        "thin" => {
+            let (start, end) = grid_endpoints(&args, height, width);
+            let square = SquareTopology::new(height, width);
+            let (start, end) = (square.index(start.0, start.1), square.index(end.0, end.1));
+            run_topology_mazes(&args, "thin", square, start, end);
+            return;
+        }
+        other => {
+            eprintln!("Unknown topology '{}'", other);
+            std::process::exit(1);
+        }
+    }
+
+    if args.tile_size > 0 {
+        run_tiled_mazes(&args);
+        return;
+    }
+    if args.compact {
+        run_compact_maze(&args);
+        return;
+    }
+
+    let grid = square_grid(&args, wrap);
+    let default_start = grid.default_start();
+    let default_end = grid.default_end();
+    let start = (
+        args.start_row.unwrap_or(default_start.0),
+        args.start_col.unwrap_or(default_start.1),
+    );
+    let end = (
+        args.end_row.unwrap_or(default_end.0),
+        args.end_col.unwrap_or(default_end.1),
+    );
+    for (name, pos) in [("Start", start), ("End", end)] {
+        if let Err(MazeError::OutsideMask(row, col)) = grid.check_inside(pos) {
+            eprintln!("{} ({}, {}) lies outside the maze", name, row, col);
+            std::process::exit(1);
+        }
+    }
 
     // Resolve generator implementations from names
+    let weave = WeaveMazeGenerator::new(args.weave_density);
     let available_generators: &[(&str, &dyn MazeGenerator)] = &[
         ("prims", &PrimMazeGenerator),
         ("recursive_backtracker", &RecursiveBacktrackerMazeGenerator),
         ("kruskal", &KruskalMazeGenerator),
         ("aldous_broder", &AldousBroderMazeGenerator),
+        ("wilson", &WilsonMazeGenerator),
+        ("weave", &weave),
     ];
     let generators: Vec<(&str, &dyn MazeGenerator)> = args
         .generators
@@ -125,12 +433,25 @@ fn main() {
 
     for (generator_name, generator) in generators {
         println!("\n--- Generating maze with {} ---", generator_name);
+        let one_way = OneWayMazeGenerator::new(generator, args.one_way);
+        let generator: &dyn MazeGenerator = if args.one_way > 0.0 {
+            &one_way
+        } else {
+            generator
+        };
+        let with_checkpoints = CheckpointMazeGenerator::new(generator, args.checkpoints);
+        let generator: &dyn MazeGenerator = if args.checkpoints > 0 {
+            &with_checkpoints
+        } else {
+            generator
+        };
+        let keyed = KeyDoorMazeGenerator::new(generator, args.doors);
+        let generator: &dyn MazeGenerator = if args.doors > 0 { &keyed } else { generator };
         let mut tracker = progress::ProgressTracker::new(generate_gifs);
         let gen_start = Instant::now();
         let imperfect_percentage = args.imperfect_percentage;
-        let maze = generator.generate(
-            height,
-            width,
+        let maze = generator.generate_on(
+            &grid,
             Some(start),
             Some(end),
             imperfect_percentage,
@@ -140,20 +461,23 @@ fn main() {
         println!("Maze generation took: {:.2?}", gen_duration);
 
         if generate_gifs {
-            let gif_path = format!("{}/generated_maze_{}.gif", output_dir, generator_name);
-            println!("Saving generation GIF to {}...", gif_path);
+            let gif_path = format!(
+                "{}/generated_maze_{}.{}",
+                output_dir, generator_name, animation_extension
+            );
+            println!("Saving generation animation to {}...", gif_path);
             let gif_start = Instant::now();
             if let Err(e) = gif_generator::save_history_to_gif(
                 &maze,
                 &tracker.history,
                 &gif_path,
                 scale,
-                gif_generator_delay,
+                generator_policy,
             ) {
                 eprintln!("Error saving generation GIF: {}", e);
             }
             let gif_duration = gif_start.elapsed();
-            println!("Saving generation GIF took: {:.2?}", gif_duration);
+            println!("Saving generation animation took: {:.2?}", gif_duration);
         }
 
         let generated_maze_path = format!("{}/generated_maze_{}.png", output_dir, generator_name);
@@ -162,9 +486,64 @@ fn main() {
             eprintln!("Error saving generated maze: {}", e);
             continue;
         }
+        let generated_svg_path = format!("{}/generated_maze_{}.svg", output_dir, generator_name);
+        if let Err(e) = maze_svg::save_maze_to_svg(&maze, &generated_svg_path, scale) {
+            eprintln!("Error saving generated maze SVG: {}", e);
+        }
         let save_duration = save_start.elapsed();
         println!("Saving generated maze took: {:.2?}", save_duration);
 
+        if args.routes > 0 {
+            let routes_path = format!("{}/generated_maze_{}_routes", output_dir, generator_name);
+            save_routes(&maze, args.routes, &routes_path, scale);
+        }
+
+        if args.agents > 0 {
+            let agents = random_agents(&maze, args.agents);
+            let plan_start = Instant::now();
+            match plan_agents(&maze, &agents) {
+                Some(timeline) => {
+                    println!(
+                        "Planned {} agents over {} steps in {:.2?}",
+                        agents.len(),
+                        timeline[0].len() - 1,
+                        plan_start.elapsed()
+                    );
+                    let gif_path = format!(
+                        "{}/generated_maze_{}_agents.{}",
+                        output_dir, generator_name, animation_extension
+                    );
+                    if let Err(e) = gif_generator::save_agents_to_gif(
+                        &maze,
+                        &timeline,
+                        &gif_path,
+                        scale,
+                        solver_timing,
+                    ) {
+                        eprintln!("Error saving agents GIF: {}", e);
+                    }
+                }
+                None => println!("No collision-free plan found for {} agents", agents.len()),
+            }
+        }
+
+        if args.replan > 0 {
+            let changes = random_changes(&maze, args.replan);
+            let gif_path = format!(
+                "{}/generated_maze_{}_replanning.{}",
+                output_dir, generator_name, animation_extension
+            );
+            if let Err(e) = gif_generator::save_replanning_to_gif(
+                &maze,
+                &changes,
+                &gif_path,
+                scale,
+                solver_timing,
+            ) {
+                eprintln!("Error saving replanning GIF: {}", e);
+            }
+        }
+
         // Resolve solver implementations from names
         let available_solvers: &[(&str, &dyn Solver)] = &[
             ("astar", &solvers::astar::AstarSolver),
@@ -178,35 +557,63 @@ fn main() {
             .filter_map(|name| available_solvers.iter().find(|(n, _)| n == name).copied())
             .collect();
 
+        // one adjacency shared by all solvers of this maze
+        let graph_start = Instant::now();
+        let graph = CsrGraph::from_maze(&maze);
+        println!(
+            "\nSolver graph of {} nodes built in {:.2?}",
+            graph.node_count(),
+            graph_start.elapsed()
+        );
+
+        let mut results = Vec::new();
         for (solver_name, solver) in &solvers {
             println!("\n--- Solving with {} ---", solver_name);
             let mut tracker = progress::ProgressTracker::new(generate_gifs);
-            let solve_start = Instant::now();
-            match solver.solve(&maze, Some(&mut tracker)) {
+            let result = if maze.checkpoints().is_empty() {
+                solver.search(&graph, Some(&mut tracker)).positions(&graph)
+            } else {
+                let solve_start = Instant::now();
+                let tour = solve_tour(&maze, *solver, Some(&mut tracker));
+                SolveResult {
+                    cost: tour.as_ref().map(|tour| tour.length),
+                    nodes_expanded: tour.as_ref().map_or(0, |tour| tour.nodes_expanded),
+                    max_frontier: tour.as_ref().map_or(0, |tour| tour.max_frontier),
+                    optimal: tour.as_ref().is_some_and(|tour| tour.optimal),
+                    path: tour.map(|tour| tour.path),
+                    elapsed: solve_start.elapsed(),
+                }
+            };
+            match &result.path {
                 Some(path) => {
-                    let solve_duration = solve_start.elapsed();
                     println!("Path found with {}.", solver_name);
-                    println!("Solving took: {:.2?}", solve_duration);
+                    println!("Solving took: {:.2?}", result.elapsed);
+                    if args.print {
+                        print!(
+                            "{}",
+                            maze_pprint::display_maze_with_path_colored(&maze, path)
+                        );
+                    }
 
                     if generate_gifs {
                         let gif_path = format!(
-                            "{}/generated_maze_{}_solved_{}.gif",
-                            output_dir, generator_name, solver_name
+                            "{}/generated_maze_{}_solved_{}.{}",
+                            output_dir, generator_name, solver_name, animation_extension
                         );
-                        println!("Saving solving GIF to {}...", gif_path);
+                        println!("Saving solving animation to {}...", gif_path);
                         let gif_start = Instant::now();
                         if let Err(e) = gif_generator::save_solver_history_to_gif(
                             &maze,
                             &tracker.history,
-                            &path,
+                            path,
                             &gif_path,
                             scale,
-                            gif_solver_delay,
+                            solver_policy,
                         ) {
                             eprintln!("Error saving solving GIF: {}", e);
                         }
                         let gif_duration = gif_start.elapsed();
-                        println!("Saving solving GIF took: {:.2?}", gif_duration);
+                        println!("Saving solving animation took: {:.2?}", gif_duration);
                     }
 
                     let solved_image_path = format!(
@@ -216,22 +623,428 @@ fn main() {
                     let save_path_start = Instant::now();
                     if let Err(e) = maze_image::save_maze_with_path_to_image_scaled(
                         &maze,
-                        &path,
+                        path,
                         &solved_image_path,
                         scale,
                     ) {
                         eprintln!("Error saving solved maze: {}", e);
+                    } else if let Err(e) = maze_svg::save_maze_with_path_to_svg(
+                        &maze,
+                        path,
+                        &solved_image_path.replace(".png", ".svg"),
+                        scale,
+                    ) {
+                        eprintln!("Error saving solved maze SVG: {}", e);
                     } else {
                         let save_path_duration = save_path_start.elapsed();
                         println!("Saving solved maze took: {:.2?}", save_path_duration);
                     }
                 }
                 None => {
-                    let solve_duration = solve_start.elapsed();
                     println!("No path found with {}.", solver_name);
-                    println!("Solving took: {:.2?}", solve_duration);
+                    println!("Solving took: {:.2?}", result.elapsed);
                 }
             }
+            results.push((*solver_name, result));
         }
+        print_comparison(&results);
+    }
+}
+
+/// Prints one row per solver with the length and cost of its path and the work its search took.
+fn print_comparison<N>(results: &[(&str, SolveResult<N>)]) {
+    if results.is_empty() {
+        return;
+    }
+    println!(
+        "\n{:<10} {:>9} {:>9} {:>9} {:>9} {:>8} {:>12}",
+        "solver", "length", "cost", "expanded", "frontier", "optimal", "time"
+    );
+    for (name, result) in results {
+        let length = result
+            .path
+            .as_ref()
+            .map_or("-".to_string(), |path| (path.len() - 1).to_string());
+        let cost = result.cost.map_or("-".to_string(), |cost| cost.to_string());
+        println!(
+            "{:<10} {:>9} {:>9} {:>9} {:>9} {:>8} {:>12}",
+            name,
+            length,
+            cost,
+            result.nodes_expanded,
+            result.max_frontier,
+            if result.optimal { "yes" } else { "no" },
+            format!("{:.2?}", result.elapsed)
+        );
+    }
+}
+
+/// Generates a maze into a bit-packed grid and compares the solvers on it.
+fn run_compact_maze(args: &Args) {
+    println!(
+        "\n--- Generating compact {}x{} maze with recursive_backtracker ---",
+        args.width, args.height
+    );
+    let gen_start = Instant::now();
+    let Ok(grid) = RecursiveBacktrackerMazeGenerator.generate_compact(args.width, args.height)
+    else {
+        eprintln!("A compact maze needs a width and height of at least 3");
+        std::process::exit(1);
+    };
+    println!("Maze generation took: {:.2?}", gen_start.elapsed());
+    solve_compact_maze(args, &grid);
+}
+
+fn solve_compact_maze(args: &Args, grid: &BitGrid) {
+    println!(
+        "Grid holds {} cells in {} KB",
+        grid.node_count(),
+        grid.node_count().div_ceil(8) / 1024
+    );
+
+    let available_solvers: &[(&str, &dyn Solver)] = &[
+        ("astar", &solvers::astar::AstarSolver),
+        ("bfs", &solvers::bfs_solver::BfsSolver),
+        ("dfs", &solvers::dfs_solver::DfsSolver),
+        ("dijkstra", &solvers::dijkstra::DijkstraSolver),
+    ];
+    let results: Vec<(&str, SolveResult<usize>)> = args
+        .solvers
+        .iter()
+        .filter_map(|name| available_solvers.iter().find(|(n, _)| n == name))
+        .map(|&(name, solver)| (name, solver.search(grid, None)))
+        .collect();
+    print_comparison(&results);
+}
+
+/// Generates a "square" maze per chosen generator in parallel tiles, then solves
+/// it or streams it into a PNG.
+fn run_tiled_mazes(args: &Args) {
+    let available_generators: &[(&str, &(dyn TopologyMazeGenerator + Sync))] = &[
+        ("prims", &PrimMazeGenerator),
+        ("recursive_backtracker", &RecursiveBacktrackerMazeGenerator),
+        ("kruskal", &KruskalMazeGenerator),
+        ("aldous_broder", &AldousBroderMazeGenerator),
+        ("wilson", &WilsonMazeGenerator),
+    ];
+    let jobs = std::thread::available_parallelism().map_or(1, |jobs| jobs.get());
+    for generator_name in &args.generators {
+        let Some(&(_, generator)) = available_generators
+            .iter()
+            .find(|(n, _)| n == generator_name)
+        else {
+            eprintln!("Generator '{}' cannot carve tiles", generator_name);
+            continue;
+        };
+        println!(
+            "\n--- Generating tiled {}x{} maze with {} in {} workers ---",
+            args.width, args.height, generator_name, jobs
+        );
+        let tiled = TiledMazeGenerator::new(generator, args.tile_size, jobs);
+        let gen_start = Instant::now();
+        if args.stream_png {
+            let file_path = format!("{}/maze_tiled_{}.png", args.output_dir, generator_name);
+            let written = std::fs::File::create(&file_path).and_then(|file| {
+                tiled.write_png(
+                    args.width,
+                    args.height,
+                    args.scale,
+                    std::io::BufWriter::new(file),
+                )
+            });
+            match written {
+                Ok(()) => println!(
+                    "Maze streamed to {} in {:.2?}",
+                    file_path,
+                    gen_start.elapsed()
+                ),
+                Err(e) => eprintln!("Error streaming maze image: {}", e),
+            }
+        } else {
+            let grid = tiled.generate(args.width, args.height);
+            println!("Maze generation took: {:.2?}", gen_start.elapsed());
+            solve_compact_maze(args, &grid);
+        }
+    }
+}
+
+fn run_batch_command(args: &Args, grid: BlockGrid, count: usize, seed: u64, jobs: Option<usize>) {
+    let weave = WeaveMazeGenerator::new(args.weave_density);
+    let available_generators: &[(&str, &(dyn MazeGenerator + Sync))] = &[
+        ("prims", &PrimMazeGenerator),
+        ("recursive_backtracker", &RecursiveBacktrackerMazeGenerator),
+        ("kruskal", &KruskalMazeGenerator),
+        ("aldous_broder", &AldousBroderMazeGenerator),
+        ("wilson", &WilsonMazeGenerator),
+        ("weave", &weave),
+    ];
+    let available_solvers: &[(&str, &(dyn Solver + Sync))] = &[
+        ("astar", &solvers::astar::AstarSolver),
+        ("bfs", &solvers::bfs_solver::BfsSolver),
+        ("dfs", &solvers::dfs_solver::DfsSolver),
+        ("dijkstra", &solvers::dijkstra::DijkstraSolver),
+    ];
+    let generators: Vec<_> = args
+        .generators
+        .iter()
+        .filter_map(|name| {
+            available_generators
+                .iter()
+                .find(|(n, _)| n == name)
+                .copied()
+        })
+        .collect();
+    let solvers: Vec<_> = args
+        .solvers
+        .iter()
+        .filter_map(|name| available_solvers.iter().find(|(n, _)| n == name).copied())
+        .collect();
+
+    let config = BatchConfig {
+        count,
+        seed,
+        grid,
+        imperfect_percentage: args.imperfect_percentage,
+        output_dir: args.output_dir.clone().into(),
+        jobs: jobs
+            .unwrap_or_else(|| std::thread::available_parallelism().map_or(1, |jobs| jobs.get())),
+        scale: args.scale,
+    };
+    println!(
+        "\n--- Generating a batch of {} mazes with {} workers ---",
+        count, config.jobs
+    );
+    let batch_start = Instant::now();
+    match run_batch(&config, &generators, &solvers) {
+        Ok(summary) => println!(
+            "Generated {} mazes and kept {} from an earlier run in {:.2?}",
+            summary.generated,
+            summary.skipped,
+            batch_start.elapsed()
+        ),
+        Err(e) => {
+            eprintln!("Batch failed: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Finds the `k` shortest routes through a maze and saves them overlaid as PNG and SVG.
+fn save_routes(maze: &Maze, k: usize, base_path: &str, scale: u32) {
+    let graph: &dyn MazeGraph = if maze.has_doors() {
+        &KeyDoorGraph::new(maze)
+    } else {
+        maze
+    };
+    let routes_start = Instant::now();
+    let routes: Vec<Vec<(usize, usize)>> = k_shortest_paths(graph, k)
+        .into_iter()
+        .map(|route| {
+            route
+                .nodes
+                .into_iter()
+                .map(|node| graph.position(node))
+                .collect()
+        })
+        .collect();
+    println!(
+        "Found {} shortest routes ({} of minimum length) in {:.2?}",
+        routes.len(),
+        count_shortest_paths(graph),
+        routes_start.elapsed()
+    );
+    let png_path = format!("{}.png", base_path);
+    if let Err(e) =
+        maze_image::save_maze_with_paths_to_image_scaled(maze, &routes, &png_path, scale)
+    {
+        eprintln!("Error saving routes image: {}", e);
+    }
+    let svg_path = format!("{}.svg", base_path);
+    if let Err(e) = maze_svg::save_maze_with_paths_to_svg(maze, &routes, &svg_path, scale) {
+        eprintln!("Error saving routes SVG: {}", e);
+    }
+}
+
+/// Block grid of a square maze, shaped by `--mask` if given; exits with an
+/// error for a grid too small to hold a cell or a mask that cannot be used.
+fn square_grid(args: &Args, wrap: Wrap) -> BlockGrid {
+    match &args.mask {
+        None => match BlockGrid::new(args.height, args.width) {
+            Ok(grid) => grid.with_wrap(wrap),
+            Err(_) => {
+                eprintln!("A square maze needs a width and height of at least 3");
+                std::process::exit(1);
+            }
+        },
+        Some(_) if wrap != Wrap::None => {
+            eprintln!("Masks cannot be combined with wrapping edges");
+            std::process::exit(1);
+        }
+        Some(path) => match load_mask(path) {
+            Ok(mask) if mask.is_connected() => BlockGrid::masked(mask),
+            Ok(_) => {
+                eprintln!("Mask '{}' must be a single connected shape", path);
+                std::process::exit(1);
+            }
+            Err(e) => {
+                eprintln!("Failed to load mask '{}': {}", path, e);
+                std::process::exit(1);
+            }
+        },
+    }
+}
+
+/// Start and end (row, column) on a `rows` x `cols` grid topology, top-left
+/// and bottom-right by default; exits with an error for a cell off the grid.
+fn grid_endpoints(args: &Args, rows: usize, cols: usize) -> ((usize, usize), (usize, usize)) {
+    if rows == 0 || cols == 0 {
+        eprintln!("Width and height must be at least 1");
+        std::process::exit(1);
+    }
+    let start = (args.start_row.unwrap_or(0), args.start_col.unwrap_or(0));
+    let end = (
+        args.end_row.unwrap_or(rows - 1),
+        args.end_col.unwrap_or(cols - 1),
+    );
+    for (name, (row, col)) in [("Start", start), ("End", end)] {
+        if row >= rows || col >= cols {
+            eprintln!("{} ({}, {}) lies outside the maze", name, row, col);
+            std::process::exit(1);
+        }
+    }
+    (start, end)
+}
+
+/// Generates and solves mazes on a non-square topology, saving SVG and PNG renderings.
+fn run_topology_mazes<T: Topology + Clone>(
+    args: &Args,
+    topology_name: &str,
+    topology: T,
+    start: usize,
+    end: usize,
+) {
+    let available_generators: &[(&str, &dyn TopologyMazeGenerator)] = &[
+        ("prims", &PrimMazeGenerator),
+        ("recursive_backtracker", &RecursiveBacktrackerMazeGenerator),
+        ("kruskal", &KruskalMazeGenerator),
+        ("aldous_broder", &AldousBroderMazeGenerator),
+        ("wilson", &WilsonMazeGenerator),
+    ];
+    let available_solvers: &[(&str, &dyn Solver)] = &[
+        ("astar", &solvers::astar::AstarSolver),
+        ("bfs", &solvers::bfs_solver::BfsSolver),
+        ("dfs", &solvers::dfs_solver::DfsSolver),
+        ("dijkstra", &solvers::dijkstra::DijkstraSolver),
+    ];
+
+    for generator_name in &args.generators {
+        let Some((_, generator)) = available_generators
+            .iter()
+            .find(|(n, _)| n == generator_name)
+        else {
+            continue;
+        };
+        println!(
+            "\n--- Generating {} maze with {} ---",
+            topology_name, generator_name
+        );
+        let gen_start = Instant::now();
+        let passages = generator.carve(&topology, None);
+        let maze = LinkedMaze::new(topology.clone(), passages, start, end);
+        println!("Maze generation took: {:.2?}", gen_start.elapsed());
+
+        let base_path = format!(
+            "{}/generated_{}_maze_{}",
+            args.output_dir, topology_name, generator_name
+        );
+        if let Err(e) = linked_maze_image::save_linked_maze_to_svg(
+            &maze,
+            &format!("{}.svg", base_path),
+            args.scale,
+        ) {
+            eprintln!("Error saving generated maze SVG: {}", e);
+        }
+        if let Err(e) = linked_maze_image::save_linked_maze_to_image_scaled(
+            &maze,
+            &format!("{}.png", base_path),
+            args.scale,
+        ) {
+            eprintln!("Error saving generated maze: {}", e);
+        }
+
+        let mut results = Vec::new();
+        for solver_name in &args.solvers {
+            let Some((_, solver)) = available_solvers.iter().find(|(n, _)| n == solver_name) else {
+                continue;
+            };
+            println!("\n--- Solving with {} ---", solver_name);
+            let result = solver.search(&maze, None);
+            match &result.path {
+                Some(path) => {
+                    println!("Path found with {}.", solver_name);
+                    println!("Solving took: {:.2?}", result.elapsed);
+                    let solved_path = format!("{}_solved_{}", base_path, solver_name);
+                    if let Err(e) = linked_maze_image::save_linked_maze_with_path_to_svg(
+                        &maze,
+                        path,
+                        &format!("{}.svg", solved_path),
+                        args.scale,
+                    ) {
+                        eprintln!("Error saving solved maze SVG: {}", e);
+                    }
+                    if let Err(e) = linked_maze_image::save_linked_maze_with_path_to_image_scaled(
+                        &maze,
+                        path,
+                        &format!("{}.png", solved_path),
+                        args.scale,
+                    ) {
+                        eprintln!("Error saving solved maze: {}", e);
+                    }
+                }
+                None => {
+                    println!("No path found with {}.", solver_name);
+                    println!("Solving took: {:.2?}", result.elapsed);
+                }
+            }
+            results.push((solver_name.as_str(), result));
+        }
+        print_comparison(&results);
+    }
+}
+
+/// Checks a path file against a maze, exiting with an error status when it is not a solution.
+fn verify(maze_path: &str, path_file: &str, shortest: bool, scale: u32) {
+    let maze = if maze_path.to_lowercase().ends_with(".png") {
+        maze_image::load_maze_from_image_scaled(maze_path, scale)
+    } else {
+        Maze::from_file(maze_path).map_err(|e| format!("{:?}", e))
+    };
+    let maze = maze.unwrap_or_else(|e| {
+        eprintln!("Failed to load maze '{}': {}", maze_path, e);
+        std::process::exit(1);
+    });
+    let path = std::fs::read_to_string(path_file)
+        .map_err(|e| e.to_string())
+        .and_then(|text| parse_path(&text))
+        .unwrap_or_else(|e| {
+            eprintln!("Failed to read path '{}': {}", path_file, e);
+            std::process::exit(1);
+        });
+
+    match validate_path(&maze, &path, shortest) {
+        Ok(()) => println!("Valid solution of {} steps", path.len() - 1),
+        Err(e) => {
+            eprintln!("Invalid solution: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Loads a mask from a PNG image or, for any other extension, an ASCII mask file.
+fn load_mask(path: &str) -> Result<Mask, String> {
+    if path.to_lowercase().ends_with(".png") {
+        Mask::from_image(path)
+    } else {
+        Mask::from_file(path).map_err(|e| format!("{:?}", e))
     }
 }
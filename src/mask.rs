@@ -0,0 +1,211 @@
+use std::collections::VecDeque;
+use std::fs;
+use std::path::Path;
+
+use crate::maze::MazeError;
+
+/// Character marking a masked-out cell in the ASCII mask format
+pub const MASK_OFF: char = 'X';
+/// Character marking a cell inside the mask in the ASCII mask format
+pub const MASK_ON: char = '.';
+
+/// Shape a block maze is carved into: one flag per logical cell, so a mask of
+/// `rows` x `cols` produces a (2 * rows + 1) x (2 * cols + 1) block maze.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mask {
+    rows: usize,
+    cols: usize,
+    enabled: Vec<bool>,
+}
+
+impl Mask {
+    /// Mask with every cell enabled
+    pub fn new(rows: usize, cols: usize) -> Self {
+        Mask {
+            rows,
+            cols,
+            enabled: vec![true; rows * cols],
+        }
+    }
+
+    /// Parses an ASCII mask: `X` marks cells outside the shape, `.` cells inside it.
+    /// Short lines are padded with masked-out cells.
+    pub fn from_text(text: &str) -> Result<Self, MazeError> {
+        let lines: Vec<&str> = text.lines().collect();
+        let cols = lines.iter().map(|line| line.chars().count()).max();
+        let (rows, cols) = match cols {
+            Some(cols) if cols > 0 => (lines.len(), cols),
+            _ => return Err(MazeError::InvalidDimensions),
+        };
+
+        let mut mask = Mask::new(rows, cols);
+        mask.enabled.fill(false);
+        for (row, line) in lines.iter().enumerate() {
+            for (col, ch) in line.chars().enumerate() {
+                match ch {
+                    MASK_ON => mask.set(row, col, true),
+                    MASK_OFF => {}
+                    _ => return Err(MazeError::InvalidCharacter(ch, row, col)),
+                }
+            }
+        }
+        Ok(mask)
+    }
+
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, MazeError> {
+        Mask::from_text(&fs::read_to_string(path)?)
+    }
+
+    /// Loads a mask from an image with one pixel per cell: black or fully
+    /// transparent pixels are outside the shape.
+    pub fn from_image(path: &str) -> Result<Self, String> {
+        let img = image::open(path).map_err(|e| e.to_string())?.to_rgba8();
+        let (width, height) = img.dimensions();
+        let mut mask = Mask::new(height as usize, width as usize);
+        for (x, y, pixel) in img.enumerate_pixels() {
+            let [r, g, b, a] = pixel.0;
+            let luma = (r as u32 * 299 + g as u32 * 587 + b as u32 * 114) / 1000;
+            mask.set(y as usize, x as usize, a > 0 && luma >= 128);
+        }
+        Ok(mask)
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    pub fn is_enabled(&self, row: usize, col: usize) -> bool {
+        row < self.rows && col < self.cols && self.enabled[row * self.cols + col]
+    }
+
+    pub fn set(&mut self, row: usize, col: usize, enabled: bool) {
+        self.enabled[row * self.cols + col] = enabled;
+    }
+
+    pub fn enabled_count(&self) -> usize {
+        self.enabled.iter().filter(|&&enabled| enabled).count()
+    }
+
+    /// Whether every enabled cell can be reached from every other one. Generators
+    /// can only produce a perfect maze on a connected mask.
+    pub fn is_connected(&self) -> bool {
+        let Some(first) = self.enabled.iter().position(|&enabled| enabled) else {
+            return false;
+        };
+        let mut seen = vec![false; self.enabled.len()];
+        let mut queue = VecDeque::from([first]);
+        seen[first] = true;
+        let mut reached = 0;
+        while let Some(index) = queue.pop_front() {
+            reached += 1;
+            let (row, col) = (index / self.cols, index % self.cols);
+            let neighbors = [
+                (row.wrapping_sub(1), col),
+                (row + 1, col),
+                (row, col.wrapping_sub(1)),
+                (row, col + 1),
+            ];
+            for (n_row, n_col) in neighbors {
+                if self.is_enabled(n_row, n_col) && !seen[n_row * self.cols + n_col] {
+                    seen[n_row * self.cols + n_col] = true;
+                    queue.push_back(n_row * self.cols + n_col);
+                }
+            }
+        }
+        reached == self.enabled_count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cell::Cell;
+    use crate::generators::aldous_broder::AldousBroderMazeGenerator;
+    use crate::generators::generator::{assert_perfect_block_maze, BlockGrid, MazeGenerator};
+    use crate::generators::kruskal::KruskalMazeGenerator;
+    use crate::generators::prims::PrimMazeGenerator;
+    use crate::generators::recursive_backtracker::RecursiveBacktrackerMazeGenerator;
+    use crate::generators::weave::WeaveMazeGenerator;
+    use crate::generators::wilson::WilsonMazeGenerator;
+
+    // a plus sign: the corners of the 5 x 5 square are masked out
+    const PLUS: &str = "\
+XX.XX
+XX.XX
+.....
+XX.XX
+XX.XX";
+
+    #[test]
+    fn test_mask_from_text() {
+        let mask = Mask::from_text(PLUS).unwrap();
+        assert_eq!((mask.rows(), mask.cols()), (5, 5));
+        assert_eq!(mask.enabled_count(), 9);
+        assert!(mask.is_enabled(2, 0));
+        assert!(!mask.is_enabled(0, 0));
+        assert!(mask.is_connected());
+
+        let result = Mask::from_text("..\n.#");
+        assert!(matches!(
+            result,
+            Err(MazeError::InvalidCharacter('#', 1, 1))
+        ));
+    }
+
+    #[test]
+    fn test_mask_from_image() {
+        let mut img = image::RgbImage::from_pixel(3, 2, image::Rgb([0xff, 0xff, 0xff]));
+        img.put_pixel(1, 0, image::Rgb([0, 0, 0]));
+        let path = std::env::temp_dir().join(format!("test_mask_{}.png", std::process::id()));
+        img.save(&path).unwrap();
+
+        let mask = Mask::from_image(path.to_str().unwrap());
+        std::fs::remove_file(&path).unwrap();
+        let mask = mask.unwrap();
+        assert_eq!((mask.rows(), mask.cols()), (2, 3));
+        assert!(!mask.is_enabled(0, 1));
+        assert_eq!(mask.enabled_count(), 5);
+    }
+
+    #[test]
+    fn test_disconnected_mask() {
+        let mask = Mask::from_text(".X.").unwrap();
+        assert!(!mask.is_connected());
+    }
+
+    #[test]
+    fn test_every_generator_stays_inside_mask() {
+        let weave = WeaveMazeGenerator::new(0.5);
+        let generators: [&dyn MazeGenerator; 6] = [
+            &AldousBroderMazeGenerator,
+            &KruskalMazeGenerator,
+            &PrimMazeGenerator,
+            &RecursiveBacktrackerMazeGenerator,
+            &WilsonMazeGenerator,
+            &weave,
+        ];
+        let grid = BlockGrid::masked(Mask::from_text(PLUS).unwrap());
+        for generator in generators {
+            let maze = generator.generate_on(&grid, None, None, 0.0, None);
+            assert_eq!(maze.start, (1, 5));
+            assert_eq!(maze.end, (9, 5));
+            assert_perfect_block_maze(&maze);
+            for (y, row) in maze.cells.iter().enumerate() {
+                for (x, cell) in row.iter().enumerate() {
+                    if cell.is_open() {
+                        assert!(
+                            grid.contains(y, x),
+                            "Carved outside the mask at {:?}",
+                            (y, x)
+                        );
+                    }
+                }
+            }
+            assert_eq!(maze.cells[0][0], Cell::Void);
+        }
+    }
+}
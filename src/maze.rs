@@ -4,15 +4,59 @@ use std::fs::File;
 use std::io::{self, BufRead};
 use std::path::Path;
 
-use crate::cell::Cell;
+use crate::cell::{Axis, Cell, Direction, CHECKPOINT_CHAR, DOOR_CHARS, KEY_CHARS};
 use rand::seq::SliceRandom;
-use rand::Rng;
+
+/// Adjacency list keyed by cell position, each edge is associated with a weight
+pub type Graph = HashMap<(usize, usize), Vec<((usize, usize), usize)>>;
+
+/// Header line prefix recording the topology in the text format
+pub const TOPOLOGY_HEADER: &str = "topology:";
+
+/// Which grid edges wrap around to the opposite side.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Wrap {
+    /// Edges clip, the plain rectangular maze
+    #[default]
+    None,
+    /// Left and right edges wrap
+    Cylinder,
+    /// Left/right and top/bottom edges wrap
+    Torus,
+}
+
+impl Wrap {
+    pub fn horizontal(self) -> bool {
+        self != Wrap::None
+    }
+
+    pub fn vertical(self) -> bool {
+        self == Wrap::Torus
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Wrap::None => "plane",
+            Wrap::Cylinder => "cylinder",
+            Wrap::Torus => "torus",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        [Wrap::None, Wrap::Cylinder, Wrap::Torus]
+            .into_iter()
+            .find(|wrap| wrap.name() == name)
+    }
+}
 
 #[derive(Clone)]
 pub struct Maze {
     pub cells: Vec<Vec<Cell>>,
     pub start: (usize, usize),
     pub end: (usize, usize),
+    pub wrap: Wrap,
+    /// Each portal cell mapped to the other portal of its letter, see `Maze::link_portals`
+    pub portals: HashMap<(usize, usize), (usize, usize)>,
 }
 
 #[derive(Debug)]
@@ -20,6 +64,14 @@ pub enum MazeError {
     Io(io::Error),
     InvalidCharacter(char, usize, usize),
     MissingStartOrEnd,
+    InvalidDimensions,
+    UnknownTopology(String),
+    OutsideMask(usize, usize),
+    /// A portal letter that does not appear exactly twice
+    UnpairedPortal(char),
+    /// A cell (row, column) that breaks the layout of logical cells and
+    /// passages needed to store the maze as passages between cells
+    NotLinkable(usize, usize),
 }
 
 impl From<io::Error> for MazeError {
@@ -29,6 +81,50 @@ impl From<io::Error> for MazeError {
 }
 
 impl Maze {
+    pub fn new(cells: Vec<Vec<Cell>>, start: (usize, usize), end: (usize, usize)) -> Self {
+        let mut maze = Maze {
+            cells,
+            start,
+            end,
+            wrap: Wrap::None,
+            portals: HashMap::new(),
+        };
+        // a letter without a partner simply stays unlinked
+        let _ = maze.link_portals();
+        maze
+    }
+
+    /// Pairs up portal cells by letter, filling `portals`. Call again after
+    /// placing or removing portals. Fails on a letter that does not appear
+    /// exactly twice, linking only the complete pairs.
+    pub fn link_portals(&mut self) -> Result<(), MazeError> {
+        let mut by_letter: HashMap<u8, Vec<(usize, usize)>> = HashMap::new();
+        for (y, row) in self.cells.iter().enumerate() {
+            for (x, cell) in row.iter().enumerate() {
+                if let Cell::Portal(letter) = *cell {
+                    by_letter.entry(letter).or_default().push((y, x));
+                }
+            }
+        }
+
+        self.portals.clear();
+        for ends in by_letter.values() {
+            if let [a, b] = ends[..] {
+                self.portals.insert(a, b);
+                self.portals.insert(b, a);
+            }
+        }
+        let unpaired = by_letter
+            .iter()
+            .filter(|(_, ends)| ends.len() != 2)
+            .map(|(&letter, _)| letter)
+            .min();
+        match unpaired {
+            Some(letter) => Err(MazeError::UnpairedPortal(Cell::portal_char(letter))),
+            None => Ok(()),
+        }
+    }
+
     pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, MazeError> {
         let file = File::open(path)?;
         let reader = io::BufReader::new(file);
@@ -36,15 +132,40 @@ impl Maze {
         let mut cells = Vec::new();
         let mut start = None;
         let mut end = None;
+        let mut wrap = Wrap::None;
 
-        for (y, line) in reader.lines().enumerate() {
+        for (i_line, line) in reader.lines().enumerate() {
             let line = line?;
+            if i_line == 0 {
+                if let Some(name) = line.strip_prefix(TOPOLOGY_HEADER) {
+                    wrap = Wrap::from_name(name.trim())
+                        .ok_or_else(|| MazeError::UnknownTopology(name.trim().to_string()))?;
+                    continue;
+                }
+            }
+            let y = cells.len();
             let mut row = Vec::new();
 
             for (x, ch) in line.chars().enumerate() {
                 let cell = match ch {
                     '#' => Cell::Wall,
                     '.' => Cell::Path,
+                    '-' => Cell::Crossing(Axis::Horizontal),
+                    '|' => Cell::Crossing(Axis::Vertical),
+                    ' ' => Cell::Void,
+                    CHECKPOINT_CHAR => Cell::Checkpoint,
+                    _ if KEY_CHARS.contains(&ch) => {
+                        Cell::Key(KEY_CHARS.iter().position(|&c| c == ch).unwrap() as u8)
+                    }
+                    _ if DOOR_CHARS.contains(&ch) => {
+                        Cell::Door(DOOR_CHARS.iter().position(|&c| c == ch).unwrap() as u8)
+                    }
+                    _ if Direction::from_arrow(ch).is_some() => {
+                        Cell::OneWay(Direction::from_arrow(ch).unwrap())
+                    }
+                    _ if Cell::portal_letter(ch).is_some() => {
+                        Cell::Portal(Cell::portal_letter(ch).unwrap())
+                    }
                     'S' => {
                         if start.is_some() {
                             return Err(MazeError::InvalidCharacter(ch, y, x));
@@ -70,7 +191,38 @@ impl Maze {
         let start = start.ok_or(MazeError::MissingStartOrEnd)?;
         let end = end.ok_or(MazeError::MissingStartOrEnd)?;
 
-        Ok(Maze { cells, start, end })
+        // rows may be cut short where the maze's shape ends early
+        let width = cells.iter().map(Vec::len).max().unwrap_or(0);
+        for row in &mut cells {
+            row.resize(width, Cell::Void);
+        }
+
+        let mut maze = Maze {
+            cells,
+            start,
+            end,
+            wrap,
+            portals: HashMap::new(),
+        };
+        maze.link_portals()?;
+        Ok(maze)
+    }
+
+    /// Index of a cell when the maze is viewed as a graph (see `MazeGraph`)
+    pub fn node_index(&self, row: usize, col: usize) -> usize {
+        row * self.cells[0].len() + col
+    }
+
+    /// Graph node of a cell entered along `axis`. A crossing has one node per
+    /// corridor: the horizontal one shares the cell's `node_index`, the vertical
+    /// one lives in a second layer after all cells.
+    pub fn layer_node(&self, row: usize, col: usize, axis: Axis) -> usize {
+        match (self.cells[row][col], axis) {
+            (Cell::Crossing(_), Axis::Vertical) => {
+                self.cells.len() * self.cells[0].len() + self.node_index(row, col)
+            }
+            _ => self.node_index(row, col),
+        }
     }
 
     pub fn get_neighbors(
@@ -78,15 +230,36 @@ impl Maze {
         col: usize,
         height: usize,
         width: usize,
+    ) -> Vec<(usize, usize)> {
+        Maze::get_wrapped_neighbors(row, col, height, width, Wrap::None)
+    }
+
+    /// Like `get_neighbors`, but steps across wrapping edges to the opposite side.
+    pub fn get_wrapped_neighbors(
+        row: usize,
+        col: usize,
+        height: usize,
+        width: usize,
+        wrap: Wrap,
     ) -> Vec<(usize, usize)> {
         const DIRS: [(isize, isize); 4] = [(-1, 0), (1, 0), (0, -1), (0, 1)];
         let mut neighbors = Vec::new();
 
         for (dy, dx) in DIRS.iter() {
-            let new_row = row as isize + dy;
-            let new_col = col as isize + dx;
+            let mut new_row = row as isize + dy;
+            let mut new_col = col as isize + dx;
+            if wrap.vertical() {
+                new_row = new_row.rem_euclid(height as isize);
+            }
+            if wrap.horizontal() {
+                new_col = new_col.rem_euclid(width as isize);
+            }
 
-            if new_row >= 0 && new_row < height as isize && new_col >= 0 && new_col < width as isize
+            if new_row >= 0
+                && new_row < height as isize
+                && new_col >= 0
+                && new_col < width as isize
+                && (new_row as usize, new_col as usize) != (row, col)
             {
                 neighbors.push((new_row as usize, new_col as usize));
             }
@@ -95,23 +268,93 @@ impl Maze {
         neighbors
     }
 
-    // converts maze to adjacency list representation, each edge is associated with a weight
-    pub fn to_graph(&self) -> HashMap<(usize, usize), Vec<((usize, usize), usize)>> {
+    /// Steps needed between two cells ignoring walls, taking wrapping edges into account
+    pub fn grid_distance(&self, a: (usize, usize), b: (usize, usize)) -> usize {
+        let rows = self.cells.len();
+        let cols = self.cells[0].len();
+        let mut dy = a.0.abs_diff(b.0);
+        let mut dx = a.1.abs_diff(b.1);
+        if self.wrap.vertical() {
+            dy = dy.min(rows - dy);
+        }
+        if self.wrap.horizontal() {
+            dx = dx.min(cols - dx);
+        }
+        dy + dx
+    }
+
+    /// Whether a single step between neighbouring cells is allowed by one-way
+    /// cells: a one-way cell is only entered and left moving in its direction.
+    pub fn allows_step(&self, from: (usize, usize), to: (usize, usize)) -> bool {
+        let direction = Direction::of_step(from, to);
+        [from, to]
+            .iter()
+            .all(|&(row, col)| match self.cells[row][col] {
+                Cell::OneWay(one_way) => one_way == direction,
+                _ => true,
+            })
+    }
+
+    /// Whether a cell lies on a border row/column that wraps to the opposite side
+    pub fn is_wrap_seam(&self, row: usize, col: usize) -> bool {
+        (self.wrap.vertical() && row == 0) || (self.wrap.horizontal() && col == 0)
+    }
+
+    /// Lower bound on the steps from `pos` to the end: either walk there
+    /// ignoring walls, or walk to the nearest portal, jump, and walk on from
+    /// the portal closest to the end.
+    pub fn distance_to_end(&self, pos: (usize, usize)) -> usize {
+        self.distance_between(pos, self.end)
+    }
+
+    /// Lower bound on the steps between any two cells, see `distance_to_end`.
+    pub fn distance_between(&self, from: (usize, usize), to: (usize, usize)) -> usize {
+        let direct = self.grid_distance(from, to);
+        let via_portal = self
+            .portals
+            .keys()
+            .map(|&portal| self.grid_distance(from, portal))
+            .min()
+            .zip(
+                self.portals
+                    .keys()
+                    .map(|&portal| self.grid_distance(portal, to))
+                    .min(),
+            )
+            .map(|(to_portal, from_portal)| to_portal + 1 + from_portal);
+        via_portal.map_or(direct, |via| direct.min(via))
+    }
+
+    // converts maze to adjacency list representation, each edge is associated with a weight.
+    // Doors are left out as they need a key. Crossings are not nodes: an edge runs straight through them to the cell on the
+    // far side, weighted by the number of steps taken. A portal has an extra edge of weight 1 to its partner.
+    // One-way cells make the graph directed: an edge only exists in the direction the arrows allow.
+    pub fn to_graph(&self) -> Graph {
         let mut graph = HashMap::new();
         let rows = self.cells.len();
         let cols = self.cells[0].len();
 
         for i_row in 0..rows {
             for i_col in 0..cols {
-                if self.cells[i_row][i_col] != Cell::Wall {
-                    let neighbors = Maze::get_neighbors(i_row, i_col, rows, cols);
+                if matches!(
+                    self.cells[i_row][i_col],
+                    Cell::Path
+                        | Cell::Key(_)
+                        | Cell::Portal(_)
+                        | Cell::OneWay(_)
+                        | Cell::Checkpoint
+                ) {
+                    let neighbors =
+                        Maze::get_wrapped_neighbors(i_row, i_col, rows, cols, self.wrap);
                     let mut adj_list = Vec::new();
-                    for (n_row, n_col) in neighbors {
-                        if self.cells[n_row][n_col] != Cell::Wall {
-                            // Each edge has weight 1
-                            adj_list.push(((n_row, n_col), 1));
+                    for neighbor in neighbors {
+                        if let Some(edge) = self.follow_crossings((i_row, i_col), neighbor) {
+                            adj_list.push(edge);
                         }
                     }
+                    if let Some(&exit) = self.portals.get(&(i_row, i_col)) {
+                        adj_list.push((exit, 1));
+                    }
                     graph.insert((i_row, i_col), adj_list);
                 }
             }
@@ -120,6 +363,59 @@ impl Maze {
         graph
     }
 
+    // steps from `from` into `to` and keeps going straight while on a crossing,
+    // returning the first plain path cell reached and the steps taken
+    fn follow_crossings(
+        &self,
+        from: (usize, usize),
+        to: (usize, usize),
+    ) -> Option<((usize, usize), usize)> {
+        let rows = self.cells.len();
+        let cols = self.cells[0].len();
+        let (mut prev, mut current, mut steps) = (from, to, 1);
+        loop {
+            if !self.allows_step(prev, current) {
+                return None;
+            }
+            match self.cells[current.0][current.1] {
+                Cell::Wall | Cell::Void | Cell::Door(_) => return None,
+                Cell::Path
+                | Cell::Key(_)
+                | Cell::Portal(_)
+                | Cell::OneWay(_)
+                | Cell::Checkpoint => return Some((current, steps)),
+                Cell::Crossing(_) if steps > rows.max(cols) => return None,
+                Cell::Crossing(_) => {
+                    let axis = Axis::of_step(prev, current);
+                    let next =
+                        Maze::get_wrapped_neighbors(current.0, current.1, rows, cols, self.wrap)
+                            .into_iter()
+                            .find(|&n| n != prev && Axis::of_step(current, n) == axis)?;
+                    (prev, current, steps) = (current, next, steps + 1);
+                }
+            }
+        }
+    }
+
+    /// Checkpoint cells in row-major order
+    pub fn checkpoints(&self) -> Vec<(usize, usize)> {
+        let mut checkpoints = Vec::new();
+        for (y, row) in self.cells.iter().enumerate() {
+            for (x, cell) in row.iter().enumerate() {
+                if *cell == Cell::Checkpoint {
+                    checkpoints.push((y, x));
+                }
+            }
+        }
+        checkpoints
+    }
+
+    pub fn has_doors(&self) -> bool {
+        self.cells
+            .iter()
+            .any(|row| row.iter().any(|cell| matches!(cell, Cell::Door(_))))
+    }
+
     pub fn remove_walls(&mut self, percentage: f32) {
         if !(0.0..=100.0).contains(&percentage) {
             eprintln!("Percentage must be between 0.0 and 100.0");
@@ -130,20 +426,35 @@ impl Maze {
         let cols = self.cells[0].len();
         let mut walls = Vec::new();
 
-        for r in 1..rows - 1 {
-            for c in 1..cols - 1 {
-                if self.cells[r][c] == Cell::Wall {
+        // wrapping edges are interior walls too
+        let row_range = if self.wrap.vertical() {
+            0..rows
+        } else {
+            1..rows - 1
+        };
+        let col_range = if self.wrap.horizontal() {
+            0..cols
+        } else {
+            1..cols - 1
+        };
+        for r in row_range {
+            for c in col_range.clone() {
+                // walls bordering the outside of a shaped maze stay up
+                if self.cells[r][c] == Cell::Wall
+                    && Maze::get_wrapped_neighbors(r, c, rows, cols, self.wrap)
+                        .iter()
+                        .all(|&(n_row, n_col)| self.cells[n_row][n_col] != Cell::Void)
+                {
                     walls.push((r, c));
                 }
             }
         }
 
         let num_walls_to_remove = ((walls.len() as f32) * percentage / 100.0) as usize;
-        let mut rng = rand::thread_rng();
+        let mut rng = crate::random::rng();
         walls.shuffle(&mut rng);
 
-        for i in 0..num_walls_to_remove {
-            let (r, c) = walls[i];
+        for &(r, c) in walls.iter().take(num_walls_to_remove) {
             self.cells[r][c] = Cell::Path;
         }
     }
@@ -151,6 +462,9 @@ impl Maze {
 
 impl fmt::Display for Maze {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.wrap != Wrap::None {
+            writeln!(f, "{} {}", TOPOLOGY_HEADER, self.wrap.name())?;
+        }
         for (i_row, row) in self.cells.iter().enumerate() {
             for (i_col, cell) in row.iter().enumerate() {
                 let ch;
@@ -162,6 +476,14 @@ impl fmt::Display for Maze {
                     ch = match cell {
                         Cell::Wall => '#',
                         Cell::Path => '.',
+                        Cell::Crossing(Axis::Horizontal) => '-',
+                        Cell::Crossing(Axis::Vertical) => '|',
+                        Cell::Void => ' ',
+                        Cell::Key(color) => KEY_CHARS[*color as usize],
+                        Cell::Door(color) => DOOR_CHARS[*color as usize],
+                        Cell::Portal(letter) => Cell::portal_char(*letter),
+                        Cell::OneWay(direction) => direction.arrow(),
+                        Cell::Checkpoint => CHECKPOINT_CHAR,
                     };
                 }
                 write!(f, "{}", ch)?;
@@ -242,4 +564,189 @@ mod tests {
         let result = Maze::from_file(&path);
         assert!(matches!(result, Err(MazeError::MissingStartOrEnd)));
     }
+
+    #[test]
+    fn test_wrapped_neighbors() {
+        let plain = Maze::get_wrapped_neighbors(0, 0, 4, 6, Wrap::None);
+        assert_eq!(plain, vec![(1, 0), (0, 1)]);
+        let cylinder = Maze::get_wrapped_neighbors(0, 0, 4, 6, Wrap::Cylinder);
+        assert_eq!(cylinder, vec![(1, 0), (0, 5), (0, 1)]);
+        let torus = Maze::get_wrapped_neighbors(0, 0, 4, 6, Wrap::Torus);
+        assert_eq!(torus, vec![(3, 0), (1, 0), (0, 5), (0, 1)]);
+    }
+
+    #[test]
+    fn test_topology_header_round_trip() {
+        let maze_text = "\
+topology: cylinder\n\
+####\n\
+S#.E\n\
+####\n";
+
+        let mut path = std::env::temp_dir();
+        path.push("test_maze_cylinder.txt");
+        {
+            let mut file = File::create(&path).unwrap();
+            write!(file, "{}", maze_text).unwrap();
+        }
+
+        let maze = Maze::from_file(&path).expect("Failed to parse maze");
+        assert_eq!(maze.wrap, Wrap::Cylinder);
+        assert_eq!(maze.start, (1, 0));
+        assert_eq!(maze.end, (1, 3));
+        // start and end touch across the seam
+        assert_eq!(maze.grid_distance(maze.start, maze.end), 1);
+        assert!(maze.to_graph()[&maze.start].contains(&((1, 3), 1)));
+        assert_eq!(maze.to_string(), maze_text);
+    }
+
+    #[test]
+    fn test_unknown_topology_header() {
+        let mut path = std::env::temp_dir();
+        path.push("test_maze_unknown_topology.txt");
+        {
+            let mut file = File::create(&path).unwrap();
+            write!(file, "topology: klein\n#S.E#").unwrap();
+        }
+
+        let result = Maze::from_file(&path);
+        assert!(matches!(result, Err(MazeError::UnknownTopology(name)) if name == "klein"));
+    }
+
+    #[test]
+    fn test_crossing_tunnels_in_graph() {
+        let maze_text = "\
+#######\n\
+###.###\n\
+#S.|.E#\n\
+###.###\n\
+#######\n";
+
+        let mut path = std::env::temp_dir();
+        path.push("test_maze_crossing.txt");
+        {
+            let mut file = File::create(&path).unwrap();
+            write!(file, "{}", maze_text).unwrap();
+        }
+
+        let maze = Maze::from_file(&path).expect("Failed to parse maze");
+        assert_eq!(maze.cells[2][3], Cell::Crossing(Axis::Vertical));
+        assert_eq!(maze.to_string(), maze_text);
+
+        // the crossing is not a node; both corridors pass straight through it
+        let graph = maze.to_graph();
+        assert!(!graph.contains_key(&(2, 3)));
+        assert_eq!(graph[&(2, 2)], vec![((2, 1), 1), ((2, 4), 2)]);
+        assert_eq!(graph[&(1, 3)], vec![((3, 3), 2)]);
+    }
+
+    #[test]
+    fn test_portals_link_in_graph() {
+        let maze_text = "\
+#########\n\
+#S.a#b..#\n\
+#####.#.#\n\
+#E..a.#b#\n\
+#########\n";
+
+        let mut path = std::env::temp_dir();
+        path.push("test_maze_portals.txt");
+        {
+            let mut file = File::create(&path).unwrap();
+            write!(file, "{}", maze_text).unwrap();
+        }
+
+        let maze = Maze::from_file(&path).expect("Failed to parse maze");
+        assert_eq!(maze.cells[1][3], Cell::Portal(0));
+        assert_eq!(maze.portals[&(1, 3)], (3, 4));
+        assert_eq!(maze.portals[&(1, 5)], (3, 7));
+        assert_eq!(maze.to_string(), maze_text);
+
+        let graph = maze.to_graph();
+        assert!(graph[&(1, 3)].contains(&((3, 4), 1)));
+        assert!(graph[&(3, 4)].contains(&((1, 3), 1)));
+        // from (1, 7) the end is 8 steps away, or 2 to a portal, 1 through it
+        // and at least 3 from the portal closest to the end
+        assert_eq!(maze.distance_to_end((1, 7)), 2 + 1 + 3);
+    }
+
+    #[test]
+    fn test_unpaired_portal() {
+        let mut path = std::env::temp_dir();
+        path.push("test_maze_unpaired_portal.txt");
+        {
+            let mut file = File::create(&path).unwrap();
+            write!(file, "#SqEq#\n#z...#").unwrap();
+        }
+
+        let result = Maze::from_file(&path);
+        assert!(matches!(result, Err(MazeError::UnpairedPortal('z'))));
+    }
+
+    #[test]
+    fn test_one_way_cells_direct_graph() {
+        let maze_text = "\
+#######\n\
+#S.>.E#\n\
+###v###\n\
+#..<..#\n\
+#######\n";
+
+        let mut path = std::env::temp_dir();
+        path.push("test_maze_one_way.txt");
+        {
+            let mut file = File::create(&path).unwrap();
+            write!(file, "{}", maze_text).unwrap();
+        }
+
+        let maze = Maze::from_file(&path).expect("Failed to parse maze");
+        assert_eq!(maze.cells[2][3], Cell::OneWay(Direction::South));
+        assert_eq!(maze.to_string(), maze_text);
+
+        let graph = maze.to_graph();
+        // through the arrow eastwards only
+        assert_eq!(graph[&(1, 2)], vec![((1, 1), 1), ((1, 3), 1)]);
+        assert_eq!(graph[&(1, 4)], vec![((1, 5), 1)]);
+        assert_eq!(graph[&(1, 3)], vec![((1, 4), 1)]);
+        // an arrow is not entered from the side
+        assert_eq!(graph[&(2, 3)], vec![]);
+        assert_eq!(graph[&(3, 4)], vec![((3, 3), 1), ((3, 5), 1)]);
+    }
+
+    #[test]
+    fn test_portals_and_arrows_round_trip_through_text() {
+        use crate::cell::PORTAL_LETTERS;
+
+        let mut row = vec![Cell::Path];
+        row.extend((0..PORTAL_LETTERS).map(Cell::Portal));
+        row.extend(Direction::ALL.map(Cell::OneWay));
+        row.push(Cell::Path);
+        let width = row.len() + 2;
+        let framed = |inner: Vec<Cell>| {
+            let mut cells = vec![Cell::Wall];
+            cells.extend(inner);
+            cells.push(Cell::Wall);
+            cells
+        };
+        let cells = vec![
+            vec![Cell::Wall; width],
+            framed(row.clone()),
+            framed(row),
+            vec![Cell::Wall; width],
+        ];
+        let maze = Maze::new(cells, (1, 1), (2, width - 2));
+        assert_eq!(maze.portals.len(), 2 * PORTAL_LETTERS as usize);
+
+        let path = std::env::temp_dir().join(format!(
+            "test_maze_portal_letters_{}.txt",
+            std::process::id()
+        ));
+        std::fs::write(&path, maze.to_string()).unwrap();
+        let loaded = Maze::from_file(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        let loaded = loaded.expect("Failed to parse maze");
+        assert_eq!(loaded.cells, maze.cells);
+        assert_eq!(loaded.portals, maze.portals);
+    }
 }
@@ -0,0 +1,227 @@
+use crate::cell::{Axis, Cell, KEY_COLORS};
+use crate::linked_maze::LinkedMaze;
+use crate::maze::Maze;
+use crate::topologies::topology::Topology;
+
+/// Read-only graph view of a maze shared by all solvers. Nodes are dense
+/// indices in `0..node_count()`.
+pub trait MazeGraph {
+    fn node_count(&self) -> usize;
+
+    fn start_node(&self) -> usize;
+
+    fn end_node(&self) -> usize;
+
+    /// Nodes reachable in one step from `node`, each with its edge weight.
+    fn neighbors(&self, node: usize) -> Vec<(usize, usize)>;
+
+    /// Calls `visit` with each neighbour of `node` and its edge weight, without
+    /// collecting them first where the graph can avoid it.
+    fn for_each_neighbor(&self, node: usize, visit: &mut dyn FnMut(usize, usize)) {
+        for (neighbor, weight) in self.neighbors(node) {
+            visit(neighbor, weight);
+        }
+    }
+
+    /// Whether reaching `node` solves the maze.
+    fn is_goal(&self, node: usize) -> bool {
+        node == self.end_node()
+    }
+
+    /// Admissible estimate of the remaining cost from `node` to the end.
+    fn heuristic(&self, node: usize) -> usize;
+
+    /// Grid coordinates (row, column) of a node, used for progress tracking and output.
+    fn position(&self, node: usize) -> (usize, usize);
+}
+
+impl MazeGraph for Maze {
+    // every cell has a node; crossings get a second one for their vertical corridor
+    fn node_count(&self) -> usize {
+        2 * self.cells.len() * self.cells[0].len()
+    }
+
+    fn start_node(&self) -> usize {
+        self.node_index(self.start.0, self.start.1)
+    }
+
+    fn end_node(&self) -> usize {
+        self.node_index(self.end.0, self.end.1)
+    }
+
+    fn neighbors(&self, node: usize) -> Vec<(usize, usize)> {
+        self.graph_neighbors(node, Cell::is_open)
+    }
+
+    fn heuristic(&self, node: usize) -> usize {
+        self.distance_to_end(MazeGraph::position(self, node))
+    }
+
+    fn position(&self, node: usize) -> (usize, usize) {
+        let cols = self.cells[0].len();
+        let cell = node % (self.cells.len() * cols);
+        (cell / cols, cell % cols)
+    }
+}
+
+impl Maze {
+    /// Graph neighbours of `node` among the cells `can_enter` accepts, respecting
+    /// crossings: on a crossing only the corridor the node belongs to is followed.
+    /// A portal also leads to its partner, and one-way cells are only passed in
+    /// their direction.
+    pub(crate) fn graph_neighbors(
+        &self,
+        node: usize,
+        can_enter: impl Fn(Cell) -> bool,
+    ) -> Vec<(usize, usize)> {
+        let (row, col) = MazeGraph::position(self, node);
+        let corridor = match self.cells[row][col] {
+            Cell::Wall | Cell::Void => return Vec::new(),
+            Cell::Crossing(_) if node == self.node_index(row, col) => Some(Axis::Horizontal),
+            Cell::Crossing(_) => Some(Axis::Vertical),
+            _ => None,
+        };
+        Maze::get_wrapped_neighbors(row, col, self.cells.len(), self.cells[0].len(), self.wrap)
+            .into_iter()
+            .filter(|&(n_row, n_col)| can_enter(self.cells[n_row][n_col]))
+            .filter(|&next| self.allows_step((row, col), next))
+            .filter_map(|(n_row, n_col)| {
+                let axis = Axis::of_step((row, col), (n_row, n_col));
+                if corridor.is_some_and(|corridor| corridor != axis) {
+                    return None;
+                }
+                Some((self.layer_node(n_row, n_col, axis), 1))
+            })
+            .chain(
+                self.portals
+                    .get(&(row, col))
+                    .map(|&(p_row, p_col)| (self.node_index(p_row, p_col), 1)),
+            )
+            .collect()
+    }
+}
+
+/// State-space view of a maze with keys and doors. A node is a maze node together
+/// with the set of key colors collected on the way there, so a door opens once its
+/// key is held and the end is reached with whatever keys were picked up.
+pub struct KeyDoorGraph<'a> {
+    maze: &'a Maze,
+}
+
+impl<'a> KeyDoorGraph<'a> {
+    pub fn new(maze: &'a Maze) -> Self {
+        KeyDoorGraph { maze }
+    }
+
+    /// Maze node and bitset of held key colors of a state
+    pub fn split(&self, node: usize) -> (usize, u8) {
+        let cells = self.maze.node_count();
+        (node % cells, (node / cells) as u8)
+    }
+
+    fn join(&self, maze_node: usize, keys: u8) -> usize {
+        keys as usize * self.maze.node_count() + maze_node
+    }
+}
+
+impl MazeGraph for KeyDoorGraph<'_> {
+    fn node_count(&self) -> usize {
+        self.maze.node_count() << KEY_COLORS
+    }
+
+    fn start_node(&self) -> usize {
+        self.maze.start_node()
+    }
+
+    fn end_node(&self) -> usize {
+        self.maze.end_node()
+    }
+
+    fn is_goal(&self, node: usize) -> bool {
+        self.split(node).0 == self.maze.end_node()
+    }
+
+    fn neighbors(&self, node: usize) -> Vec<(usize, usize)> {
+        let (maze_node, keys) = self.split(node);
+        self.maze
+            .graph_neighbors(maze_node, |cell| match cell {
+                Cell::Door(color) => keys & (1 << color) != 0,
+                cell => cell.is_open(),
+            })
+            .into_iter()
+            .map(|(next, weight)| {
+                let (row, col) = MazeGraph::position(self.maze, next);
+                let keys = match self.maze.cells[row][col] {
+                    Cell::Key(color) => keys | (1 << color),
+                    _ => keys,
+                };
+                (self.join(next, keys), weight)
+            })
+            .collect()
+    }
+
+    fn heuristic(&self, node: usize) -> usize {
+        self.maze.heuristic(self.split(node).0)
+    }
+
+    fn position(&self, node: usize) -> (usize, usize) {
+        MazeGraph::position(self.maze, self.split(node).0)
+    }
+}
+
+impl<T: Topology> MazeGraph for LinkedMaze<T> {
+    fn node_count(&self) -> usize {
+        self.topology.cell_count()
+    }
+
+    fn start_node(&self) -> usize {
+        self.start
+    }
+
+    fn end_node(&self) -> usize {
+        self.end
+    }
+
+    fn neighbors(&self, node: usize) -> Vec<(usize, usize)> {
+        self.passages.links(node).iter().map(|&n| (n, 1)).collect()
+    }
+
+    fn heuristic(&self, node: usize) -> usize {
+        self.topology.distance(node, self.end)
+    }
+
+    fn position(&self, node: usize) -> (usize, usize) {
+        self.topology.position(node)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solvers::astar::AstarSolver;
+    use crate::solvers::bfs_solver::BfsSolver;
+    use crate::solvers::solver::Solver;
+
+    #[test]
+    fn test_key_door_graph_fetches_key_first() {
+        // #########
+        // #1.S.A.E#
+        // #########
+        let mut cells = vec![vec![Cell::Wall; 9]; 3];
+        cells[1][1..8].fill(Cell::Path);
+        cells[1][1] = Cell::Key(0);
+        cells[1][5] = Cell::Door(0);
+        let maze = Maze::new(cells, (1, 3), (1, 7));
+        assert!(maze.has_doors());
+        // without the key the door stays shut
+        assert!(BfsSolver.solve_graph(&maze, None).is_none());
+
+        let expected: Vec<(usize, usize)> = [3, 2, 1, 2, 3, 4, 5, 6, 7]
+            .into_iter()
+            .map(|col| (1, col))
+            .collect();
+        for solver in [&AstarSolver as &dyn Solver, &BfsSolver] {
+            assert_eq!(solver.solve(&maze, None), Some(expected.clone()));
+        }
+    }
+}
@@ -1,7 +1,17 @@
-use crate::cell::Cell;
-use crate::colors::{END_COLOR, PATH_COLOR, SOLUTION_PATH_COLOR, START_COLOR, WALL_COLOR};
-use crate::maze::Maze;
-use image::{ImageBuffer, Rgb, RgbImage};
+use crate::cell::{Axis, Cell, Direction};
+use crate::colors::{theme, Theme, PORTAL_COLOR_COUNT};
+use crate::maze::{Maze, Wrap};
+use image::{ImageBuffer, Rgb, Rgba, RgbaImage};
+
+/// Pixel left for cells outside a shaped maze
+const TRANSPARENT: Rgba<u8> = Rgba([0, 0, 0, 0]);
+/// Back edge and tip of a one-way arrow along its direction, as fractions of the cell size
+pub(crate) const ARROW_BACK: f64 = 0.25;
+pub(crate) const ARROW_TIP: f64 = 0.75;
+/// Half the width of a one-way arrow's back edge, as a fraction of the cell size
+pub(crate) const ARROW_HALF_WIDTH: f64 = 0.3;
+/// Distance from the center to a corner of a checkpoint diamond, as a fraction of the cell size
+pub(crate) const CHECKPOINT_RADIUS: f64 = 0.35;
 use std::collections::HashSet;
 
 pub fn save_maze_to_image(maze: &Maze, path: &str) -> Result<(), image::ImageError> {
@@ -15,23 +25,28 @@ pub fn save_maze_to_image_scaled(
 ) -> Result<(), image::ImageError> {
     let width = maze.cells[0].len() as u32 * scale;
     let height = maze.cells.len() as u32 * scale;
-    let mut img: RgbImage = ImageBuffer::new(width, height);
+    let mut img: RgbaImage = ImageBuffer::new(width, height);
+    let theme = theme();
 
     for (y, row) in maze.cells.iter().enumerate() {
         for (x, cell) in row.iter().enumerate() {
             let color = if (y, x) == maze.start {
-                START_COLOR
+                theme.start
             } else if (y, x) == maze.end {
-                END_COLOR
+                theme.end
             } else {
-                match cell {
-                    Cell::Wall => WALL_COLOR,
-                    Cell::Path => PATH_COLOR,
-                }
+                base_color(&theme, maze, y, x, cell)
             };
             for i in 0..scale {
                 for j in 0..scale {
-                    img.put_pixel(x as u32 * scale + j, y as u32 * scale + i, color);
+                    let pixel = if *cell == Cell::Void {
+                        TRANSPARENT
+                    } else if let Some(overlay) = cell_overlay(cell, i, j, scale) {
+                        opaque(overlay_color(&theme, overlay))
+                    } else {
+                        opaque(color)
+                    };
+                    img.put_pixel(x as u32 * scale + j, y as u32 * scale + i, pixel);
                 }
             }
         }
@@ -55,27 +70,91 @@ pub fn save_maze_with_path_to_image_scaled(
 ) -> Result<(), image::ImageError> {
     let width = maze.cells[0].len() as u32 * scale;
     let height = maze.cells.len() as u32 * scale;
-    let mut img: RgbImage = ImageBuffer::new(width, height);
+    let mut img: RgbaImage = ImageBuffer::new(width, height);
+    let theme = theme();
 
     let path_set: HashSet<&(usize, usize)> = path.iter().collect();
 
     for (y, row) in maze.cells.iter().enumerate() {
         for (x, cell) in row.iter().enumerate() {
             let color = if (y, x) == maze.start {
-                START_COLOR
+                theme.start
             } else if (y, x) == maze.end {
-                END_COLOR
+                theme.end
             } else if path_set.contains(&(y, x)) {
-                SOLUTION_PATH_COLOR
+                theme.solution_path
             } else {
-                match cell {
-                    Cell::Wall => WALL_COLOR,
-                    Cell::Path => PATH_COLOR,
+                base_color(&theme, maze, y, x, cell)
+            };
+            for i in 0..scale {
+                for j in 0..scale {
+                    let pixel = if *cell == Cell::Void {
+                        TRANSPARENT
+                    } else if let Some(overlay) = cell_overlay(cell, i, j, scale) {
+                        opaque(overlay_color(&theme, overlay))
+                    } else {
+                        opaque(color)
+                    };
+                    img.put_pixel(x as u32 * scale + j, y as u32 * scale + i, pixel);
                 }
+            }
+        }
+    }
+    img.save(file_path)
+}
+
+/// Saves a block maze with several routes overlaid, each in its own color from
+/// the theme's route colors. Later routes fill a smaller square in the middle of their
+/// cells, so where routes share a cell each one stays visible as a ring.
+pub fn save_maze_with_paths_to_image_scaled(
+    maze: &Maze,
+    paths: &[Vec<(usize, usize)>],
+    file_path: &str,
+    scale: u32,
+) -> Result<(), image::ImageError> {
+    let width = maze.cells[0].len() as u32 * scale;
+    let height = maze.cells.len() as u32 * scale;
+    let mut img: RgbaImage = ImageBuffer::new(width, height);
+    let theme = theme();
+
+    let path_sets: Vec<HashSet<&(usize, usize)>> =
+        paths.iter().map(|path| path.iter().collect()).collect();
+    let ring = scale / (2 * paths.len().max(1) as u32);
+
+    for (y, row) in maze.cells.iter().enumerate() {
+        for (x, cell) in row.iter().enumerate() {
+            let color = if (y, x) == maze.start {
+                theme.start
+            } else if (y, x) == maze.end {
+                theme.end
+            } else {
+                base_color(&theme, maze, y, x, cell)
+            };
+            let on_paths: Vec<usize> = if (y, x) == maze.start || (y, x) == maze.end {
+                Vec::new()
+            } else {
+                (0..paths.len())
+                    .filter(|&index| path_sets[index].contains(&(y, x)))
+                    .collect()
             };
             for i in 0..scale {
                 for j in 0..scale {
-                    img.put_pixel(x as u32 * scale + j, y as u32 * scale + i, color);
+                    // how many rings in from the cell's edge the pixel lies
+                    let depth = i.min(j).min(scale - 1 - i).min(scale - 1 - j);
+                    let route = on_paths
+                        .iter()
+                        .rev()
+                        .find(|&&index| depth >= index as u32 * ring);
+                    let pixel = if *cell == Cell::Void {
+                        TRANSPARENT
+                    } else if let Some(overlay) = cell_overlay(cell, i, j, scale) {
+                        opaque(overlay_color(&theme, overlay))
+                    } else if let Some(&index) = route {
+                        opaque(theme.routes[index % theme.routes.len()])
+                    } else {
+                        opaque(color)
+                    };
+                    img.put_pixel(x as u32 * scale + j, y as u32 * scale + i, pixel);
                 }
             }
         }
@@ -83,12 +162,211 @@ pub fn save_maze_with_path_to_image_scaled(
     img.save(file_path)
 }
 
+// color of a cell before any path or detail is drawn over it
+pub(crate) fn base_color(theme: &Theme, maze: &Maze, y: usize, x: usize, cell: &Cell) -> Rgb<u8> {
+    match cell {
+        Cell::Wall => theme.wall,
+        Cell::Path if maze.is_wrap_seam(y, x) => theme.wrap,
+        Cell::Path
+        | Cell::Crossing(_)
+        | Cell::Key(_)
+        | Cell::Door(_)
+        | Cell::Portal(_)
+        | Cell::OneWay(_)
+        | Cell::Checkpoint => theme.path,
+        Cell::Void => theme.background,
+    }
+}
+
+fn opaque(color: Rgb<u8>) -> Rgba<u8> {
+    Rgba([color.0[0], color.0[1], color.0[2], 0xff])
+}
+
+/// Detail drawn over a cell's base color.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Overlay {
+    /// Bridge rail of a crossing, drawn in the wall color
+    Rail,
+    /// Key disc or door frame in the given key color
+    KeyColor(u8),
+    /// Portal ring in the color of the given portal letter
+    PortalColor(u8),
+    /// Arrow of a one-way cell, drawn in the wall color
+    Arrow,
+    /// Checkpoint diamond
+    Checkpoint,
+}
+
+/// Detail covering pixel (i, j) of a `scale`-sized cell, if any: rails along a
+/// crossing's upper corridor, a disc for a key, a frame for a door, a ring
+/// for a portal, an arrow for a one-way cell or a diamond for a checkpoint.
+pub(crate) fn cell_overlay(cell: &Cell, i: u32, j: u32, scale: u32) -> Option<Overlay> {
+    match *cell {
+        Cell::Crossing(_) if is_bridge_rail(cell, i, j, scale) => Some(Overlay::Rail),
+        Cell::Key(color) if scale < 3 || is_key_disc(i, j, scale) => Some(Overlay::KeyColor(color)),
+        Cell::Door(color) if scale < 3 || is_door_frame(i, j, scale) => {
+            Some(Overlay::KeyColor(color))
+        }
+        Cell::Portal(letter) if scale < 3 || is_portal_ring(i, j, scale) => {
+            Some(Overlay::PortalColor(letter))
+        }
+        Cell::OneWay(direction) if is_arrow(direction, i, j, scale) => Some(Overlay::Arrow),
+        Cell::Checkpoint if scale < 3 || is_checkpoint_diamond(i, j, scale) => {
+            Some(Overlay::Checkpoint)
+        }
+        _ => None,
+    }
+}
+
+pub(crate) fn is_key_disc(i: u32, j: u32, scale: u32) -> bool {
+    let center = scale as f64 / 2.0;
+    let (dy, dx) = (i as f64 + 0.5 - center, j as f64 + 0.5 - center);
+    dx * dx + dy * dy <= (0.3 * scale as f64).powi(2)
+}
+
+fn is_portal_ring(i: u32, j: u32, scale: u32) -> bool {
+    let center = scale as f64 / 2.0;
+    let (dy, dx) = (i as f64 + 0.5 - center, j as f64 + 0.5 - center);
+    let distance = (dx * dx + dy * dy).sqrt() / scale as f64;
+    (0.2..=0.45).contains(&distance)
+}
+
+fn is_checkpoint_diamond(i: u32, j: u32, scale: u32) -> bool {
+    let center = scale as f64 / 2.0;
+    let (dy, dx) = (i as f64 + 0.5 - center, j as f64 + 0.5 - center);
+    dx.abs() + dy.abs() <= CHECKPOINT_RADIUS * scale as f64
+}
+
+/// Whether pixel (i, j) of a `scale`-sized cell lies on the triangle pointing
+/// the way a one-way cell is passed. Cells too small to show it have none.
+fn is_arrow(direction: Direction, i: u32, j: u32, scale: u32) -> bool {
+    if scale < 5 {
+        return false;
+    }
+    let (u, v) = (
+        (j as f64 + 0.5) / scale as f64,
+        (i as f64 + 0.5) / scale as f64,
+    );
+    // distance along the arrow from the cell's back edge, and across it
+    let (along, across) = match direction {
+        Direction::East => (u, v),
+        Direction::West => (1.0 - u, v),
+        Direction::South => (v, u),
+        Direction::North => (1.0 - v, u),
+    };
+    (ARROW_BACK..=ARROW_TIP).contains(&along)
+        && (across - 0.5).abs() <= ARROW_HALF_WIDTH * (ARROW_TIP - along) / (ARROW_TIP - ARROW_BACK)
+}
+
+// a door keeps its middle free so a path through it stays visible
+pub(crate) fn is_door_frame(i: u32, j: u32, scale: u32) -> bool {
+    let thickness = (scale / 4).max(1);
+    [i, j]
+        .iter()
+        .any(|&offset| offset < thickness || offset >= scale - thickness)
+}
+
+pub(crate) fn overlay_color(theme: &Theme, overlay: Overlay) -> Rgb<u8> {
+    match overlay {
+        Overlay::Rail => theme.wall,
+        Overlay::KeyColor(color) => theme.key_doors[color as usize],
+        Overlay::PortalColor(letter) => portal_color(theme, letter),
+        Overlay::Arrow => theme.wall,
+        Overlay::Checkpoint => theme.checkpoint,
+    }
+}
+
+/// Color a portal of the given letter is drawn in; both ends of a pair share it
+pub(crate) fn portal_color(theme: &Theme, letter: u8) -> Rgb<u8> {
+    theme.portals[letter as usize % PORTAL_COLOR_COUNT]
+}
+
+/// Whether pixel (i, j) of a `scale`-sized cell lies on the rails drawn along both
+/// sides of a crossing's upper corridor, which make it read as a bridge over the
+/// corridor running underneath. Cells too small to show rails have none.
+fn is_bridge_rail(cell: &Cell, i: u32, j: u32, scale: u32) -> bool {
+    let Cell::Crossing(over) = cell else {
+        return false;
+    };
+    if scale < 5 {
+        return false;
+    }
+    let inset = scale / 5;
+    let thickness = (scale / 10).max(1);
+    let on_rail = |offset: u32| {
+        (inset..inset + thickness).contains(&offset)
+            || (scale - inset - thickness..scale - inset).contains(&offset)
+    };
+    match over {
+        Axis::Horizontal => on_rail(i),
+        Axis::Vertical => on_rail(j),
+    }
+}
+
+// recovers a crossing from the rails drawn by `is_bridge_rail`
+fn crossing_at(theme: &Theme, img: &RgbaImage, x: usize, y: usize, scale: u32) -> Option<Cell> {
+    if scale < 5 {
+        return None;
+    }
+    let (left, top) = (x as u32 * scale, y as u32 * scale);
+    let (inset, middle) = (scale / 5, scale / 2);
+    if *img.get_pixel(left + middle, top + inset) == opaque(theme.wall) {
+        Some(Cell::Crossing(Axis::Horizontal))
+    } else if *img.get_pixel(left + inset, top + middle) == opaque(theme.wall) {
+        Some(Cell::Crossing(Axis::Vertical))
+    } else {
+        None
+    }
+}
+
+// recovers a key or door from its color: doors are framed, keys are discs
+fn key_or_door_at(theme: &Theme, img: &RgbaImage, x: usize, y: usize, scale: u32) -> Option<Cell> {
+    let (left, top) = (x as u32 * scale, y as u32 * scale);
+    let color_at = |i: u32, j: u32| {
+        let pixel = img.get_pixel(left + j, top + i);
+        theme
+            .key_doors
+            .iter()
+            .position(|&color| opaque(color) == *pixel)
+            .map(|color| color as u8)
+    };
+    if scale < 3 {
+        // too small to tell apart; a lone color is read as a key
+        return color_at(0, 0).map(Cell::Key);
+    }
+    color_at(0, 0)
+        .map(Cell::Door)
+        .or_else(|| color_at(scale / 2, scale / 2).map(Cell::Key))
+}
+
+// recovers a portal from its ring; the letter is only known up to the color
+// cycle, so more pairs than portal colors do not survive the round trip
+fn portal_at(theme: &Theme, img: &RgbaImage, x: usize, y: usize, scale: u32) -> Option<Cell> {
+    let (left, top) = (x as u32 * scale, y as u32 * scale);
+    let (i, j) = if scale < 3 {
+        (0, 0)
+    } else {
+        (scale / 2, scale / 2 + (scale * 3 + 5) / 10)
+    };
+    let pixel = img.get_pixel(left + j, top + i);
+    theme
+        .portals
+        .iter()
+        .position(|&color| opaque(color) == *pixel)
+        .map(|letter| Cell::Portal(letter as u8))
+}
+
+fn checkpoint_at(theme: &Theme, img: &RgbaImage, x: usize, y: usize, scale: u32) -> Option<Cell> {
+    let pixel = img.get_pixel(x as u32 * scale + scale / 2, y as u32 * scale + scale / 2);
+    (*pixel == opaque(theme.checkpoint)).then_some(Cell::Checkpoint)
+}
+
 pub fn load_maze_from_image(path: &str) -> Result<Maze, String> {
     load_maze_from_image_scaled(path, 1)
 }
 
 pub fn load_maze_from_image_scaled(path: &str, scale: u32) -> Result<Maze, String> {
-    let img = image::open(path).map_err(|e| e.to_string())?.to_rgb8();
+    let img = image::open(path).map_err(|e| e.to_string())?.to_rgba8();
     let (width, height) = img.dimensions();
 
     if width % scale != 0 || height % scale != 0 {
@@ -100,34 +378,57 @@ pub fn load_maze_from_image_scaled(path: &str, scale: u32) -> Result<Maze, Strin
     let mut cells = vec![vec![Cell::Path; maze_width]; maze_height];
     let mut start = None;
     let mut end = None;
+    let mut wrap = Wrap::None;
 
-    for y in 0..maze_height {
-        for x in 0..maze_width {
-            let pixel = img.get_pixel(x as u32 * scale, y as u32 * scale);
-            match *pixel {
-                START_COLOR => {
-                    if start.is_some() {
-                        return Err("Multiple start points found".to_string());
-                    }
-                    start = Some((y, x));
-                    cells[y][x] = Cell::Path;
-                }
-                END_COLOR => {
-                    if end.is_some() {
-                        return Err("Multiple end points found".to_string());
-                    }
-                    end = Some((y, x));
-                    cells[y][x] = Cell::Path;
-                }
-                WALL_COLOR => {
-                    cells[y][x] = Cell::Wall;
+    let theme = theme();
+    for (y, row) in cells.iter_mut().enumerate() {
+        for (x, cell) in row.iter_mut().enumerate() {
+            let [r, g, b, a] = img.get_pixel(x as u32 * scale, y as u32 * scale).0;
+            if a == 0 {
+                *cell = Cell::Void;
+                continue;
+            }
+            let color = Rgb([r, g, b]);
+            if color == theme.start {
+                if start.is_some() {
+                    return Err("Multiple start points found".to_string());
                 }
-                PATH_COLOR => {
-                    cells[y][x] = Cell::Path;
+                start = Some((y, x));
+                *cell = Cell::Path;
+            } else if color == theme.end {
+                if end.is_some() {
+                    return Err("Multiple end points found".to_string());
                 }
-                _ => {
-                    cells[y][x] = Cell::Path;
+                end = Some((y, x));
+                *cell = Cell::Path;
+            } else if color == theme.wall {
+                *cell = Cell::Wall;
+            } else if color == theme.checkpoint {
+                // a checkpoint too small to draw as a diamond
+                *cell = Cell::Checkpoint;
+            } else if theme.portals.contains(&color) {
+                // a portal too small to draw as a ring
+                *cell = portal_at(&theme, &img, x, y, scale).unwrap_or(Cell::Path);
+            } else if theme.key_doors.contains(&color) {
+                // the corner of a door frame, or a key too small to draw as a disc
+                *cell = key_or_door_at(&theme, &img, x, y, scale).unwrap_or(Cell::Path);
+            } else if color == theme.wrap {
+                // seam passages on the top row only exist on a torus
+                if y == 0 {
+                    wrap = Wrap::Torus;
+                } else if wrap == Wrap::None {
+                    wrap = Wrap::Cylinder;
                 }
+                *cell = Cell::Path;
+            } else {
+                *cell = Cell::Path;
+            }
+            if *cell == Cell::Path {
+                *cell = crossing_at(&theme, &img, x, y, scale)
+                    .or_else(|| key_or_door_at(&theme, &img, x, y, scale))
+                    .or_else(|| portal_at(&theme, &img, x, y, scale))
+                    .or_else(|| checkpoint_at(&theme, &img, x, y, scale))
+                    .unwrap_or(Cell::Path);
             }
         }
     }
@@ -135,5 +436,7 @@ pub fn load_maze_from_image_scaled(path: &str, scale: u32) -> Result<Maze, Strin
     let start = start.ok_or_else(|| "No start point found".to_string())?;
     let end = end.ok_or_else(|| "No end point found".to_string())?;
 
-    Ok(Maze { cells, start, end })
+    let mut maze = Maze::new(cells, start, end);
+    maze.wrap = wrap;
+    Ok(maze)
 }
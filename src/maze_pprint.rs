@@ -1,32 +1,126 @@
-use crate::cell::Cell;
+use crate::cell::{Axis, Cell, CHECKPOINT_CHAR, DOOR_CHARS, KEY_CHARS};
+use crate::colors::theme;
 use crate::maze::Maze;
+use crate::maze_image::{base_color, cell_overlay, overlay_color};
+use image::Rgb;
 use std::collections::HashSet;
 
 pub fn display_maze_with_path(maze: &Maze, path: &[(usize, usize)]) -> String {
     let mut result = String::new();
     let path_set: HashSet<(usize, usize)> = path.iter().copied().collect();
 
+    for (row_idx, row) in maze.cells.iter().enumerate() {
+        for col_idx in 0..row.len() {
+            result.push(cell_char(maze, &path_set, row_idx, col_idx));
+        }
+        result.push('\n');
+    }
+
+    result
+}
+
+/// Like `display_maze_with_path`, with each cell colored as in a PNG of the
+/// maze with one pixel per cell, in the current theme, using 24-bit ANSI colors.
+pub fn display_maze_with_path_colored(maze: &Maze, path: &[(usize, usize)]) -> String {
+    let mut result = String::new();
+    let path_set: HashSet<(usize, usize)> = path.iter().copied().collect();
+    let theme = theme();
+
     for (row_idx, row) in maze.cells.iter().enumerate() {
         for (col_idx, cell) in row.iter().enumerate() {
-            let ch = if path_set.contains(&(row_idx, col_idx)) {
-                // Prefer Start and End symbols even if in path
-                if (row_idx, col_idx) == maze.start {
-                    'S'
-                } else if (row_idx, col_idx) == maze.end {
-                    'E'
-                } else {
-                    '@'
-                }
+            let ch = cell_char(maze, &path_set, row_idx, col_idx);
+            if *cell == Cell::Void {
+                result.push_str("\x1b[0m");
+                result.push(ch);
+                continue;
+            }
+            let position = (row_idx, col_idx);
+            let Rgb([r, g, b]) = if position == maze.start {
+                theme.start
+            } else if position == maze.end {
+                theme.end
+            } else if path_set.contains(&position) {
+                theme.solution_path
+            } else if let Some(overlay) = cell_overlay(cell, 0, 0, 1) {
+                overlay_color(&theme, overlay)
             } else {
-                match cell {
-                    Cell::Wall => '#',
-                    Cell::Path => '.',
-                }
+                base_color(&theme, maze, row_idx, col_idx, cell)
             };
-            result.push(ch);
+            // dark text on light cells and light text on dark ones
+            let luma = 299 * r as u32 + 587 * g as u32 + 114 * b as u32;
+            let text = if luma > 140_000 { 0 } else { 255 };
+            result.push_str(&format!(
+                "\x1b[48;2;{};{};{}m\x1b[38;2;{t};{t};{t}m{}",
+                r,
+                g,
+                b,
+                ch,
+                t = text
+            ));
         }
-        result.push('\n');
+        result.push_str("\x1b[0m\n");
     }
 
     result
 }
+
+fn cell_char(
+    maze: &Maze,
+    path_set: &HashSet<(usize, usize)>,
+    row_idx: usize,
+    col_idx: usize,
+) -> char {
+    if path_set.contains(&(row_idx, col_idx)) {
+        // Prefer Start and End symbols even if in path
+        if (row_idx, col_idx) == maze.start {
+            'S'
+        } else if (row_idx, col_idx) == maze.end {
+            'E'
+        } else {
+            '@'
+        }
+    } else {
+        match &maze.cells[row_idx][col_idx] {
+            Cell::Wall => '#',
+            Cell::Path => '.',
+            Cell::Crossing(Axis::Horizontal) => '-',
+            Cell::Crossing(Axis::Vertical) => '|',
+            Cell::Void => ' ',
+            Cell::Key(color) => KEY_CHARS[*color as usize],
+            Cell::Door(color) => DOOR_CHARS[*color as usize],
+            Cell::Portal(letter) => Cell::portal_char(*letter),
+            Cell::OneWay(direction) => direction.arrow(),
+            Cell::Checkpoint => CHECKPOINT_CHAR,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::colors::{set_theme, Theme};
+
+    #[test]
+    fn test_colored_display_follows_theme() {
+        let mut cells = vec![vec![Cell::Wall; 5]; 3];
+        cells[1][1..4].fill(Cell::Path);
+        let maze = Maze::new(cells, (1, 1), (1, 3));
+        let path = [(1, 1), (1, 2), (1, 3)];
+        let plain = display_maze_with_path(&maze, &path);
+        assert_eq!(plain, "#####\n#S@E#\n#####\n");
+
+        set_theme(Theme::DARK);
+        let colored = display_maze_with_path_colored(&maze, &path);
+        set_theme(Theme::TAB10);
+        let [r, g, b] = Theme::DARK.start.0;
+        assert!(colored.contains(&format!("\x1b[48;2;{};{};{}m", r, g, b)));
+        assert_ne!(colored, display_maze_with_path_colored(&maze, &path));
+        // stripping the escape codes leaves the plain display
+        let mut stripped = colored.clone();
+        while let Some(start) = stripped.find('\x1b') {
+            let end = start + stripped[start..].find('m').unwrap();
+            stripped.replace_range(start..=end, "");
+        }
+        assert_eq!(stripped, plain);
+    }
+}
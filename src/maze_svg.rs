@@ -0,0 +1,329 @@
+use crate::cell::{Axis, Cell, Direction};
+use crate::colors::theme;
+use crate::linked_maze_image::svg_color;
+use crate::maze::Maze;
+use crate::maze_image::{portal_color, ARROW_BACK, ARROW_HALF_WIDTH, ARROW_TIP, CHECKPOINT_RADIUS};
+use std::fmt::Write;
+use std::io;
+
+/// Distance of a bridge rail from the cell edge, as a fraction of the cell size.
+const RAIL_INSET: f64 = 0.2;
+
+pub fn save_maze_to_svg(maze: &Maze, file_path: &str, scale: u32) -> io::Result<()> {
+    save_maze_with_path_to_svg(maze, &[], file_path, scale)
+}
+
+pub fn save_maze_with_path_to_svg(
+    maze: &Maze,
+    path: &[(usize, usize)],
+    file_path: &str,
+    scale: u32,
+) -> io::Result<()> {
+    std::fs::write(file_path, maze_to_svg(maze, path, scale))
+}
+
+/// Saves a block maze with several routes overlaid, each in its own color.
+pub fn save_maze_with_paths_to_svg(
+    maze: &Maze,
+    paths: &[Vec<(usize, usize)>],
+    file_path: &str,
+    scale: u32,
+) -> io::Result<()> {
+    let paths: Vec<&[(usize, usize)]> = paths.iter().map(Vec::as_slice).collect();
+    std::fs::write(file_path, maze_with_paths_to_svg(maze, &paths, scale))
+}
+
+/// Renders a block maze, and optionally a path through it, as an SVG document.
+/// Crossings are drawn as bridges: rails line the upper corridor and a path
+/// running underneath disappears below the deck.
+pub fn maze_to_svg(maze: &Maze, path: &[(usize, usize)], scale: u32) -> String {
+    maze_with_paths_to_svg(maze, &[path], scale)
+}
+
+/// Like `maze_to_svg` with any number of paths, drawn in the theme's route colors and
+/// each a little thinner than the one before so shared stretches stay visible.
+pub fn maze_with_paths_to_svg(maze: &Maze, paths: &[&[(usize, usize)]], scale: u32) -> String {
+    let theme = theme();
+    let scale = scale as f64;
+    let rows = maze.cells.len();
+    let cols = maze.cells[0].len();
+    let mut svg = String::new();
+
+    let _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w:.0}" height="{h:.0}" viewBox="0 0 {w:.2} {h:.2}">"#,
+        w = cols as f64 * scale,
+        h = rows as f64 * scale,
+    );
+    // cells outside a shaped maze are left out, showing whatever lies behind
+    let mut open = String::new();
+    let mut walls = String::new();
+    let mut seams = String::new();
+    for (y, row) in maze.cells.iter().enumerate() {
+        for (x, cell) in row.iter().enumerate() {
+            let square = format!(
+                "M{:.2} {:.2}h{s:.2}v{s:.2}h{:.2}z",
+                x as f64 * scale,
+                y as f64 * scale,
+                -scale,
+                s = scale
+            );
+            match cell {
+                Cell::Wall => walls.push_str(&square),
+                Cell::Void => {}
+                Cell::Path if maze.is_wrap_seam(y, x) => seams.push_str(&square),
+                Cell::Path
+                | Cell::Crossing(_)
+                | Cell::Key(_)
+                | Cell::Door(_)
+                | Cell::Portal(_)
+                | Cell::OneWay(_)
+                | Cell::Checkpoint => open.push_str(&square),
+            }
+        }
+    }
+    for (squares, color) in [(open, theme.path), (walls, theme.wall), (seams, theme.wrap)] {
+        if !squares.is_empty() {
+            let _ = writeln!(
+                svg,
+                r#"<path d="{}" fill="{}"/>"#,
+                squares,
+                svg_color(color)
+            );
+        }
+    }
+
+    let center = |(y, x): (usize, usize)| ((x as f64 + 0.5) * scale, (y as f64 + 0.5) * scale);
+    for (index, path) in paths.iter().enumerate() {
+        let mut path_data = String::new();
+        // steps across a wrapping edge or through a portal are left out rather than drawn across the maze
+        for pair in path
+            .windows(2)
+            .filter(|pair| pair[0].0.abs_diff(pair[1].0) + pair[0].1.abs_diff(pair[1].1) == 1)
+        {
+            let (x1, y1) = center(pair[0]);
+            let (x2, y2) = center(pair[1]);
+            let _ = write!(path_data, "M{:.2} {:.2}L{:.2} {:.2}", x1, y1, x2, y2);
+        }
+        if !path_data.is_empty() {
+            let share = (paths.len() - index) as f64 / paths.len() as f64;
+            let _ = writeln!(
+                svg,
+                r#"<path d="{}" stroke="{}" stroke-width="{:.2}" stroke-linecap="round" fill="none"/>"#,
+                path_data,
+                svg_color(theme.routes[index % theme.routes.len()]),
+                (scale * 0.25 * share).max(1.0)
+            );
+        }
+    }
+
+    let mut decks = String::new();
+    let mut rails = String::new();
+    for (y, row) in maze.cells.iter().enumerate() {
+        for (x, cell) in row.iter().enumerate() {
+            let Cell::Crossing(over) = *cell else {
+                continue;
+            };
+            let (left, top) = (x as f64 * scale, y as f64 * scale);
+            let inset = RAIL_INSET * scale;
+            if paths.iter().any(|path| passes_under(path, (y, x), over)) {
+                let _ = match over {
+                    Axis::Horizontal => write!(
+                        decks,
+                        "M{:.2} {:.2}h{s:.2}v{:.2}h{:.2}z",
+                        left,
+                        top + inset,
+                        scale - 2.0 * inset,
+                        -scale,
+                        s = scale
+                    ),
+                    Axis::Vertical => write!(
+                        decks,
+                        "M{:.2} {:.2}h{:.2}v{s:.2}h{:.2}z",
+                        left + inset,
+                        top,
+                        scale - 2.0 * inset,
+                        2.0 * inset - scale,
+                        s = scale
+                    ),
+                };
+            }
+            let _ = match over {
+                Axis::Horizontal => write!(
+                    rails,
+                    "M{:.2} {:.2}h{s:.2}M{:.2} {:.2}h{s:.2}",
+                    left,
+                    top + inset,
+                    left,
+                    top + scale - inset,
+                    s = scale
+                ),
+                Axis::Vertical => write!(
+                    rails,
+                    "M{:.2} {:.2}v{s:.2}M{:.2} {:.2}v{s:.2}",
+                    left + inset,
+                    top,
+                    left + scale - inset,
+                    top,
+                    s = scale
+                ),
+            };
+        }
+    }
+    if !decks.is_empty() {
+        let _ = writeln!(
+            svg,
+            r#"<path d="{}" fill="{}"/>"#,
+            decks,
+            svg_color(theme.path)
+        );
+    }
+    if !rails.is_empty() {
+        let _ = writeln!(
+            svg,
+            r#"<path d="{}" stroke="{}" stroke-width="{:.2}" fill="none"/>"#,
+            rails,
+            svg_color(theme.wall),
+            (scale * 0.1).max(1.0)
+        );
+    }
+
+    // keys are discs, doors are frames, portals are rings, one-way cells show
+    // arrows and checkpoints diamonds, leaving a path through them visible
+    for (y, row) in maze.cells.iter().enumerate() {
+        for (x, cell) in row.iter().enumerate() {
+            match *cell {
+                Cell::Key(color) => {
+                    let (cx, cy) = center((y, x));
+                    let _ = writeln!(
+                        svg,
+                        r#"<circle cx="{:.2}" cy="{:.2}" r="{:.2}" fill="{}"/>"#,
+                        cx,
+                        cy,
+                        scale * 0.3,
+                        svg_color(theme.key_doors[color as usize])
+                    );
+                }
+                Cell::Door(color) => {
+                    let thickness = scale * 0.25;
+                    let _ = writeln!(
+                        svg,
+                        r#"<rect x="{:.2}" y="{:.2}" width="{s:.2}" height="{s:.2}" stroke="{}" stroke-width="{:.2}" fill="none"/>"#,
+                        x as f64 * scale + thickness / 2.0,
+                        y as f64 * scale + thickness / 2.0,
+                        svg_color(theme.key_doors[color as usize]),
+                        thickness,
+                        s = scale - thickness
+                    );
+                }
+                Cell::Portal(letter) => {
+                    let (cx, cy) = center((y, x));
+                    let _ = writeln!(
+                        svg,
+                        r#"<circle cx="{:.2}" cy="{:.2}" r="{:.2}" stroke="{}" stroke-width="{:.2}" fill="none"/>"#,
+                        cx,
+                        cy,
+                        scale * 0.325,
+                        svg_color(portal_color(&theme, letter)),
+                        scale * 0.25
+                    );
+                }
+                Cell::OneWay(direction) => {
+                    let (left, top) = (x as f64 * scale, y as f64 * scale);
+                    // corners given as (along, across) the arrow
+                    let points: Vec<String> = [
+                        (ARROW_BACK, 0.5 - ARROW_HALF_WIDTH),
+                        (ARROW_TIP, 0.5),
+                        (ARROW_BACK, 0.5 + ARROW_HALF_WIDTH),
+                    ]
+                    .iter()
+                    .map(|&(along, across)| {
+                        let (u, v) = match direction {
+                            Direction::East => (along, across),
+                            Direction::West => (1.0 - along, across),
+                            Direction::South => (across, along),
+                            Direction::North => (across, 1.0 - along),
+                        };
+                        format!("{:.2},{:.2}", left + u * scale, top + v * scale)
+                    })
+                    .collect();
+                    let _ = writeln!(
+                        svg,
+                        r#"<polygon points="{}" fill="{}"/>"#,
+                        points.join(" "),
+                        svg_color(theme.wall)
+                    );
+                }
+                Cell::Checkpoint => {
+                    let (cx, cy) = center((y, x));
+                    let r = CHECKPOINT_RADIUS * scale;
+                    let _ = writeln!(
+                        svg,
+                        r#"<polygon points="{:.2},{:.2} {:.2},{:.2} {:.2},{:.2} {:.2},{:.2}" fill="{}"/>"#,
+                        cx,
+                        cy - r,
+                        cx + r,
+                        cy,
+                        cx,
+                        cy + r,
+                        cx - r,
+                        cy,
+                        svg_color(theme.checkpoint)
+                    );
+                }
+                _ => {}
+            }
+        }
+    }
+
+    for ((y, x), color) in [(maze.start, theme.start), (maze.end, theme.end)] {
+        let _ = writeln!(
+            svg,
+            r#"<rect x="{:.2}" y="{:.2}" width="{s:.2}" height="{s:.2}" fill="{}"/>"#,
+            x as f64 * scale,
+            y as f64 * scale,
+            svg_color(color),
+            s = scale
+        );
+    }
+
+    svg.push_str("</svg>\n");
+    svg
+}
+
+// whether the path crosses `cell` along the corridor running below `over`
+fn passes_under(path: &[(usize, usize)], cell: (usize, usize), over: Axis) -> bool {
+    path.windows(2)
+        .any(|pair| pair.contains(&cell) && Axis::of_step(pair[0], pair[1]) != over)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::colors::theme;
+
+    fn crossing_maze() -> Maze {
+        let mut cells = vec![vec![Cell::Wall; 5]; 5];
+        cells[2][1..4].fill(Cell::Path);
+        for row in &mut cells[1..4] {
+            row[2] = Cell::Path;
+        }
+        cells[2][2] = Cell::Crossing(Axis::Vertical);
+        Maze::new(cells, (2, 1), (2, 3))
+    }
+
+    #[test]
+    fn test_svg_draws_deck_over_path_passing_under() {
+        let maze = crossing_maze();
+        let deck = r#"<path d="M22.00 20.00h6.00v10.00"#;
+
+        let under = maze_to_svg(&maze, &[(2, 1), (2, 2), (2, 3)], 10);
+        assert!(under.contains(deck));
+        assert!(under.contains(&svg_color(theme().solution_path)));
+
+        let unsolved = maze_to_svg(&maze, &[], 10);
+        assert!(!unsolved.contains(deck));
+        // the rails are drawn either way
+        assert!(unsolved.contains("v10.00M"));
+    }
+}
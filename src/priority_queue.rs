@@ -33,3 +33,9 @@ impl<T: Ord> PriorityQueue<T> {
         self.heap.peek().map(|Reverse(item)| item)
     }
 }
+
+impl<T: Ord> Default for PriorityQueue<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
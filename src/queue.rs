@@ -31,3 +31,9 @@ impl<T> Queue<T> {
         self.queue.front()
     }
 }
+
+impl<T> Default for Queue<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,74 +1,84 @@
-use crate::maze::Maze;
+use crate::cell::Cell;
+use crate::maze_graph::MazeGraph;
 use crate::priority_queue::PriorityQueue;
 use crate::progress::ProgressTracker;
-use crate::solvers::solver::Solver;
-use std::collections::HashMap;
+use crate::solvers::solver::{parent_array, trace_path, SolveResult, Solver};
+use std::time::Instant;
 
 pub struct AstarSolver;
 
 impl Solver for AstarSolver {
-    fn solve(
+    fn search(
         &self,
-        maze: &Maze,
+        graph: &dyn MazeGraph,
         mut tracker: Option<&mut ProgressTracker>,
-    ) -> Option<Vec<(usize, usize)>> {
-        let graph = maze.to_graph();
+    ) -> SolveResult<usize> {
+        let started = Instant::now();
+        let mut nodes_expanded = 0;
+        let mut max_frontier = 1;
+        let start = graph.start_node();
 
         let mut queue = PriorityQueue::new();
-        let mut distances: HashMap<(usize, usize), (usize, (usize, usize))> = HashMap::new();
+        // 32-bit distances and parents keep the per-node arrays small on huge grids
+        let mut distances = vec![u32::MAX; graph.node_count()];
+        let mut parent = parent_array(graph);
 
-        distances.insert(maze.start, (0, maze.start));
-        queue.push((heuristic(maze.start, maze.end), maze.start));
+        distances[start] = 0;
+        queue.push((graph.heuristic(start), start));
 
-        while let Some((_, current_node)) = queue.pop() {
+        while let Some((priority, current_node)) = queue.pop() {
+            // an entry left behind when the node was reached more cheaply later
+            if priority > distances[current_node] as usize + graph.heuristic(current_node) {
+                continue;
+            }
+            nodes_expanded += 1;
             if let Some(ref mut t) = tracker {
-                t.record(current_node.0, current_node.1, crate::cell::Cell::Path);
+                let (row, col) = graph.position(current_node);
+                t.record(row, col, Cell::Path);
             }
 
-            if current_node == maze.end {
-                let mut path = vec![current_node];
-                let mut current = current_node;
-
-                while current != maze.start {
-                    if let Some(&(_, prev)) = distances.get(&current) {
-                        path.push(prev);
-                        current = prev;
-                    } else {
-                        break; // should never happen
-                    }
-                }
-                path.reverse();
-                return Some(path);
+            if graph.is_goal(current_node) {
+                return SolveResult::new(
+                    graph,
+                    Some(trace_path(&parent, start, current_node)),
+                    nodes_expanded,
+                    max_frontier,
+                    true,
+                    started,
+                );
             }
-            let current_cost = distances
-                .get(&current_node)
-                .map(|x| x.0)
-                .unwrap_or(usize::MAX);
+            let current_cost = distances[current_node] as usize;
 
-            for &(neighbor, weight) in graph.get(&current_node).unwrap_or(&vec![]) {
+            graph.for_each_neighbor(current_node, &mut |neighbor, weight| {
                 let new_cost = current_cost + weight;
-                if new_cost < distances.get(&neighbor).map(|x| x.0).unwrap_or(usize::MAX) {
-                    distances.insert(neighbor, (new_cost, current_node));
+                if new_cost < distances[neighbor] as usize {
+                    distances[neighbor] = new_cost as u32;
+                    parent[neighbor] = current_node as u32;
 
-                    let priority = new_cost + heuristic(neighbor, maze.end);
+                    let priority = new_cost + graph.heuristic(neighbor);
                     queue.push((priority, neighbor));
                 }
-            }
+            });
+            max_frontier = max_frontier.max(queue.length());
         }
-        None // No path found
+        // No path found
+        SolveResult::new(graph, None, nodes_expanded, max_frontier, true, started)
     }
 }
 
-fn heuristic(a: (usize, usize), b: (usize, usize)) -> usize {
-    ((a.0 as isize - b.0 as isize).abs() + (a.1 as isize - b.1 as isize).abs()) as usize
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::cell::Cell;
+    use crate::generators::generator::MazeGenerator;
+    use crate::generators::kruskal::KruskalMazeGenerator;
+    use crate::linked_maze::{LinkedMaze, Passages};
     use crate::maze::Maze;
+    use crate::solvers::dfs_solver::DfsSolver;
     use crate::solvers::solver::Solver;
+    use crate::solvers::solver::StaleEntryGraph;
+    use crate::topologies::hex::HexTopology;
+    use std::collections::HashSet;
 
     #[test]
     fn test_astar_solver_finds_path() {
@@ -80,7 +90,7 @@ mod tests {
         let start = (0, 0);
         let end = (2, 2);
 
-        let maze = Maze { cells, start, end };
+        let maze = Maze::new(cells, start, end);
         let solver = AstarSolver;
 
         let path = solver.solve(&maze, None);
@@ -100,6 +110,26 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_astar_result_reports_metrics() {
+        let cells = vec![
+            vec![Cell::Path, Cell::Wall, Cell::Path],
+            vec![Cell::Path, Cell::Path, Cell::Path],
+            vec![Cell::Wall, Cell::Path, Cell::Path],
+        ];
+        let maze = Maze::new(cells, (0, 0), (2, 2));
+
+        let result = AstarSolver.solve_with_result(&maze, None);
+        assert_eq!(result.path.as_ref().map(Vec::len), Some(5));
+        assert_eq!(result.cost, Some(4));
+        assert!(result.optimal);
+        assert!(result.nodes_expanded >= 5);
+        assert!(result.max_frontier >= 1);
+
+        let dfs = DfsSolver.solve_with_result(&maze, None);
+        assert!(!dfs.optimal);
+    }
+
     #[test]
     fn test_astar_solver_no_path() {
         let cells = vec![
@@ -110,10 +140,70 @@ mod tests {
         let start = (0, 0);
         let end = (2, 2);
 
-        let maze = Maze { cells, start, end };
+        let maze = Maze::new(cells, start, end);
         let solver = AstarSolver;
 
         let path = solver.solve(&maze, None);
         assert!(path.is_none(), "Expected no path due to walls");
     }
+
+    #[test]
+    fn test_astar_solver_on_hex_maze() {
+        let hex = HexTopology::new(2, 2);
+        let mut passages = Passages::new(4);
+        passages.link(0, 1);
+        passages.link(1, 3);
+        passages.link(3, 2);
+        let maze = LinkedMaze::new(hex, passages, 0, 2);
+
+        let path = AstarSolver.solve_graph(&maze, None);
+        assert_eq!(path, Some(vec![0, 1, 3, 2]));
+    }
+
+    #[test]
+    fn test_astar_solver_takes_portal_shortcut() {
+        // ###########
+        // #a.S.....E#
+        // #.#######.#
+        // #........a#
+        // ###########
+        let mut cells = vec![vec![Cell::Wall; 11]; 5];
+        cells[1][1..10].fill(Cell::Path);
+        cells[3][1..10].fill(Cell::Path);
+        cells[2][1] = Cell::Path;
+        cells[2][9] = Cell::Path;
+        cells[1][1] = Cell::Portal(0);
+        cells[3][9] = Cell::Portal(0);
+        let maze = Maze::new(cells, (1, 3), (1, 9));
+
+        // the end is 6 steps straight ahead, through the portal it is 2 + 1 + 2
+        let path = AstarSolver.solve(&maze, None).unwrap();
+        assert_eq!(path.len() - 1, 5);
+        assert_eq!(path[..4], [(1, 3), (1, 2), (1, 1), (3, 9)]);
+    }
+
+    #[test]
+    fn test_astar_expands_each_cell_at_most_once() {
+        for _ in 0..10 {
+            let maze = KruskalMazeGenerator
+                .generate(41, 41, None, None, 30.0, None)
+                .unwrap();
+            let open = maze
+                .cells
+                .iter()
+                .flatten()
+                .filter(|cell| cell.is_open())
+                .count();
+            let mut tracker = ProgressTracker::new(true);
+            let result = AstarSolver.solve_with_result(&maze, Some(&mut tracker));
+            assert!(result.path.is_some());
+            let expanded: HashSet<_> = tracker.history.iter().map(|&(y, x, _)| (y, x)).collect();
+            assert_eq!(result.nodes_expanded, expanded.len());
+            assert!(result.nodes_expanded <= open);
+        }
+
+        let result = AstarSolver.search(&StaleEntryGraph, None);
+        assert_eq!(result.cost, Some(12));
+        assert_eq!(result.nodes_expanded, StaleEntryGraph.node_count());
+    }
 }
@@ -1,54 +1,69 @@
+use crate::bit_grid::BitSet;
 use crate::cell::Cell;
-use crate::maze::Maze;
+use crate::maze_graph::MazeGraph;
 use crate::progress::ProgressTracker;
 use crate::queue::Queue;
-use crate::solvers::solver::Solver;
-use std::collections::{HashMap, HashSet};
+use crate::solvers::solver::{parent_array, trace_path, SolveResult, Solver};
+use std::time::Instant;
 
 pub struct BfsSolver;
 
 impl Solver for BfsSolver {
-    fn solve(
+    fn search(
         &self,
-        maze: &Maze,
+        graph: &dyn MazeGraph,
         mut tracker: Option<&mut ProgressTracker>,
-    ) -> Option<Vec<(usize, usize)>> {
-        let mut visited = HashSet::new();
+    ) -> SolveResult<usize> {
+        let started = Instant::now();
+        let mut nodes_expanded = 0;
+        let mut max_frontier = 1;
+        // breadth-first order only guarantees the cheapest path with unit weights
+        let mut unit_weights = true;
+        let start = graph.start_node();
+        // nodes are marked when first queued, so each is reached along a shortest path
+        let mut discovered = BitSet::new(graph.node_count());
         let mut queue = Queue::new();
-        let mut parent: HashMap<(usize, usize), (usize, usize)> = HashMap::new();
+        let mut parent = parent_array(graph);
 
-        queue.enqueue(maze.start);
+        discovered.insert(start);
+        queue.enqueue(start);
 
-        while let Some((row, col)) = queue.dequeue() {
-            visited.insert((row, col));
+        while let Some(node) = queue.dequeue() {
+            nodes_expanded += 1;
             if let Some(ref mut t) = tracker {
-                t.record(row, col, crate::cell::Cell::Path);
+                let (row, col) = graph.position(node);
+                t.record(row, col, Cell::Path);
             }
 
-            if (row, col) == maze.end {
-                let mut path = vec![(row, col)];
-                let mut current = (row, col);
-
-                while let Some(&prev) = parent.get(&current) {
-                    path.push(prev);
-                    current = prev;
-                }
+            if graph.is_goal(node) {
+                return SolveResult::new(
+                    graph,
+                    Some(trace_path(&parent, start, node)),
+                    nodes_expanded,
+                    max_frontier,
+                    unit_weights,
+                    started,
+                );
+            }
 
-                path.reverse();
-                return Some(path);
-            } else if maze.cells[row][col] != Cell::Wall {
-                let neighbors =
-                    Maze::get_neighbors(row, col, maze.cells.len(), maze.cells[0].len());
-                for (n_row, n_col) in neighbors {
-                    if maze.cells[n_row][n_col] != Cell::Wall && !visited.contains(&(n_row, n_col))
-                    {
-                        parent.insert((n_row, n_col), (row, col));
-                        queue.enqueue((n_row, n_col));
-                    }
+            graph.for_each_neighbor(node, &mut |neighbor, weight| {
+                unit_weights &= weight == 1;
+                if discovered.insert(neighbor) {
+                    parent[neighbor] = node as u32;
+                    queue.enqueue(neighbor);
                 }
-            }
+            });
+            max_frontier = max_frontier.max(queue.length());
         }
-        None // No path found
+        // No path found
+        SolveResult::new(
+            graph,
+            None,
+            nodes_expanded,
+            max_frontier,
+            unit_weights,
+            started,
+        )
     }
 }
 
@@ -69,7 +84,7 @@ mod tests {
         let start = (0, 0);
         let end = (2, 2);
 
-        let maze = Maze { cells, start, end };
+        let maze = Maze::new(cells, start, end);
 
         let solver = BfsSolver;
         let path = solver.solve(&maze, None);
@@ -102,7 +117,7 @@ mod tests {
         let start = (0, 0);
         let end = (2, 2);
 
-        let maze = Maze { cells, start, end };
+        let maze = Maze::new(cells, start, end);
 
         let solver = BfsSolver;
         let path = solver.solve(&maze, None);
@@ -1,54 +1,61 @@
+use crate::bit_grid::BitSet;
 use crate::cell::Cell;
-use crate::maze::Maze;
+use crate::maze_graph::MazeGraph;
 use crate::progress::ProgressTracker;
-use crate::solvers::solver::Solver;
+use crate::solvers::solver::{parent_array, trace_path, SolveResult, Solver};
 use crate::stack::Stack;
-use std::collections::{HashMap, HashSet};
+use std::time::Instant;
 
 pub struct DfsSolver;
 
 impl Solver for DfsSolver {
-    fn solve(
+    fn search(
         &self,
-        maze: &Maze,
+        graph: &dyn MazeGraph,
         mut tracker: Option<&mut ProgressTracker>,
-    ) -> Option<Vec<(usize, usize)>> {
+    ) -> SolveResult<usize> {
+        let started = Instant::now();
+        let mut nodes_expanded = 0;
+        let mut max_frontier = 1;
         let mut stack = Stack::new();
-        let mut visited: HashSet<(usize, usize)> = HashSet::new();
-        let mut parent: HashMap<(usize, usize), (usize, usize)> = HashMap::new(); // To track the path
+        let start = graph.start_node();
+        let mut visited = BitSet::new(graph.node_count());
+        let mut parent = parent_array(graph); // To track the path
 
-        stack.push(maze.start);
+        stack.push(start);
 
-        while let Some((row, col)) = stack.pop() {
-            visited.insert((row, col));
+        while let Some(node) = stack.pop() {
+            // a node may be pushed again before it is first popped
+            if !visited.insert(node) {
+                continue;
+            }
+            nodes_expanded += 1;
             if let Some(ref mut t) = tracker {
-                t.record(row, col, crate::cell::Cell::Path);
+                let (row, col) = graph.position(node);
+                t.record(row, col, Cell::Path);
             }
 
-            if (row, col) == maze.end {
-                let mut path = vec![(row, col)];
-                let mut current = (row, col);
-
-                while let Some(&prev) = parent.get(&current) {
-                    path.push(prev);
-                    current = prev;
-                }
+            if graph.is_goal(node) {
+                return SolveResult::new(
+                    graph,
+                    Some(trace_path(&parent, start, node)),
+                    nodes_expanded,
+                    max_frontier,
+                    false,
+                    started,
+                );
+            }
 
-                path.reverse();
-                return Some(path);
-            } else if maze.cells[row][col] != Cell::Wall {
-                let neighbors =
-                    Maze::get_neighbors(row, col, maze.cells.len(), maze.cells[0].len());
-                for (n_row, n_col) in neighbors {
-                    if maze.cells[n_row][n_col] != Cell::Wall && !visited.contains(&(n_row, n_col))
-                    {
-                        parent.insert((n_row, n_col), (row, col));
-                        stack.push((n_row, n_col));
-                    }
+            graph.for_each_neighbor(node, &mut |neighbor, _| {
+                if !visited.contains(neighbor) {
+                    parent[neighbor] = node as u32;
+                    stack.push(neighbor);
                 }
-            }
+            });
+            max_frontier = max_frontier.max(stack.length());
         }
-        None // No path found
+        // No path found
+        SolveResult::new(graph, None, nodes_expanded, max_frontier, false, started)
     }
 }
 
@@ -93,7 +100,7 @@ mod tests {
         let start = (1, 1);
         let end = (1, 5);
 
-        let maze = Maze { cells, start, end };
+        let maze = Maze::new(cells, start, end);
 
         let solver = DfsSolver;
         let path = solver.solve(&maze, None);
@@ -161,7 +168,7 @@ mod tests {
         let start = (1, 1);
         let end = (1, 5);
 
-        let maze = Maze { cells, start, end };
+        let maze = Maze::new(cells, start, end);
 
         let solver = DfsSolver;
         let path = solver.solve(&maze, None);
@@ -1,64 +1,64 @@
-use crate::maze::Maze;
+use crate::cell::Cell;
+use crate::maze_graph::MazeGraph;
 use crate::priority_queue::PriorityQueue;
 use crate::progress::ProgressTracker;
-use crate::solvers::solver::Solver;
-use std::collections::HashMap;
+use crate::solvers::solver::{parent_array, trace_path, SolveResult, Solver};
+use std::time::Instant;
 
 pub struct DijkstraSolver;
 
 impl Solver for DijkstraSolver {
-    fn solve(
+    fn search(
         &self,
-        maze: &Maze,
+        graph: &dyn MazeGraph,
         mut tracker: Option<&mut ProgressTracker>,
-    ) -> Option<Vec<(usize, usize)>> {
-        let graph = maze.to_graph();
+    ) -> SolveResult<usize> {
+        let started = Instant::now();
+        let mut nodes_expanded = 0;
+        let mut max_frontier = 1;
+        let start = graph.start_node();
 
         let mut queue = PriorityQueue::new();
-        let mut distances: HashMap<(usize, usize), (usize, (usize, usize))> = HashMap::new();
+        // 32-bit distances and parents keep the per-node arrays small on huge grids
+        let mut distances = vec![u32::MAX; graph.node_count()];
+        let mut parent = parent_array(graph);
 
-        distances.insert(maze.start, (0, maze.start));
-        queue.push((0, maze.start));
+        distances[start] = 0;
+        queue.push((0, start));
 
         while let Some((current_distance, current_node)) = queue.pop() {
-            if let Some(ref mut t) = tracker {
-                t.record(current_node.0, current_node.1, crate::cell::Cell::Path);
+            if current_distance > distances[current_node] as usize {
+                continue; // Skip if we found a better path already
             }
-
-            if current_node == maze.end {
-                let mut path = vec![current_node];
-                let mut current = current_node;
-
-                while current != maze.start {
-                    if let Some(&(_, prev)) = distances.get(&current) {
-                        path.push(prev);
-                        current = prev;
-                    } else {
-                        break; // should never happen
-                    }
-                }
-                path.reverse();
-                return Some(path);
+            nodes_expanded += 1;
+            if let Some(ref mut t) = tracker {
+                let (row, col) = graph.position(current_node);
+                t.record(row, col, Cell::Path);
             }
 
-            if current_distance
-                > distances
-                    .get(&current_node)
-                    .unwrap_or(&(usize::MAX, (0, 0)))
-                    .0
-            {
-                continue; // Skip if we found a better path already
+            if graph.is_goal(current_node) {
+                return SolveResult::new(
+                    graph,
+                    Some(trace_path(&parent, start, current_node)),
+                    nodes_expanded,
+                    max_frontier,
+                    true,
+                    started,
+                );
             }
 
-            for &(neighbor, weight) in graph.get(&current_node).unwrap_or(&vec![]) {
+            graph.for_each_neighbor(current_node, &mut |neighbor, weight| {
                 let new_distance = current_distance + weight;
-                if new_distance < distances.get(&neighbor).unwrap_or(&(usize::MAX, (0, 0))).0 {
-                    distances.insert(neighbor, (new_distance, current_node));
+                if new_distance < distances[neighbor] as usize {
+                    distances[neighbor] = new_distance as u32;
+                    parent[neighbor] = current_node as u32;
                     queue.push((new_distance, neighbor));
                 }
-            }
+            });
+            max_frontier = max_frontier.max(queue.length());
         }
-        None // No path found
+        // No path found
+        SolveResult::new(graph, None, nodes_expanded, max_frontier, true, started)
     }
 }
 
@@ -66,8 +66,12 @@ impl Solver for DijkstraSolver {
 mod tests {
     use super::*;
     use crate::cell::Cell;
+    use crate::generators::generator::MazeGenerator;
+    use crate::generators::kruskal::KruskalMazeGenerator;
     use crate::maze::Maze;
     use crate::solvers::solver::Solver;
+    use crate::solvers::solver::StaleEntryGraph;
+    use std::collections::HashSet;
 
     #[test]
     fn test_dijkstra_solver_finds_path() {
@@ -79,7 +83,7 @@ mod tests {
         let start = (0, 0);
         let end = (2, 2);
 
-        let maze = Maze { cells, start, end };
+        let maze = Maze::new(cells, start, end);
         let solver = DijkstraSolver;
 
         let path = solver.solve(&maze, None);
@@ -109,10 +113,35 @@ mod tests {
         let start = (0, 0);
         let end = (2, 2);
 
-        let maze = Maze { cells, start, end };
+        let maze = Maze::new(cells, start, end);
         let solver = DijkstraSolver;
 
         let path = solver.solve(&maze, None);
         assert!(path.is_none(), "Expected no path due to walls");
     }
+
+    #[test]
+    fn test_dijkstra_expands_each_cell_at_most_once() {
+        for _ in 0..10 {
+            let maze = KruskalMazeGenerator
+                .generate(41, 41, None, None, 30.0, None)
+                .unwrap();
+            let open = maze
+                .cells
+                .iter()
+                .flatten()
+                .filter(|cell| cell.is_open())
+                .count();
+            let mut tracker = ProgressTracker::new(true);
+            let result = DijkstraSolver.solve_with_result(&maze, Some(&mut tracker));
+            assert!(result.path.is_some());
+            let expanded: HashSet<_> = tracker.history.iter().map(|&(y, x, _)| (y, x)).collect();
+            assert_eq!(result.nodes_expanded, expanded.len());
+            assert!(result.nodes_expanded <= open);
+        }
+
+        let result = DijkstraSolver.search(&StaleEntryGraph, None);
+        assert_eq!(result.cost, Some(12));
+        assert_eq!(result.nodes_expanded, StaleEntryGraph.node_count());
+    }
 }
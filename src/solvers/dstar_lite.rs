@@ -0,0 +1,382 @@
+//! Incremental replanning with D* Lite for mazes whose cells change while an
+//! agent walks them. The search runs backwards from the end, so when walls
+//! appear or disappear only the distances around the change are repaired
+//! instead of solving the maze again from scratch.
+use rand::seq::SliceRandom;
+
+use crate::cell::Cell;
+use crate::maze::Maze;
+use crate::maze_graph::MazeGraph;
+use crate::priority_queue::PriorityQueue;
+use crate::solvers::bfs_solver::BfsSolver;
+use crate::solvers::solver::Solver;
+
+/// Priority of a queued node: estimated total cost through it, then its own distance
+type Key = (usize, usize);
+
+/// A cell taking a new value once the agent has made `step` moves.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CellChange {
+    pub step: usize,
+    pub position: (usize, usize),
+    pub cell: Cell,
+}
+
+/// D* Lite planner for one agent heading for the maze's end. Doors stay shut,
+/// as held keys are not tracked.
+pub struct DStarLite {
+    maze: Maze,
+    agent: usize,
+    goal: usize,
+    /// Agent node when the keys were last offset by `km`
+    last: usize,
+    km: usize,
+    /// Settled distance of every node to the end
+    g: Vec<usize>,
+    /// One-step lookahead distance of every node to the end
+    rhs: Vec<usize>,
+    /// Current key of every queued node; queue entries with another key are stale
+    queued: Vec<Option<Key>>,
+    queue: PriorityQueue<(Key, usize)>,
+    expansions: usize,
+}
+
+impl DStarLite {
+    /// Planner for an agent standing on the maze's start.
+    pub fn new(maze: Maze) -> Self {
+        let node_count = maze.node_count();
+        let agent = maze.start_node();
+        let goal = maze.end_node();
+        let mut planner = DStarLite {
+            maze,
+            agent,
+            goal,
+            last: agent,
+            km: 0,
+            g: vec![usize::MAX; node_count],
+            rhs: vec![usize::MAX; node_count],
+            queued: vec![None; node_count],
+            queue: PriorityQueue::new(),
+            expansions: 0,
+        };
+        planner.rhs[goal] = 0;
+        planner.update_vertex(goal);
+        planner
+    }
+
+    /// The maze with all changes applied so far.
+    pub fn maze(&self) -> &Maze {
+        &self.maze
+    }
+
+    /// Cell the agent stands on.
+    pub fn agent(&self) -> (usize, usize) {
+        self.maze.position(self.agent)
+    }
+
+    /// Nodes expanded by all searches so far.
+    pub fn expansions(&self) -> usize {
+        self.expansions
+    }
+
+    /// Shortest path from the agent to the end, repairing the previous search
+    /// after changes. `None` when the end cannot be reached anymore.
+    pub fn path(&mut self) -> Option<Vec<(usize, usize)>> {
+        let nodes = self.node_path()?;
+        Some(
+            nodes
+                .into_iter()
+                .map(|node| self.maze.position(node))
+                .collect(),
+        )
+    }
+
+    /// Moves the agent one step along its shortest path and returns its new
+    /// cell, or `None` when it is already at the end or cut off from it.
+    pub fn advance(&mut self) -> Option<(usize, usize)> {
+        let nodes = self.node_path()?;
+        let &next = nodes.get(1)?;
+        self.agent = next;
+        Some(self.agent())
+    }
+
+    /// Sets a cell to a new value, e.g. a wall appearing or being knocked
+    /// down. The next call to `path` repairs the route around it.
+    pub fn notify_change(&mut self, row: usize, col: usize, cell: Cell) {
+        let relink =
+            matches!(cell, Cell::Portal(_)) || matches!(self.maze.cells[row][col], Cell::Portal(_));
+        let mut affected = self.affected_nodes(row, col, relink);
+        self.maze.cells[row][col] = cell;
+        if relink {
+            // an unpaired letter simply links nothing
+            let _ = self.maze.link_portals();
+        }
+        affected.extend(self.affected_nodes(row, col, relink));
+        affected.sort_unstable();
+        affected.dedup();
+
+        self.km += self
+            .maze
+            .distance_between(self.maze.position(self.last), self.agent());
+        self.last = self.agent;
+        for node in affected {
+            self.update_vertex(node);
+        }
+    }
+
+    // nodes whose outgoing edges may change with cell (row, col): its own, its
+    // neighbours' and its portal partner's, or every portal's on relinking
+    fn affected_nodes(&self, row: usize, col: usize, all_portals: bool) -> Vec<usize> {
+        let rows = self.maze.cells.len();
+        let cols = self.maze.cells[0].len();
+        let mut cells = Maze::get_wrapped_neighbors(row, col, rows, cols, self.maze.wrap);
+        cells.push((row, col));
+        if all_portals {
+            cells.extend(self.maze.portals.keys().copied());
+        } else {
+            cells.extend(self.maze.portals.get(&(row, col)).copied());
+        }
+        cells
+            .into_iter()
+            .flat_map(|(row, col)| self.nodes_at(row, col))
+            .collect()
+    }
+
+    // graph nodes of a cell, two for a crossing
+    fn nodes_at(&self, row: usize, col: usize) -> Vec<usize> {
+        let node = self.maze.node_index(row, col);
+        match self.maze.cells[row][col] {
+            Cell::Crossing(_) => vec![
+                node,
+                node + self.maze.cells.len() * self.maze.cells[0].len(),
+            ],
+            _ => vec![node],
+        }
+    }
+
+    fn predecessors(&self, node: usize) -> Vec<usize> {
+        let (row, col) = self.maze.position(node);
+        let rows = self.maze.cells.len();
+        let cols = self.maze.cells[0].len();
+        let mut cells = Maze::get_wrapped_neighbors(row, col, rows, cols, self.maze.wrap);
+        cells.extend(self.maze.portals.get(&(row, col)).copied());
+        cells
+            .into_iter()
+            .flat_map(|(row, col)| self.nodes_at(row, col))
+            .filter(|&candidate| {
+                self.maze
+                    .neighbors(candidate)
+                    .iter()
+                    .any(|&(next, _)| next == node)
+            })
+            .collect()
+    }
+
+    fn calculate_key(&self, node: usize) -> Key {
+        let distance = self.g[node].min(self.rhs[node]);
+        let estimate = self
+            .maze
+            .distance_between(self.agent(), self.maze.position(node));
+        (
+            distance.saturating_add(estimate).saturating_add(self.km),
+            distance,
+        )
+    }
+
+    fn update_vertex(&mut self, node: usize) {
+        if node != self.goal {
+            self.rhs[node] = self
+                .maze
+                .neighbors(node)
+                .into_iter()
+                .map(|(next, weight)| self.g[next].saturating_add(weight))
+                .min()
+                .unwrap_or(usize::MAX);
+        }
+        self.queued[node] = None;
+        if self.g[node] != self.rhs[node] {
+            let key = self.calculate_key(node);
+            self.queued[node] = Some(key);
+            self.queue.push((key, node));
+        }
+    }
+
+    // smallest queued entry that is still current, dropping stale ones
+    fn top(&mut self) -> Option<(Key, usize)> {
+        while let Some(&(key, node)) = self.queue.peek() {
+            if self.queued[node] == Some(key) {
+                return Some((key, node));
+            }
+            self.queue.pop();
+        }
+        None
+    }
+
+    fn compute_shortest_path(&mut self) {
+        while let Some((old_key, node)) = self.top() {
+            let agent = self.agent;
+            if old_key >= self.calculate_key(agent) && self.rhs[agent] == self.g[agent] {
+                break;
+            }
+            self.queue.pop();
+            self.queued[node] = None;
+            self.expansions += 1;
+
+            let new_key = self.calculate_key(node);
+            if old_key < new_key {
+                self.queued[node] = Some(new_key);
+                self.queue.push((new_key, node));
+            } else if self.g[node] > self.rhs[node] {
+                self.g[node] = self.rhs[node];
+                for predecessor in self.predecessors(node) {
+                    self.update_vertex(predecessor);
+                }
+            } else {
+                self.g[node] = usize::MAX;
+                self.update_vertex(node);
+                for predecessor in self.predecessors(node) {
+                    self.update_vertex(predecessor);
+                }
+            }
+        }
+    }
+
+    fn node_path(&mut self) -> Option<Vec<usize>> {
+        self.compute_shortest_path();
+        if self.g[self.agent] == usize::MAX {
+            return None;
+        }
+        let mut path = vec![self.agent];
+        let mut current = self.agent;
+        while current != self.goal {
+            let (cost, next) = self
+                .maze
+                .neighbors(current)
+                .into_iter()
+                .map(|(next, weight)| (self.g[next].saturating_add(weight), next))
+                .min()?;
+            if cost == usize::MAX || path.len() > self.g.len() {
+                return None;
+            }
+            path.push(next);
+            current = next;
+        }
+        Some(path)
+    }
+}
+
+/// Schedules `count` changes spread over an agent's walk from the start to the
+/// end: alternately a passage a few steps ahead on the solution is walled up
+/// and a wall between two passages is knocked down.
+pub fn random_changes(maze: &Maze, count: usize) -> Vec<CellChange> {
+    let Some(solution) = BfsSolver.solve(maze, None) else {
+        return Vec::new();
+    };
+    let rows = maze.cells.len();
+    let cols = maze.cells[0].len();
+    let mut openings: Vec<(usize, usize)> = (1..rows.saturating_sub(1))
+        .flat_map(|row| (1..cols.saturating_sub(1)).map(move |col| (row, col)))
+        .filter(|&(row, col)| {
+            maze.cells[row][col] == Cell::Wall
+                && ((maze.cells[row - 1][col].is_open() && maze.cells[row + 1][col].is_open())
+                    || (maze.cells[row][col - 1].is_open() && maze.cells[row][col + 1].is_open()))
+        })
+        .collect();
+    openings.shuffle(&mut crate::random::rng());
+
+    let mut changes = Vec::new();
+    for i in 0..count {
+        let step = (i + 1) * (solution.len() - 1) / (count + 1);
+        if i % 2 == 0 {
+            let position = solution[(step + 3).min(solution.len() - 1)];
+            if position != maze.end && maze.cells[position.0][position.1] == Cell::Path {
+                changes.push(CellChange {
+                    step,
+                    position,
+                    cell: Cell::Wall,
+                });
+            }
+        } else if let Some(position) = openings.pop() {
+            changes.push(CellChange {
+                step,
+                position,
+                cell: Cell::Path,
+            });
+        }
+    }
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generators::generator::MazeGenerator;
+    use crate::generators::kruskal::KruskalMazeGenerator;
+
+    // #######
+    // #S...E#
+    // #.#.#.#
+    // #.....#
+    // #######
+    fn double_loop() -> Maze {
+        let mut cells = vec![vec![Cell::Wall; 7]; 5];
+        cells[1][1..6].fill(Cell::Path);
+        cells[3][1..6].fill(Cell::Path);
+        for col in [1, 3, 5] {
+            cells[2][col] = Cell::Path;
+        }
+        Maze::new(cells, (1, 1), (1, 5))
+    }
+
+    fn fresh_length(planner: &DStarLite) -> Option<usize> {
+        let mut maze = planner.maze().clone();
+        maze.start = planner.agent();
+        BfsSolver.solve(&maze, None).map(|path| path.len())
+    }
+
+    #[test]
+    fn test_path_is_repaired_after_changes() {
+        let mut planner = DStarLite::new(double_loop());
+        assert_eq!(planner.path().map(|path| path.len()), Some(5));
+
+        planner.notify_change(1, 3, Cell::Wall);
+        let detour = planner.path().expect("Bottom row is still open");
+        assert_eq!(detour.len(), 9);
+        assert!(!detour.contains(&(1, 3)));
+
+        planner.notify_change(3, 3, Cell::Wall);
+        assert_eq!(planner.path(), None);
+
+        planner.notify_change(1, 3, Cell::Path);
+        assert_eq!(planner.path().map(|path| path.len()), Some(5));
+    }
+
+    #[test]
+    fn test_agent_reaches_end_through_changes() {
+        let maze = KruskalMazeGenerator
+            .generate(21, 21, None, None, 20.0, None)
+            .unwrap();
+        let changes = random_changes(&maze, 6);
+        let mut planner = DStarLite::new(maze);
+        let mut step = 0;
+        loop {
+            for change in changes.iter().filter(|change| change.step == step) {
+                let (row, col) = change.position;
+                let old = planner.maze().cells[row][col];
+                planner.notify_change(row, col, change.cell);
+                if planner.path().is_none() {
+                    planner.notify_change(row, col, old);
+                }
+            }
+            let path = planner
+                .path()
+                .expect("Changes cutting off the end are undone");
+            assert_eq!(Some(path.len()), fresh_length(&planner));
+            if planner.advance().is_none() {
+                break;
+            }
+            step += 1;
+        }
+        assert_eq!(planner.agent(), planner.maze().end);
+    }
+}
@@ -2,4 +2,8 @@ pub mod astar;
 pub mod bfs_solver;
 pub mod dfs_solver;
 pub mod dijkstra;
+pub mod dstar_lite;
+pub mod multi_agent;
+pub mod routes;
 pub mod solver;
+pub mod tour;
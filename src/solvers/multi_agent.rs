@@ -0,0 +1,338 @@
+//! Collision-free routes for several agents sharing one maze, planned with
+//! conflict-based search: every agent is routed on its own through space and
+//! time, and wherever two routes collide the search branches on which of the
+//! two agents has to keep out of the way.
+use std::collections::{HashMap, HashSet};
+
+use crate::cell::Cell;
+use crate::maze::Maze;
+use crate::maze_graph::MazeGraph;
+use crate::priority_queue::PriorityQueue;
+use rand::seq::SliceRandom;
+
+/// Most constraint sets examined before giving up on a set of agents.
+pub const MAX_EXPANSIONS: usize = 10_000;
+
+/// Start and end cell of one agent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Agent {
+    pub start: (usize, usize),
+    pub end: (usize, usize),
+}
+
+/// Places and moves one agent must avoid. Time is counted in steps.
+#[derive(Debug, Clone, Default)]
+struct Constraints {
+    /// (node, time) pairs the agent may not occupy
+    vertices: HashSet<(usize, usize)>,
+    /// (from, to, time) moves the agent may not make between `time` and `time + 1`
+    edges: HashSet<(usize, usize, usize)>,
+}
+
+impl Constraints {
+    fn allows_move(&self, from: usize, to: usize, time: usize) -> bool {
+        !self.vertices.contains(&(to, time + 1)) && !self.edges.contains(&(from, to, time))
+    }
+
+    fn latest(&self) -> usize {
+        self.vertices
+            .iter()
+            .map(|&(_, time)| time)
+            .chain(self.edges.iter().map(|&(_, _, time)| time + 1))
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+/// First collision between two agents' routes.
+enum Conflict {
+    /// Both agents on `node` at `time`
+    Vertex {
+        agents: (usize, usize),
+        node: usize,
+        time: usize,
+    },
+    /// The agents swap `from` and `to` between `time` and `time + 1`
+    Edge {
+        agents: (usize, usize),
+        from: usize,
+        to: usize,
+        time: usize,
+    },
+}
+
+/// Plans a synchronized timeline for all agents: entry `t` of every route is
+/// where that agent stands after `t` steps, waits repeat a cell and all routes
+/// are padded to the same length with the agent resting on its end. No two
+/// agents share a cell at the same time or swap cells in one step, and the
+/// total number of steps is as small as possible.
+///
+/// Returns `None` when no such timeline is found within `MAX_EXPANSIONS`.
+pub fn plan_agents(maze: &Maze, agents: &[Agent]) -> Option<Vec<Vec<(usize, usize)>>> {
+    let agent_mazes: Vec<Maze> = agents
+        .iter()
+        .map(|agent| {
+            let mut agent_maze = maze.clone();
+            agent_maze.start = agent.start;
+            agent_maze.end = agent.end;
+            agent_maze
+        })
+        .collect();
+
+    let root_constraints = vec![Constraints::default(); agents.len()];
+    let root_routes = agent_mazes
+        .iter()
+        .zip(&root_constraints)
+        .map(|(agent_maze, constraints)| plan_agent(agent_maze, constraints))
+        .collect::<Option<Vec<_>>>()?;
+
+    // search nodes are kept in `nodes` and queued by total cost
+    let mut nodes = vec![(root_constraints, root_routes)];
+    let mut queue = PriorityQueue::new();
+    queue.push((total_cost(&nodes[0].1), 0));
+    let mut expansions = 0;
+    while let Some((_, index)) = queue.pop() {
+        expansions += 1;
+        if expansions > MAX_EXPANSIONS {
+            return None;
+        }
+        let (constraints, routes) = nodes[index].clone();
+        let Some(conflict) = first_conflict(&routes) else {
+            return Some(timeline(maze, routes));
+        };
+
+        let (first, second) = match conflict {
+            Conflict::Vertex { agents, .. } | Conflict::Edge { agents, .. } => agents,
+        };
+        for agent in [first, second] {
+            let mut constraints = constraints.clone();
+            match conflict {
+                Conflict::Vertex { node, time, .. } => {
+                    constraints[agent].vertices.insert((node, time));
+                }
+                Conflict::Edge { from, to, time, .. } => {
+                    let (from, to) = if agent == first {
+                        (from, to)
+                    } else {
+                        (to, from)
+                    };
+                    constraints[agent].edges.insert((from, to, time));
+                }
+            }
+            let Some(route) = plan_agent(&agent_mazes[agent], &constraints[agent]) else {
+                continue;
+            };
+            let mut routes = routes.clone();
+            routes[agent] = route;
+            queue.push((total_cost(&routes), nodes.len()));
+            nodes.push((constraints, routes));
+        }
+    }
+    None
+}
+
+fn total_cost(routes: &[Vec<usize>]) -> usize {
+    routes.iter().map(|route| route.len() - 1).sum()
+}
+
+// where agent `agent` is at `time`, resting on its end once it got there
+fn node_at(routes: &[Vec<usize>], agent: usize, time: usize) -> usize {
+    let route = &routes[agent];
+    route[time.min(route.len() - 1)]
+}
+
+fn first_conflict(routes: &[Vec<usize>]) -> Option<Conflict> {
+    let length = routes.iter().map(Vec::len).max().unwrap_or(0);
+    for time in 0..length {
+        for a in 0..routes.len() {
+            for b in a + 1..routes.len() {
+                let node = node_at(routes, a, time);
+                if node == node_at(routes, b, time) {
+                    return Some(Conflict::Vertex {
+                        agents: (a, b),
+                        node,
+                        time,
+                    });
+                }
+                let next = node_at(routes, a, time + 1);
+                if next != node
+                    && node == node_at(routes, b, time + 1)
+                    && next == node_at(routes, b, time)
+                {
+                    return Some(Conflict::Edge {
+                        agents: (a, b),
+                        from: node,
+                        to: next,
+                        time,
+                    });
+                }
+            }
+        }
+    }
+    None
+}
+
+fn timeline(maze: &Maze, routes: Vec<Vec<usize>>) -> Vec<Vec<(usize, usize)>> {
+    let length = routes.iter().map(Vec::len).max().unwrap_or(0);
+    routes
+        .into_iter()
+        .map(|mut route| {
+            let last = *route.last().unwrap();
+            route.resize(length, last);
+            route.into_iter().map(|node| maze.position(node)).collect()
+        })
+        .collect()
+}
+
+/// Picks `count` agents for a maze: the first runs from the maze's start to its
+/// end, the others between random distinct passage cells.
+pub fn random_agents(maze: &Maze, count: usize) -> Vec<Agent> {
+    let mut cells: Vec<(usize, usize)> = (0..maze.cells.len())
+        .flat_map(|row| (0..maze.cells[0].len()).map(move |col| (row, col)))
+        .filter(|&(row, col)| maze.cells[row][col] == Cell::Path)
+        .filter(|&pos| pos != maze.start && pos != maze.end)
+        .collect();
+    cells.shuffle(&mut crate::random::rng());
+
+    let mut agents = vec![Agent {
+        start: maze.start,
+        end: maze.end,
+    }];
+    agents.extend(cells.chunks_exact(2).map(|pair| Agent {
+        start: pair[0],
+        end: pair[1],
+    }));
+    agents.truncate(count);
+    agents
+}
+
+// space-time A* from the maze's start to its end under `constraints`, waiting
+// in place when needed
+fn plan_agent(maze: &Maze, constraints: &Constraints) -> Option<Vec<usize>> {
+    let start = maze.start_node();
+    let goal = maze.end_node();
+    let latest = constraints.latest();
+    // long enough to wait out every constraint and then walk anywhere
+    let horizon = latest + maze.node_count();
+    // the agent rests on its end for good, so it may only stop there once
+    // nothing keeps it off later
+    let last_goal_constraint = constraints
+        .vertices
+        .iter()
+        .filter(|&&(node, _)| node == goal)
+        .map(|&(_, time)| time)
+        .max();
+
+    let mut queue = PriorityQueue::new();
+    let mut parents: HashMap<(usize, usize), (usize, usize)> = HashMap::new();
+    let mut closed = HashSet::new();
+    queue.push((maze.heuristic(start), 0, start));
+
+    while let Some((_, time, node)) = queue.pop() {
+        if !closed.insert((node, time)) {
+            continue;
+        }
+        if node == goal && last_goal_constraint.is_none_or(|last| last < time) {
+            let mut path = vec![node];
+            let mut state = (node, time);
+            while let Some(&previous) = parents.get(&state) {
+                path.push(previous.0);
+                state = previous;
+            }
+            path.reverse();
+            return Some(path);
+        }
+        if time >= horizon {
+            continue;
+        }
+
+        let moves = maze
+            .neighbors(node)
+            .into_iter()
+            .map(|(next, _)| next)
+            .chain(std::iter::once(node));
+        for next in moves {
+            let state = (next, time + 1);
+            if closed.contains(&state) || !constraints.allows_move(node, next, time) {
+                continue;
+            }
+            if let std::collections::hash_map::Entry::Vacant(entry) = parents.entry(state) {
+                entry.insert((node, time));
+                queue.push((time + 1 + maze.heuristic(next), time + 1, next));
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_collision_free(routes: &[Vec<(usize, usize)>]) {
+        let length = routes[0].len();
+        for time in 0..length {
+            let cells: HashSet<_> = routes.iter().map(|route| route[time]).collect();
+            assert_eq!(cells.len(), routes.len(), "Agents share a cell at {}", time);
+            if time + 1 < length {
+                for (a, first) in routes.iter().enumerate() {
+                    for second in &routes[a + 1..] {
+                        assert!(
+                            !(first[time] == second[time + 1] && first[time + 1] == second[time]),
+                            "Agents swap cells at {}",
+                            time
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_agents_pass_each_other_using_a_side_pocket() {
+        // #######
+        // #.....#
+        // ###.###
+        // #######
+        let mut cells = vec![vec![Cell::Wall; 7]; 4];
+        cells[1][1..6].fill(Cell::Path);
+        cells[2][3] = Cell::Path;
+        let maze = Maze::new(cells, (1, 1), (1, 5));
+        let agents = [
+            Agent {
+                start: (1, 1),
+                end: (1, 5),
+            },
+            Agent {
+                start: (1, 5),
+                end: (1, 1),
+            },
+        ];
+
+        let routes = plan_agents(&maze, &agents).expect("Agents must get past each other");
+        assert_eq!(routes[0].len(), routes[1].len());
+        for (route, agent) in routes.iter().zip(&agents) {
+            assert_eq!(route.first(), Some(&agent.start));
+            assert_eq!(route.last(), Some(&agent.end));
+        }
+        assert!(routes[1].contains(&(2, 3)), "Second agent must step aside");
+        assert_collision_free(&routes);
+    }
+
+    #[test]
+    fn test_plain_corridor_has_no_way_past() {
+        let cells = vec![vec![Cell::Path; 4]];
+        let maze = Maze::new(cells, (0, 0), (0, 3));
+        let agents = [
+            Agent {
+                start: (0, 0),
+                end: (0, 3),
+            },
+            Agent {
+                start: (0, 3),
+                end: (0, 0),
+            },
+        ];
+        assert!(plan_agents(&maze, &agents).is_none());
+    }
+}
@@ -0,0 +1,251 @@
+//! Queries over many routes through a maze rather than a single solution, most
+//! useful on imperfect mazes where walls were removed to open up loops.
+use std::collections::{HashMap, HashSet};
+
+use crate::maze_graph::MazeGraph;
+use crate::priority_queue::PriorityQueue;
+
+/// Path of graph nodes from the start to a goal together with its total cost.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Route {
+    pub cost: usize,
+    pub nodes: Vec<usize>,
+}
+
+/// Up to `k` shortest loopless routes from the start to a goal in order of
+/// cost, found with Yen's algorithm.
+pub fn k_shortest_paths(graph: &dyn MazeGraph, k: usize) -> Vec<Route> {
+    let mut found: Vec<Route> = Vec::new();
+    let Some(first) = shortest_route(graph, graph.start_node(), &HashSet::new(), &HashSet::new())
+    else {
+        return found;
+    };
+    found.push(first);
+
+    let mut candidates = PriorityQueue::new();
+    let mut seen: HashSet<Vec<usize>> = HashSet::from([found[0].nodes.clone()]);
+    while found.len() < k {
+        let previous = found.last().unwrap().nodes.clone();
+        let mut root_cost = 0;
+        // branch off the previous route at every node but the goal
+        for spur_index in 0..previous.len() - 1 {
+            let root = &previous[..=spur_index];
+            let spur = previous[spur_index];
+            let banned_edges: HashSet<(usize, usize)> = found
+                .iter()
+                .filter(|route| {
+                    route.nodes.len() > spur_index + 1 && route.nodes[..=spur_index] == *root
+                })
+                .map(|route| (spur, route.nodes[spur_index + 1]))
+                .collect();
+            let banned_nodes: HashSet<usize> = root[..spur_index].iter().copied().collect();
+
+            if let Some(spur_route) = shortest_route(graph, spur, &banned_nodes, &banned_edges) {
+                let mut nodes = root[..spur_index].to_vec();
+                nodes.extend(spur_route.nodes);
+                if seen.insert(nodes.clone()) {
+                    candidates.push(Route {
+                        cost: root_cost + spur_route.cost,
+                        nodes,
+                    });
+                }
+            }
+            root_cost += edge_weight(graph, spur, previous[spur_index + 1]);
+        }
+
+        match candidates.pop() {
+            Some(route) => found.push(route),
+            None => break,
+        }
+    }
+    found
+}
+
+/// Number of distinct minimum-cost routes from the start to the goal,
+/// saturating at `u64::MAX`. Zero when the goal cannot be reached.
+pub fn count_shortest_paths(graph: &dyn MazeGraph) -> u64 {
+    let start = graph.start_node();
+    let mut queue = PriorityQueue::new();
+    // cheapest known cost of each node and how many routes achieve it
+    let mut best: HashMap<usize, (usize, u64)> = HashMap::from([(start, (0, 1))]);
+    let mut settled = HashSet::new();
+    let mut goal_cost = None;
+    let mut count: u64 = 0;
+
+    queue.push((0, start));
+    while let Some((cost, node)) = queue.pop() {
+        if goal_cost.is_some_and(|goal_cost| cost > goal_cost) {
+            break;
+        }
+        if !settled.insert(node) {
+            continue;
+        }
+        let routes = best[&node].1;
+        // several goal nodes are possible, e.g. one per set of keys held
+        if graph.is_goal(node) {
+            goal_cost = Some(cost);
+            count = count.saturating_add(routes);
+            continue;
+        }
+        for (neighbor, weight) in graph.neighbors(node) {
+            let new_cost = cost + weight;
+            let entry = best.entry(neighbor).or_insert((usize::MAX, 0));
+            if new_cost < entry.0 {
+                *entry = (new_cost, routes);
+                queue.push((new_cost, neighbor));
+            } else if new_cost == entry.0 {
+                entry.1 = entry.1.saturating_add(routes);
+            }
+        }
+    }
+    count
+}
+
+/// Every loopless route from the start to a goal costing at most `max_cost`,
+/// in depth-first order. The number of routes grows quickly with the bound.
+pub fn all_simple_paths(graph: &dyn MazeGraph, max_cost: usize) -> Vec<Route> {
+    let start = graph.start_node();
+    let mut routes = Vec::new();
+    let mut nodes = vec![start];
+    let mut on_route = HashSet::from([start]);
+    // each stack entry holds the unexplored neighbours of the route's node at that depth
+    let mut stack = vec![graph.neighbors(start)];
+    let mut costs = vec![0];
+
+    while let Some(pending) = stack.last_mut() {
+        let Some((next, weight)) = pending.pop() else {
+            stack.pop();
+            costs.pop();
+            if let Some(node) = nodes.pop() {
+                on_route.remove(&node);
+            }
+            continue;
+        };
+        let cost = costs.last().unwrap() + weight;
+        if cost > max_cost || on_route.contains(&next) {
+            continue;
+        }
+        if graph.is_goal(next) {
+            let mut route = nodes.clone();
+            route.push(next);
+            routes.push(Route { cost, nodes: route });
+            continue;
+        }
+        nodes.push(next);
+        on_route.insert(next);
+        stack.push(graph.neighbors(next));
+        costs.push(cost);
+    }
+    routes
+}
+
+// Dijkstra from `source` to a goal, never entering `banned_nodes` or taking `banned_edges`
+fn shortest_route(
+    graph: &dyn MazeGraph,
+    source: usize,
+    banned_nodes: &HashSet<usize>,
+    banned_edges: &HashSet<(usize, usize)>,
+) -> Option<Route> {
+    let mut queue = PriorityQueue::new();
+    let mut distances: HashMap<usize, (usize, usize)> = HashMap::from([(source, (0, source))]);
+    queue.push((0, source));
+
+    while let Some((cost, node)) = queue.pop() {
+        if cost > distances[&node].0 {
+            continue;
+        }
+        if graph.is_goal(node) {
+            let mut nodes = vec![node];
+            let mut current = node;
+            while current != source {
+                current = distances[&current].1;
+                nodes.push(current);
+            }
+            nodes.reverse();
+            return Some(Route { cost, nodes });
+        }
+        for (neighbor, weight) in graph.neighbors(node) {
+            if banned_nodes.contains(&neighbor) || banned_edges.contains(&(node, neighbor)) {
+                continue;
+            }
+            let new_cost = cost + weight;
+            if new_cost < distances.get(&neighbor).map_or(usize::MAX, |d| d.0) {
+                distances.insert(neighbor, (new_cost, node));
+                queue.push((new_cost, neighbor));
+            }
+        }
+    }
+    None
+}
+
+fn edge_weight(graph: &dyn MazeGraph, from: usize, to: usize) -> usize {
+    graph
+        .neighbors(from)
+        .into_iter()
+        .filter(|&(next, _)| next == to)
+        .map(|(_, weight)| weight)
+        .min()
+        .expect("consecutive route nodes must be neighbours")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cell::Cell;
+    use crate::maze::Maze;
+
+    // two loops side by side, four loopless routes from S to E
+    // #######
+    // #S...E#
+    // #.#.#.#
+    // #.....#
+    // #######
+    fn double_loop() -> Maze {
+        let mut cells = vec![vec![Cell::Wall; 7]; 5];
+        cells[1][1..6].fill(Cell::Path);
+        cells[3][1..6].fill(Cell::Path);
+        for col in [1, 3, 5] {
+            cells[2][col] = Cell::Path;
+        }
+        Maze::new(cells, (1, 1), (1, 5))
+    }
+
+    #[test]
+    fn test_k_shortest_paths_in_cost_order() {
+        let maze = double_loop();
+        let routes = k_shortest_paths(&maze, 10);
+        let costs: Vec<usize> = routes.iter().map(|route| route.cost).collect();
+        // straight along the top, then the three detours through the bottom row
+        assert_eq!(costs, vec![4, 8, 8, 8]);
+        let mut sorted = routes.clone();
+        sorted.sort();
+        assert_eq!(sorted, all_sorted(&maze));
+        assert_eq!(k_shortest_paths(&maze, 2), routes[..2]);
+    }
+
+    #[test]
+    fn test_count_shortest_paths() {
+        let maze = double_loop();
+        assert_eq!(count_shortest_paths(&maze), 1);
+
+        let mut open = double_loop();
+        open.start = (3, 1);
+        open.end = (1, 5);
+        // right along the bottom and up at any of the three columns
+        assert_eq!(count_shortest_paths(&open), 3);
+    }
+
+    fn all_sorted(maze: &Maze) -> Vec<Route> {
+        let mut routes = all_simple_paths(maze, usize::MAX);
+        routes.sort();
+        routes
+    }
+
+    #[test]
+    fn test_all_simple_paths_respects_bound() {
+        let maze = double_loop();
+        assert_eq!(all_simple_paths(&maze, 7).len(), 1);
+        assert_eq!(all_simple_paths(&maze, 8).len(), 4);
+        assert_eq!(all_sorted(&maze).len(), 4);
+    }
+}
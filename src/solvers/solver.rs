@@ -1,10 +1,171 @@
+use std::time::{Duration, Instant};
+
 use crate::maze::Maze;
+use crate::maze_graph::{KeyDoorGraph, MazeGraph};
 use crate::progress::ProgressTracker;
 
+/// Outcome of a search together with metrics for comparing solvers. Paths are
+/// graph nodes by default, or (row, column) positions for square mazes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SolveResult<N = (usize, usize)> {
+    pub path: Option<Vec<N>>,
+    /// Sum of the edge weights along `path`
+    pub cost: Option<usize>,
+    /// Nodes taken off the frontier
+    pub nodes_expanded: usize,
+    /// Most nodes waiting on the frontier at once
+    pub max_frontier: usize,
+    /// Whether a found path is guaranteed to be a cheapest one
+    pub optimal: bool,
+    pub elapsed: Duration,
+}
+
+impl SolveResult<usize> {
+    /// Result of a search that began at `started`, costing `path` on `graph`.
+    pub fn new(
+        graph: &dyn MazeGraph,
+        path: Option<Vec<usize>>,
+        nodes_expanded: usize,
+        max_frontier: usize,
+        optimal: bool,
+        started: Instant,
+    ) -> Self {
+        SolveResult {
+            cost: path.as_ref().map(|path| path_cost(graph, path)),
+            path,
+            nodes_expanded,
+            max_frontier,
+            optimal,
+            elapsed: started.elapsed(),
+        }
+    }
+
+    /// The same result with the path's nodes turned into grid positions.
+    pub fn positions(self, graph: &dyn MazeGraph) -> SolveResult {
+        SolveResult {
+            path: self
+                .path
+                .map(|path| path.into_iter().map(|node| graph.position(node)).collect()),
+            cost: self.cost,
+            nodes_expanded: self.nodes_expanded,
+            max_frontier: self.max_frontier,
+            optimal: self.optimal,
+            elapsed: self.elapsed,
+        }
+    }
+}
+
+/// Entry of a parent array for nodes not reached yet.
+pub(crate) const NO_PARENT: u32 = u32::MAX;
+
+/// Parent array with one entry per graph node, kept to 32 bits per node so
+/// very large grids stay affordable.
+pub(crate) fn parent_array(graph: &dyn MazeGraph) -> Vec<u32> {
+    assert!(
+        graph.node_count() < NO_PARENT as usize,
+        "graph too large for 32-bit node indices"
+    );
+    vec![NO_PARENT; graph.node_count()]
+}
+
+/// Path from `start` to `goal` read back along a parent array.
+pub(crate) fn trace_path(parents: &[u32], start: usize, goal: usize) -> Vec<usize> {
+    let mut path = vec![goal];
+    let mut current = goal;
+    while current != start && parents[current] != NO_PARENT {
+        current = parents[current] as usize;
+        path.push(current);
+    }
+    path.reverse();
+    path
+}
+
 pub trait Solver {
+    /// Searches for a path of node indices from the graph's start node to its
+    /// end node, reporting how much work the search took.
+    fn search(
+        &self,
+        graph: &dyn MazeGraph,
+        tracker: Option<&mut ProgressTracker>,
+    ) -> SolveResult<usize>;
+
+    /// Finds a path of node indices from the graph's start node to its end node.
+    fn solve_graph(
+        &self,
+        graph: &dyn MazeGraph,
+        tracker: Option<&mut ProgressTracker>,
+    ) -> Option<Vec<usize>> {
+        self.search(graph, tracker).path
+    }
+
     fn solve(
         &self,
         maze: &Maze,
         tracker: Option<&mut ProgressTracker>,
-    ) -> Option<Vec<(usize, usize)>>;
+    ) -> Option<Vec<(usize, usize)>> {
+        self.solve_with_result(maze, tracker).path
+    }
+
+    /// Like `solve`, also reporting the search metrics.
+    fn solve_with_result(&self, maze: &Maze, tracker: Option<&mut ProgressTracker>) -> SolveResult {
+        // doors need the (position, keys held) state space
+        let graph: &dyn MazeGraph = if maze.has_doors() {
+            &KeyDoorGraph::new(maze)
+        } else {
+            maze
+        };
+        self.search(graph, tracker).positions(graph)
+    }
+}
+
+// sum of the cheapest edge weights between consecutive nodes of a path
+fn path_cost(graph: &dyn MazeGraph, path: &[usize]) -> usize {
+    path.windows(2)
+        .map(|pair| {
+            graph
+                .neighbors(pair[0])
+                .into_iter()
+                .filter(|&(next, _)| next == pair[1])
+                .map(|(_, weight)| weight)
+                .min()
+                .unwrap_or(0)
+        })
+        .sum()
+}
+
+/// Graph with a cycle where the cheap way round reaches node 2 after the
+/// direct edge has already queued it, leaving a stale queue entry behind.
+#[cfg(test)]
+pub(crate) struct StaleEntryGraph;
+
+#[cfg(test)]
+impl MazeGraph for StaleEntryGraph {
+    fn node_count(&self) -> usize {
+        4
+    }
+
+    fn start_node(&self) -> usize {
+        0
+    }
+
+    fn end_node(&self) -> usize {
+        3
+    }
+
+    fn neighbors(&self, node: usize) -> Vec<(usize, usize)> {
+        match node {
+            0 => vec![(2, 5), (1, 1)],
+            1 => vec![(0, 1), (2, 1)],
+            2 => vec![(0, 5), (1, 1), (3, 10)],
+            _ => vec![(2, 10)],
+        }
+    }
+
+    fn heuristic(&self, _node: usize) -> usize {
+        0
+    }
+
+    fn position(&self, node: usize) -> (usize, usize) {
+        (0, node)
+    }
 }
@@ -0,0 +1,278 @@
+use std::iter;
+
+use crate::maze::Maze;
+use crate::progress::ProgressTracker;
+use crate::solvers::solver::Solver;
+
+/// Most checkpoints ordered exactly with Held-Karp; beyond that the order comes
+/// from nearest neighbour improved by 2-opt.
+pub const HELD_KARP_LIMIT: usize = 12;
+
+/// Route from the start through every checkpoint to the end.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tour {
+    /// Checkpoints in the order they are visited
+    pub order: Vec<(usize, usize)>,
+    /// Legs between consecutive stops stitched into one path
+    pub path: Vec<(usize, usize)>,
+    /// Number of steps along `path`
+    pub length: usize,
+    /// Nodes expanded while solving all the legs
+    pub nodes_expanded: usize,
+    /// Largest frontier of any leg's search
+    pub max_frontier: usize,
+    /// Whether every leg and the visiting order are guaranteed shortest; never
+    /// set for a maze with doors, see `solve_tour`
+    pub optimal: bool,
+}
+
+/// Finds a tour visiting all of the maze's checkpoints. Legs between every pair
+/// of stops are solved with `solver`, so the tour is only as short as the legs
+/// it finds. Returns `None` when some checkpoint or the end cannot be reached.
+///
+/// Each leg starts out without keys, so keys picked up on one leg are fetched
+/// again by any later leg needing them and the tour of a maze with doors may
+/// be longer than necessary; such tours are never reported optimal.
+pub fn solve_tour(
+    maze: &Maze,
+    solver: &dyn Solver,
+    mut tracker: Option<&mut ProgressTracker>,
+) -> Option<Tour> {
+    let checkpoints = maze.checkpoints();
+    let stops: Vec<(usize, usize)> = iter::once(maze.start)
+        .chain(checkpoints.iter().copied())
+        .chain(iter::once(maze.end))
+        .collect();
+    let n = stops.len();
+
+    // legs[i][j] runs from stop i to stop j; the tour never returns to the
+    // start nor leaves the end
+    let mut leg_maze = maze.clone();
+    let mut legs = vec![vec![None; n]; n];
+    let mut nodes_expanded = 0;
+    let mut max_frontier = 0;
+    let mut optimal = checkpoints.len() <= HELD_KARP_LIMIT && !maze.has_doors();
+    for (i, &from) in stops.iter().enumerate().take(n - 1) {
+        for (j, &to) in stops.iter().enumerate().skip(1) {
+            if i != j {
+                leg_maze.start = from;
+                leg_maze.end = to;
+                let result = solver.solve_with_result(&leg_maze, tracker.as_deref_mut());
+                nodes_expanded += result.nodes_expanded;
+                max_frontier = max_frontier.max(result.max_frontier);
+                optimal &= result.optimal;
+                legs[i][j] = result.path;
+            }
+        }
+    }
+    let costs: Vec<Vec<Option<usize>>> = legs
+        .iter()
+        .map(|row| {
+            row.iter()
+                .map(|leg| leg.as_ref().map(|path: &Vec<_>| path.len() - 1))
+                .collect()
+        })
+        .collect();
+
+    let order = if checkpoints.len() <= HELD_KARP_LIMIT {
+        held_karp(&costs)?
+    } else {
+        two_opt(nearest_neighbour(&costs)?, &costs)
+    };
+
+    let mut path = vec![maze.start];
+    for pair in order.windows(2) {
+        let leg = legs[pair[0]][pair[1]].as_ref()?;
+        path.extend_from_slice(&leg[1..]);
+    }
+    Some(Tour {
+        order: order[1..n - 1].iter().map(|&stop| stops[stop]).collect(),
+        length: tour_cost(&order, &costs)?,
+        path,
+        nodes_expanded,
+        max_frontier,
+        optimal,
+    })
+}
+
+// total cost of visiting stops in `order`, `None` if a leg is missing
+fn tour_cost(order: &[usize], costs: &[Vec<Option<usize>>]) -> Option<usize> {
+    order.windows(2).map(|pair| costs[pair[0]][pair[1]]).sum()
+}
+
+/// Exact visiting order over stops `0..n` starting at stop 0 and ending at
+/// stop `n - 1`, by dynamic programming over subsets of the stops between.
+fn held_karp(costs: &[Vec<Option<usize>>]) -> Option<Vec<usize>> {
+    let n = costs.len();
+    let between = n - 2;
+    if between == 0 {
+        return costs[0][1].map(|_| vec![0, 1]);
+    }
+
+    // best[set][last]: cheapest route from the start through the stops in `set`
+    // ending at stop `last + 1`, with the previous stop to rebuild it
+    let full = (1usize << between) - 1;
+    let mut best: Vec<Vec<Option<(usize, usize)>>> = vec![vec![None; between]; full + 1];
+    for last in 0..between {
+        best[1 << last][last] = costs[0][last + 1].map(|cost| (cost, 0));
+    }
+    for set in 1..=full {
+        for last in 0..between {
+            let Some((cost, _)) = best[set][last] else {
+                continue;
+            };
+            for next in (0..between).filter(|&next| set & (1 << next) == 0) {
+                let Some(step) = costs[last + 1][next + 1] else {
+                    continue;
+                };
+                let entry = &mut best[set | (1 << next)][next];
+                if entry.is_none_or(|(known, _)| cost + step < known) {
+                    *entry = Some((cost + step, last + 1));
+                }
+            }
+        }
+    }
+
+    let (_, mut last) = (0..between)
+        .filter_map(|last| {
+            let (cost, _) = best[full][last]?;
+            Some((cost + costs[last + 1][n - 1]?, last + 1))
+        })
+        .min()?;
+    let mut order = vec![n - 1];
+    let mut set = full;
+    while last != 0 {
+        order.push(last);
+        let (_, previous) = best[set][last - 1]?;
+        set &= !(1 << (last - 1));
+        last = previous;
+    }
+    order.push(0);
+    order.reverse();
+    Some(order)
+}
+
+/// Greedy order: always head for the closest checkpoint not yet visited.
+fn nearest_neighbour(costs: &[Vec<Option<usize>>]) -> Option<Vec<usize>> {
+    let n = costs.len();
+    let mut order = vec![0];
+    let mut remaining: Vec<usize> = (1..n - 1).collect();
+    while !remaining.is_empty() {
+        let current = *order.last().unwrap();
+        let (index, _) = remaining
+            .iter()
+            .enumerate()
+            .filter_map(|(index, &next)| Some((index, costs[current][next]?)))
+            .min_by_key(|&(_, cost)| cost)?;
+        order.push(remaining.swap_remove(index));
+    }
+    order.push(n - 1);
+    Some(order)
+}
+
+/// Reverses stretches of checkpoints while that shortens the tour. Legs may
+/// differ by direction, so every candidate is costed in full.
+fn two_opt(mut order: Vec<usize>, costs: &[Vec<Option<usize>>]) -> Vec<usize> {
+    let n = order.len();
+    let mut current = tour_cost(&order, costs);
+    let mut improved = true;
+    while improved {
+        improved = false;
+        for i in 1..n - 1 {
+            for j in i + 1..n - 1 {
+                order[i..=j].reverse();
+                let candidate = tour_cost(&order, costs);
+                if candidate.is_some_and(|cost| current.is_none_or(|known| cost < known)) {
+                    current = candidate;
+                    improved = true;
+                } else {
+                    order[i..=j].reverse();
+                }
+            }
+        }
+    }
+    order
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cell::Cell;
+    use crate::generators::generator::MazeGenerator;
+    use crate::generators::recursive_backtracker::RecursiveBacktrackerMazeGenerator;
+    use crate::solvers::bfs_solver::BfsSolver;
+
+    fn assert_visits_all(maze: &Maze, tour: &Tour) {
+        assert_eq!(tour.path.first(), Some(&maze.start));
+        assert_eq!(tour.path.last(), Some(&maze.end));
+        assert_eq!(tour.path.len() - 1, tour.length);
+        for checkpoint in maze.checkpoints() {
+            assert!(tour.path.contains(&checkpoint), "{:?} skipped", checkpoint);
+        }
+    }
+
+    #[test]
+    fn test_tour_picks_shortest_order() {
+        // ###########
+        // #*..S.*..E#
+        // ###########
+        let mut cells = vec![vec![Cell::Wall; 11]; 3];
+        cells[1][1..10].fill(Cell::Path);
+        cells[1][1] = Cell::Checkpoint;
+        cells[1][6] = Cell::Checkpoint;
+        let maze = Maze::new(cells, (1, 4), (1, 9));
+
+        let tour = solve_tour(&maze, &BfsSolver, None).unwrap();
+        // back to the far left first, then all the way right
+        assert_eq!(tour.order, vec![(1, 1), (1, 6)]);
+        assert_eq!(tour.length, 3 + 8);
+        assert_visits_all(&maze, &tour);
+    }
+
+    #[test]
+    fn test_tour_through_doors_is_not_optimal() {
+        // #########
+        // #S1.*A.E#
+        // #########
+        let mut cells = vec![vec![Cell::Wall; 9]; 3];
+        cells[1][1..8].fill(Cell::Path);
+        cells[1][2] = Cell::Key(0);
+        cells[1][4] = Cell::Checkpoint;
+        cells[1][5] = Cell::Door(0);
+        let maze = Maze::new(cells, (1, 1), (1, 7));
+
+        let tour = solve_tour(&maze, &BfsSolver, None).unwrap();
+        // the leg from the checkpoint fetches the key again
+        assert_eq!(tour.length, 3 + 2 + 2 + 3);
+        assert!(!tour.optimal);
+        assert_visits_all(&maze, &tour);
+    }
+
+    #[test]
+    fn test_heuristic_order_is_no_shorter_than_exact() {
+        let mut maze = RecursiveBacktrackerMazeGenerator
+            .generate(21, 21, None, None, 10.0, None)
+            .unwrap();
+        for &(row, col) in &[(1, 19), (19, 1), (9, 9), (5, 15), (15, 5), (3, 3)] {
+            maze.cells[row][col] = Cell::Checkpoint;
+        }
+        let tour = solve_tour(&maze, &BfsSolver, None).unwrap();
+        assert_visits_all(&maze, &tour);
+
+        let n = maze.checkpoints().len() + 2;
+        let mut costs = vec![vec![None; n]; n];
+        let stops: Vec<_> = iter::once(maze.start)
+            .chain(maze.checkpoints())
+            .chain(iter::once(maze.end))
+            .collect();
+        for (i, &from) in stops.iter().enumerate() {
+            for (j, &to) in stops.iter().enumerate() {
+                let mut leg = maze.clone();
+                (leg.start, leg.end) = (from, to);
+                costs[i][j] = BfsSolver.solve(&leg, None).map(|path| path.len() - 1);
+            }
+        }
+        let heuristic = two_opt(nearest_neighbour(&costs).unwrap(), &costs);
+        assert!(tour_cost(&heuristic, &costs).unwrap() >= tour.length);
+    }
+}
@@ -27,3 +27,9 @@ impl<T> Stack<T> {
         self.stack.last()
     }
 }
+
+impl<T> Default for Stack<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
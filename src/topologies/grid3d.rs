@@ -0,0 +1,355 @@
+use crate::linked_maze::{LinkedMaze, Passages};
+use crate::maze::MazeError;
+use crate::topologies::topology::{PassageMarker, Topology, Wall, WallShape};
+use std::fmt;
+use std::path::Path;
+
+/// Line separating consecutive floors in the text format.
+pub const FLOOR_SEPARATOR: &str = "---";
+
+/// Stack of square floors where every cell connects to its four neighbors on
+/// the same floor and to the cells directly above and below it. Floors are laid
+/// out side by side on the canvas, separated by one empty column.
+#[derive(Debug, Clone, Copy)]
+pub struct Grid3dTopology {
+    pub levels: usize,
+    pub rows: usize,
+    pub cols: usize,
+}
+
+impl Grid3dTopology {
+    pub fn new(levels: usize, rows: usize, cols: usize) -> Self {
+        Self { levels, rows, cols }
+    }
+
+    pub fn index(&self, level: usize, row: usize, col: usize) -> usize {
+        (level * self.rows + row) * self.cols + col
+    }
+
+    /// Splits a cell index into (level, row, column).
+    pub fn coords(&self, cell: usize) -> (usize, usize, usize) {
+        let floor_size = self.rows * self.cols;
+        let level = cell / floor_size;
+        let rest = cell % floor_size;
+        (level, rest / self.cols, rest % self.cols)
+    }
+
+    fn corner(&self, level: usize, row: usize, col: usize) -> (f64, f64) {
+        ((level * (self.cols + 1) + col) as f64, row as f64)
+    }
+}
+
+impl Topology for Grid3dTopology {
+    fn cell_count(&self) -> usize {
+        self.levels * self.rows * self.cols
+    }
+
+    fn neighbors(&self, cell: usize) -> Vec<usize> {
+        let (level, row, col) = self.coords(cell);
+        let mut neighbors = Vec::new();
+        if row > 0 {
+            neighbors.push(self.index(level, row - 1, col));
+        }
+        if row + 1 < self.rows {
+            neighbors.push(self.index(level, row + 1, col));
+        }
+        if col > 0 {
+            neighbors.push(self.index(level, row, col - 1));
+        }
+        if col + 1 < self.cols {
+            neighbors.push(self.index(level, row, col + 1));
+        }
+        if level > 0 {
+            neighbors.push(self.index(level - 1, row, col));
+        }
+        if level + 1 < self.levels {
+            neighbors.push(self.index(level + 1, row, col));
+        }
+        neighbors
+    }
+
+    fn distance(&self, a: usize, b: usize) -> usize {
+        // 3D Manhattan distance
+        let (al, ar, ac) = self.coords(a);
+        let (bl, br, bc) = self.coords(b);
+        al.abs_diff(bl) + ar.abs_diff(br) + ac.abs_diff(bc)
+    }
+
+    fn position(&self, cell: usize) -> (usize, usize) {
+        let (level, row, col) = self.coords(cell);
+        (level * self.rows + row, col)
+    }
+
+    fn center(&self, cell: usize) -> (f64, f64) {
+        let (level, row, col) = self.coords(cell);
+        let (x, y) = self.corner(level, row, col);
+        (x + 0.5, y + 0.5)
+    }
+
+    fn walls(&self, cell: usize) -> Vec<Wall> {
+        let (level, row, col) = self.coords(cell);
+        let (x, y) = self.corner(level, row, col);
+        let line = |neighbor, from, to| Wall {
+            neighbor,
+            shape: WallShape::Line { from, to },
+        };
+
+        let mut walls = vec![
+            line(
+                (row > 0).then(|| self.index(level, row - 1, col)),
+                (x, y),
+                (x + 1.0, y),
+            ),
+            line(
+                (col > 0).then(|| self.index(level, row, col - 1)),
+                (x, y),
+                (x, y + 1.0),
+            ),
+        ];
+        if row + 1 == self.rows {
+            walls.push(line(None, (x, y + 1.0), (x + 1.0, y + 1.0)));
+        }
+        if col + 1 == self.cols {
+            walls.push(line(None, (x + 1.0, y), (x + 1.0, y + 1.0)));
+        }
+        walls
+    }
+
+    fn bounds(&self) -> (f64, f64) {
+        (
+            (self.levels * (self.cols + 1)).saturating_sub(1) as f64,
+            self.rows as f64,
+        )
+    }
+
+    fn passage_marker(&self, cell: usize, neighbor: usize) -> Option<PassageMarker> {
+        let (level, _, _) = self.coords(cell);
+        let (neighbor_level, _, _) = self.coords(neighbor);
+        match neighbor_level.cmp(&level) {
+            std::cmp::Ordering::Greater => Some(PassageMarker::Up),
+            std::cmp::Ordering::Less => Some(PassageMarker::Down),
+            std::cmp::Ordering::Equal => None,
+        }
+    }
+}
+
+impl LinkedMaze<Grid3dTopology> {
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, MazeError> {
+        Self::from_text(&std::fs::read_to_string(path)?)
+    }
+
+    /// Parses floors in the block text format (`#` walls, `.` passages, `S`
+    /// start, `E` end) separated by `---` lines. Cells with stairs are marked
+    /// `U` (up), `D` (down) or `X` (both); stairs of the start and end cells
+    /// are taken from the matching marker on the neighboring floor.
+    pub fn from_text(text: &str) -> Result<Self, MazeError> {
+        // (first line number, rows) of every floor
+        let mut floors: Vec<(usize, Vec<Vec<char>>)> = vec![(0, Vec::new())];
+        for (y, line) in text.lines().enumerate() {
+            if line.trim_end() == FLOOR_SEPARATOR {
+                floors.push((y + 1, Vec::new()));
+            } else if !line.is_empty() {
+                floors.last_mut().unwrap().1.push(line.chars().collect());
+            }
+        }
+
+        let block_rows = floors[0].1.len();
+        let block_cols = floors[0].1.first().map_or(0, |row| row.len());
+        let consistent = floors.iter().all(|(_, floor)| {
+            floor.len() == block_rows && floor.iter().all(|row| row.len() == block_cols)
+        });
+        if !consistent
+            || block_rows.is_multiple_of(2)
+            || block_cols.is_multiple_of(2)
+            || block_rows < 3
+            || block_cols < 3
+        {
+            return Err(MazeError::InvalidDimensions);
+        }
+
+        let topology = Grid3dTopology::new(floors.len(), block_rows / 2, block_cols / 2);
+        let mut passages = Passages::new(topology.cell_count());
+        let mut start = None;
+        let mut end = None;
+
+        for (level, (first_line, floor)) in floors.iter().enumerate() {
+            for (y, row) in floor.iter().enumerate() {
+                for (x, &ch) in row.iter().enumerate() {
+                    let invalid = MazeError::InvalidCharacter(ch, first_line + y, x);
+                    let is_cell = y % 2 == 1 && x % 2 == 1;
+                    match ch {
+                        '#' if !is_cell => {}
+                        '.' if is_cell => {}
+                        '.' => {
+                            // an opening between two cells of the same floor
+                            if y % 2 == 1 && x % 2 == 0 && x > 0 && x < block_cols - 1 {
+                                passages.link(
+                                    topology.index(level, y / 2, x / 2 - 1),
+                                    topology.index(level, y / 2, x / 2),
+                                );
+                            } else if y % 2 == 0 && x % 2 == 1 && y > 0 && y < block_rows - 1 {
+                                passages.link(
+                                    topology.index(level, y / 2 - 1, x / 2),
+                                    topology.index(level, y / 2, x / 2),
+                                );
+                            } else {
+                                return Err(invalid);
+                            }
+                        }
+                        'S' | 'E' if is_cell => {
+                            let slot = if ch == 'S' { &mut start } else { &mut end };
+                            if slot.is_some() {
+                                return Err(invalid);
+                            }
+                            *slot = Some(topology.index(level, y / 2, x / 2));
+                        }
+                        'U' | 'D' | 'X' if is_cell => {
+                            let cell = topology.index(level, y / 2, x / 2);
+                            if ch != 'D' {
+                                if level + 1 == topology.levels {
+                                    return Err(invalid);
+                                }
+                                passages.link(cell, topology.index(level + 1, y / 2, x / 2));
+                            }
+                            if ch != 'U' {
+                                if level == 0 {
+                                    return Err(invalid);
+                                }
+                                passages.link(cell, topology.index(level - 1, y / 2, x / 2));
+                            }
+                        }
+                        _ => return Err(invalid),
+                    }
+                }
+            }
+        }
+
+        let start = start.ok_or(MazeError::MissingStartOrEnd)?;
+        let end = end.ok_or(MazeError::MissingStartOrEnd)?;
+        Ok(LinkedMaze::new(topology, passages, start, end))
+    }
+}
+
+impl fmt::Display for LinkedMaze<Grid3dTopology> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let topology = self.topology;
+        for level in 0..topology.levels {
+            if level > 0 {
+                writeln!(f, "{}", FLOOR_SEPARATOR)?;
+            }
+            let mut grid = vec![vec!['#'; topology.cols * 2 + 1]; topology.rows * 2 + 1];
+            for row in 0..topology.rows {
+                for col in 0..topology.cols {
+                    let cell = topology.index(level, row, col);
+                    let up = level + 1 < topology.levels
+                        && self
+                            .passages
+                            .is_linked(cell, topology.index(level + 1, row, col));
+                    let down = level > 0
+                        && self
+                            .passages
+                            .is_linked(cell, topology.index(level - 1, row, col));
+                    grid[row * 2 + 1][col * 2 + 1] = if cell == self.start {
+                        'S'
+                    } else if cell == self.end {
+                        'E'
+                    } else {
+                        match (up, down) {
+                            (true, true) => 'X',
+                            (true, false) => 'U',
+                            (false, true) => 'D',
+                            (false, false) => '.',
+                        }
+                    };
+                    if row + 1 < topology.rows
+                        && self
+                            .passages
+                            .is_linked(cell, topology.index(level, row + 1, col))
+                    {
+                        grid[row * 2 + 2][col * 2 + 1] = '.';
+                    }
+                    if col + 1 < topology.cols
+                        && self
+                            .passages
+                            .is_linked(cell, topology.index(level, row, col + 1))
+                    {
+                        grid[row * 2 + 1][col * 2 + 2] = '.';
+                    }
+                }
+            }
+            for line in grid {
+                writeln!(f, "{}", line.into_iter().collect::<String>())?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generators::generator::{assert_spanning_tree, TopologyMazeGenerator};
+    use crate::generators::recursive_backtracker::RecursiveBacktrackerMazeGenerator;
+    use crate::generators::wilson::WilsonMazeGenerator;
+    use crate::solvers::astar::AstarSolver;
+    use crate::solvers::solver::Solver;
+
+    #[test]
+    fn test_grid3d_neighbors_and_distance() {
+        let grid = Grid3dTopology::new(3, 4, 5);
+        assert_eq!(grid.neighbors(grid.index(1, 1, 1)).len(), 6);
+        assert_eq!(grid.neighbors(grid.index(0, 0, 0)).len(), 3);
+        assert_eq!(grid.distance(grid.index(0, 0, 0), grid.index(2, 3, 4)), 9);
+        assert_eq!(grid.coords(grid.index(2, 3, 1)), (2, 3, 1));
+    }
+
+    #[test]
+    fn test_generators_span_grid3d_and_astar_solves() {
+        let grid = Grid3dTopology::new(3, 5, 5);
+        let generators: [&dyn TopologyMazeGenerator; 2] =
+            [&RecursiveBacktrackerMazeGenerator, &WilsonMazeGenerator];
+        for generator in generators {
+            let passages = generator.carve(&grid, None);
+            assert_spanning_tree(&grid, &passages);
+
+            let maze = LinkedMaze::new(grid, passages, 0, grid.cell_count() - 1);
+            let path = AstarSolver.solve_graph(&maze, None).expect("path exists");
+            assert_eq!(path.first(), Some(&maze.start));
+            assert_eq!(path.last(), Some(&maze.end));
+            for pair in path.windows(2) {
+                assert!(maze.passages.is_linked(pair[0], pair[1]));
+            }
+        }
+    }
+
+    #[test]
+    fn test_grid3d_text_round_trip() {
+        let grid = Grid3dTopology::new(3, 3, 4);
+        let passages = WilsonMazeGenerator.carve(&grid, None);
+        let maze = LinkedMaze::new(grid, passages, 0, grid.cell_count() - 1);
+
+        let text = maze.to_string();
+        assert_eq!(text.matches(FLOOR_SEPARATOR).count(), 2);
+        let parsed = LinkedMaze::from_text(&text).expect("Failed to parse maze");
+        assert_eq!(parsed.start, maze.start);
+        assert_eq!(parsed.end, maze.end);
+        for cell in 0..grid.cell_count() {
+            for n in grid.neighbors(cell) {
+                assert_eq!(
+                    parsed.passages.is_linked(cell, n),
+                    maze.passages.is_linked(cell, n)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_grid3d_text_rejects_stairs_off_the_top() {
+        let text = "#####\n#S.U#\n#####\n---\n#####\n#E.U#\n#####\n";
+        let result = LinkedMaze::from_text(text);
+        assert!(matches!(
+            result,
+            Err(MazeError::InvalidCharacter('U', 5, 3))
+        ));
+    }
+}
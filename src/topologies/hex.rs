@@ -0,0 +1,183 @@
+use crate::topologies::topology::{Topology, Wall, WallShape};
+
+/// Circumradius of a hexagon in canvas units.
+const SIZE: f64 = 0.5;
+
+/// Neighbor offsets (row, column) in side order E, SE, SW, W, NW, NE for even rows.
+const EVEN_ROW_DIRS: [(isize, isize); 6] = [(0, 1), (1, 0), (1, -1), (0, -1), (-1, -1), (-1, 0)];
+/// Neighbor offsets (row, column) in side order E, SE, SW, W, NW, NE for odd rows.
+const ODD_ROW_DIRS: [(isize, isize); 6] = [(0, 1), (1, 1), (1, 0), (0, -1), (-1, 0), (-1, 1)];
+
+/// Grid of pointy-top hexagons in "odd-r" offset coordinates: odd rows are
+/// shifted right by half a cell. Each cell has up to six neighbors.
+#[derive(Debug, Clone, Copy)]
+pub struct HexTopology {
+    pub rows: usize,
+    pub cols: usize,
+}
+
+impl HexTopology {
+    pub fn new(rows: usize, cols: usize) -> Self {
+        Self { rows, cols }
+    }
+
+    pub fn index(&self, row: usize, col: usize) -> usize {
+        row * self.cols + col
+    }
+
+    fn neighbor_in(&self, cell: usize, side: usize) -> Option<usize> {
+        let (row, col) = self.position(cell);
+        let dirs = if row % 2 == 0 {
+            &EVEN_ROW_DIRS
+        } else {
+            &ODD_ROW_DIRS
+        };
+        let (dr, dc) = dirs[side];
+        let new_row = row as isize + dr;
+        let new_col = col as isize + dc;
+        if new_row >= 0
+            && new_row < self.rows as isize
+            && new_col >= 0
+            && new_col < self.cols as isize
+        {
+            Some(self.index(new_row as usize, new_col as usize))
+        } else {
+            None
+        }
+    }
+
+    // converts offset coordinates to axial (q, r) coordinates
+    fn axial(&self, cell: usize) -> (isize, isize) {
+        let (row, col) = self.position(cell);
+        let q = col as isize - (row as isize - (row as isize & 1)) / 2;
+        (q, row as isize)
+    }
+
+    fn vertex(&self, cell: usize, i: usize) -> (f64, f64) {
+        let (cx, cy) = self.center(cell);
+        let angle = (60.0 * i as f64 - 30.0).to_radians();
+        (cx + SIZE * angle.cos(), cy + SIZE * angle.sin())
+    }
+}
+
+impl Topology for HexTopology {
+    fn cell_count(&self) -> usize {
+        self.rows * self.cols
+    }
+
+    fn neighbors(&self, cell: usize) -> Vec<usize> {
+        (0..6)
+            .filter_map(|side| self.neighbor_in(cell, side))
+            .collect()
+    }
+
+    fn distance(&self, a: usize, b: usize) -> usize {
+        let (aq, ar) = self.axial(a);
+        let (bq, br) = self.axial(b);
+        let dq = aq - bq;
+        let dr = ar - br;
+        ((dq.abs() + dr.abs() + (dq + dr).abs()) / 2) as usize
+    }
+
+    fn position(&self, cell: usize) -> (usize, usize) {
+        (cell / self.cols, cell % self.cols)
+    }
+
+    fn center(&self, cell: usize) -> (f64, f64) {
+        let (row, col) = self.position(cell);
+        let width = 3f64.sqrt() * SIZE;
+        let shift = if row % 2 == 1 { 0.5 } else { 0.0 };
+        (
+            width * (col as f64 + shift) + width / 2.0,
+            1.5 * SIZE * row as f64 + SIZE,
+        )
+    }
+
+    fn walls(&self, cell: usize) -> Vec<Wall> {
+        // side i runs from vertex i to vertex i + 1, vertices start at -30 degrees
+        (0..6)
+            .filter_map(|side| {
+                let neighbor = self.neighbor_in(cell, side);
+                if neighbor.is_some_and(|n| n < cell) {
+                    return None;
+                }
+                Some(Wall {
+                    neighbor,
+                    shape: WallShape::Line {
+                        from: self.vertex(cell, side),
+                        to: self.vertex(cell, (side + 1) % 6),
+                    },
+                })
+            })
+            .collect()
+    }
+
+    fn bounds(&self) -> (f64, f64) {
+        let width = 3f64.sqrt() * SIZE;
+        let shift = if self.rows > 1 { 0.5 } else { 0.0 };
+        (
+            width * (self.cols as f64 + shift),
+            1.5 * SIZE * (self.rows.max(1) - 1) as f64 + 2.0 * SIZE,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hex_neighbors_interior() {
+        let hex = HexTopology::new(5, 5);
+        // even row
+        let mut n = hex.neighbors(hex.index(2, 2));
+        n.sort();
+        let mut expected = vec![
+            hex.index(1, 1),
+            hex.index(1, 2),
+            hex.index(2, 1),
+            hex.index(2, 3),
+            hex.index(3, 1),
+            hex.index(3, 2),
+        ];
+        expected.sort();
+        assert_eq!(n, expected);
+        // odd row
+        let mut n = hex.neighbors(hex.index(1, 2));
+        n.sort();
+        let mut expected = vec![
+            hex.index(0, 2),
+            hex.index(0, 3),
+            hex.index(1, 1),
+            hex.index(1, 3),
+            hex.index(2, 2),
+            hex.index(2, 3),
+        ];
+        expected.sort();
+        assert_eq!(n, expected);
+    }
+
+    #[test]
+    fn test_hex_neighbors_are_symmetric_and_adjacent() {
+        let hex = HexTopology::new(4, 6);
+        for cell in 0..hex.cell_count() {
+            for n in hex.neighbors(cell) {
+                assert!(hex.neighbors(n).contains(&cell));
+                assert_eq!(hex.distance(cell, n), 1);
+            }
+        }
+        assert_eq!(hex.neighbors(hex.index(0, 0)).len(), 2);
+    }
+
+    #[test]
+    fn test_hex_walls_cover_each_side_once() {
+        let hex = HexTopology::new(3, 3);
+        let total: usize = (0..hex.cell_count()).map(|c| hex.walls(c).len()).sum();
+        let shared: usize = (0..hex.cell_count())
+            .map(|c| hex.neighbors(c).len())
+            .sum::<usize>()
+            / 2;
+        let boundary = hex.cell_count() * 6 - 2 * shared;
+        assert_eq!(total, shared + boundary);
+    }
+}
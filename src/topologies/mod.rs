@@ -0,0 +1,5 @@
+pub mod grid3d;
+pub mod hex;
+pub mod polar;
+pub mod square;
+pub mod topology;
@@ -0,0 +1,214 @@
+use crate::topologies::topology::{Topology, Wall, WallShape};
+use std::f64::consts::TAU;
+
+/// Concentric rings of cells around a single center cell. Each ring is one
+/// canvas unit thick, and a ring splits every cell of the ring inside it into
+/// as many cells as needed to keep cells roughly square.
+#[derive(Debug, Clone)]
+pub struct PolarTopology {
+    ring_sizes: Vec<usize>,
+    ring_offsets: Vec<usize>,
+}
+
+impl PolarTopology {
+    pub fn new(rings: usize) -> Self {
+        let rings = rings.max(1);
+        let mut ring_sizes = vec![1];
+        for ring in 1..rings {
+            let previous = ring_sizes[ring - 1];
+            let circumference = TAU * ring as f64;
+            let ratio = (circumference / previous as f64).round().max(1.0) as usize;
+            ring_sizes.push(previous * ratio);
+        }
+        let ring_offsets = ring_sizes
+            .iter()
+            .scan(0, |offset, &size| {
+                let start = *offset;
+                *offset += size;
+                Some(start)
+            })
+            .collect();
+        Self {
+            ring_sizes,
+            ring_offsets,
+        }
+    }
+
+    pub fn rings(&self) -> usize {
+        self.ring_sizes.len()
+    }
+
+    pub fn ring_size(&self, ring: usize) -> usize {
+        self.ring_sizes[ring]
+    }
+
+    pub fn index(&self, ring: usize, position: usize) -> usize {
+        self.ring_offsets[ring] + position
+    }
+
+    fn origin(&self) -> (f64, f64) {
+        let radius = self.rings() as f64;
+        (radius, radius)
+    }
+
+    fn point(&self, radius: f64, angle: f64) -> (f64, f64) {
+        let (cx, cy) = self.origin();
+        (cx + radius * angle.cos(), cy + radius * angle.sin())
+    }
+
+    // angles (start, end) spanned by a cell, increasing clockwise on screen
+    fn angles(&self, ring: usize, position: usize) -> (f64, f64) {
+        let theta = TAU / self.ring_sizes[ring] as f64;
+        (position as f64 * theta, (position + 1) as f64 * theta)
+    }
+}
+
+impl Topology for PolarTopology {
+    fn cell_count(&self) -> usize {
+        self.ring_offsets[self.rings() - 1] + self.ring_sizes[self.rings() - 1]
+    }
+
+    fn neighbors(&self, cell: usize) -> Vec<usize> {
+        let (ring, position) = self.position(cell);
+        let size = self.ring_sizes[ring];
+        let mut neighbors = Vec::new();
+
+        if size > 1 {
+            neighbors.push(self.index(ring, (position + 1) % size));
+            let ccw = self.index(ring, (position + size - 1) % size);
+            if !neighbors.contains(&ccw) {
+                neighbors.push(ccw);
+            }
+        }
+        if ring > 0 {
+            let ratio = size / self.ring_sizes[ring - 1];
+            neighbors.push(self.index(ring - 1, position / ratio));
+        }
+        if ring + 1 < self.rings() {
+            let ratio = self.ring_sizes[ring + 1] / size;
+            neighbors.extend(
+                (position * ratio..(position + 1) * ratio).map(|p| self.index(ring + 1, p)),
+            );
+        }
+
+        neighbors
+    }
+
+    fn distance(&self, a: usize, b: usize) -> usize {
+        // every step changes the ring by at most one
+        self.position(a).0.abs_diff(self.position(b).0)
+    }
+
+    fn position(&self, cell: usize) -> (usize, usize) {
+        let ring = self.ring_offsets.partition_point(|&offset| offset <= cell) - 1;
+        (ring, cell - self.ring_offsets[ring])
+    }
+
+    fn center(&self, cell: usize) -> (f64, f64) {
+        let (ring, position) = self.position(cell);
+        if ring == 0 {
+            return self.origin();
+        }
+        let (start, end) = self.angles(ring, position);
+        self.point(ring as f64 + 0.5, (start + end) / 2.0)
+    }
+
+    fn walls(&self, cell: usize) -> Vec<Wall> {
+        let (ring, position) = self.position(cell);
+        let (start_angle, end_angle) = self.angles(ring, position);
+        let center = self.origin();
+        let mut walls = Vec::new();
+
+        if ring > 0 {
+            // inward arc, shared with the parent cell
+            let ratio = self.ring_sizes[ring] / self.ring_sizes[ring - 1];
+            walls.push(Wall {
+                neighbor: Some(self.index(ring - 1, position / ratio)),
+                shape: WallShape::Arc {
+                    center,
+                    radius: ring as f64,
+                    start_angle,
+                    end_angle,
+                },
+            });
+            // clockwise radial wall
+            walls.push(Wall {
+                neighbor: Some(self.index(ring, (position + 1) % self.ring_sizes[ring])),
+                shape: WallShape::Line {
+                    from: self.point(ring as f64, end_angle),
+                    to: self.point(ring as f64 + 1.0, end_angle),
+                },
+            });
+        }
+        if ring + 1 == self.rings() {
+            // outer rim, split so that no single arc covers the full circle
+            let middle = (start_angle + end_angle) / 2.0;
+            for (from, to) in [(start_angle, middle), (middle, end_angle)] {
+                walls.push(Wall {
+                    neighbor: None,
+                    shape: WallShape::Arc {
+                        center,
+                        radius: ring as f64 + 1.0,
+                        start_angle: from,
+                        end_angle: to,
+                    },
+                });
+            }
+        }
+
+        walls
+    }
+
+    fn bounds(&self) -> (f64, f64) {
+        let diameter = 2.0 * self.rings() as f64;
+        (diameter, diameter)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generators::aldous_broder::AldousBroderMazeGenerator;
+    use crate::generators::generator::{assert_spanning_tree, TopologyMazeGenerator};
+    use crate::generators::kruskal::KruskalMazeGenerator;
+    use crate::generators::prims::PrimMazeGenerator;
+    use crate::generators::recursive_backtracker::RecursiveBacktrackerMazeGenerator;
+    use crate::generators::wilson::WilsonMazeGenerator;
+
+    #[test]
+    fn test_polar_ring_sizes_grow_with_radius() {
+        let polar = PolarTopology::new(5);
+        let sizes: Vec<usize> = (0..polar.rings()).map(|r| polar.ring_size(r)).collect();
+        assert_eq!(sizes, vec![1, 6, 12, 24, 24]);
+        assert_eq!(polar.cell_count(), 67);
+        assert_eq!(polar.position(polar.index(3, 5)), (3, 5));
+    }
+
+    #[test]
+    fn test_polar_neighbors_are_symmetric() {
+        let polar = PolarTopology::new(7);
+        for cell in 0..polar.cell_count() {
+            for n in polar.neighbors(cell) {
+                assert!(polar.neighbors(n).contains(&cell), "{} <-> {}", cell, n);
+                assert!(polar.distance(cell, n) <= 1);
+            }
+        }
+        assert_eq!(polar.neighbors(0).len(), 6);
+    }
+
+    #[test]
+    fn test_generators_span_polar_grid() {
+        let polar = PolarTopology::new(6);
+        let generators: [&dyn TopologyMazeGenerator; 5] = [
+            &AldousBroderMazeGenerator,
+            &KruskalMazeGenerator,
+            &PrimMazeGenerator,
+            &RecursiveBacktrackerMazeGenerator,
+            &WilsonMazeGenerator,
+        ];
+        for generator in generators {
+            let passages = generator.carve(&polar, None);
+            assert_spanning_tree(&polar, &passages);
+        }
+    }
+}
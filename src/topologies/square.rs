@@ -0,0 +1,220 @@
+use crate::cell::Cell;
+use crate::linked_maze::{LinkedMaze, Passages};
+use crate::maze::{Maze, MazeError, Wrap};
+use crate::topologies::topology::{Topology, Wall, WallShape};
+
+/// Grid of square cells with up to four neighbors, storing passages between
+/// cells rather than wall blocks, so walls are drawn as thin lines. Cell
+/// (row, col) corresponds to block (2 * row + 1, 2 * col + 1) of a block `Maze`.
+#[derive(Debug, Clone, Copy)]
+pub struct SquareTopology {
+    pub rows: usize,
+    pub cols: usize,
+}
+
+impl SquareTopology {
+    pub fn new(rows: usize, cols: usize) -> Self {
+        Self { rows, cols }
+    }
+
+    pub fn index(&self, row: usize, col: usize) -> usize {
+        row * self.cols + col
+    }
+}
+
+impl Topology for SquareTopology {
+    fn cell_count(&self) -> usize {
+        self.rows * self.cols
+    }
+
+    fn neighbors(&self, cell: usize) -> Vec<usize> {
+        let (row, col) = self.position(cell);
+        Maze::get_neighbors(row, col, self.rows, self.cols)
+            .into_iter()
+            .map(|(row, col)| self.index(row, col))
+            .collect()
+    }
+
+    fn distance(&self, a: usize, b: usize) -> usize {
+        let (ar, ac) = self.position(a);
+        let (br, bc) = self.position(b);
+        ar.abs_diff(br) + ac.abs_diff(bc)
+    }
+
+    fn position(&self, cell: usize) -> (usize, usize) {
+        (cell / self.cols, cell % self.cols)
+    }
+
+    fn center(&self, cell: usize) -> (f64, f64) {
+        let (row, col) = self.position(cell);
+        (col as f64 + 0.5, row as f64 + 0.5)
+    }
+
+    fn walls(&self, cell: usize) -> Vec<Wall> {
+        // every cell owns its top and left walls, the last row and column also
+        // the outer bottom and right walls
+        let (row, col) = self.position(cell);
+        let (x, y) = (col as f64, row as f64);
+        let line = |neighbor, from, to| Wall {
+            neighbor,
+            shape: WallShape::Line { from, to },
+        };
+
+        let mut walls = vec![
+            line(
+                (row > 0).then(|| self.index(row - 1, col)),
+                (x, y),
+                (x + 1.0, y),
+            ),
+            line(
+                (col > 0).then(|| self.index(row, col - 1)),
+                (x, y),
+                (x, y + 1.0),
+            ),
+        ];
+        if row + 1 == self.rows {
+            walls.push(line(None, (x, y + 1.0), (x + 1.0, y + 1.0)));
+        }
+        if col + 1 == self.cols {
+            walls.push(line(None, (x + 1.0, y), (x + 1.0, y + 1.0)));
+        }
+        walls
+    }
+
+    fn bounds(&self) -> (f64, f64) {
+        (self.cols as f64, self.rows as f64)
+    }
+}
+
+impl LinkedMaze<SquareTopology> {
+    /// Reads the passages of a block maze laid out the way the generators
+    /// carve them: an odd-sized plane grid with open logical cells on odd rows
+    /// and columns, wall corners on even ones, and plain passages or walls in
+    /// between. Any other cell is reported as `NotLinkable`.
+    pub fn from_block_maze(maze: &Maze) -> Result<Self, MazeError> {
+        let block_rows = maze.cells.len();
+        let block_cols = maze.cells[0].len();
+        if maze.wrap != Wrap::None
+            || block_rows.is_multiple_of(2)
+            || block_cols.is_multiple_of(2)
+            || block_rows < 3
+            || block_cols < 3
+        {
+            return Err(MazeError::InvalidDimensions);
+        }
+
+        let topology = SquareTopology::new(block_rows / 2, block_cols / 2);
+        let mut passages = Passages::new(topology.cell_count());
+        for (y, row) in maze.cells.iter().enumerate() {
+            for (x, &cell) in row.iter().enumerate() {
+                let border = y == 0 || x == 0 || y == block_rows - 1 || x == block_cols - 1;
+                match (y % 2, x % 2, cell) {
+                    (1, 1, Cell::Path) => {}
+                    (0, 0, Cell::Wall) => {}
+                    (_, _, Cell::Wall) if y % 2 != x % 2 => {}
+                    (1, 0, Cell::Path) if !border => passages.link(
+                        topology.index(y / 2, x / 2 - 1),
+                        topology.index(y / 2, x / 2),
+                    ),
+                    (0, 1, Cell::Path) if !border => passages.link(
+                        topology.index(y / 2 - 1, x / 2),
+                        topology.index(y / 2, x / 2),
+                    ),
+                    _ => return Err(MazeError::NotLinkable(y, x)),
+                }
+            }
+        }
+
+        let cell_of = |(y, x): (usize, usize)| {
+            if y % 2 == 1 && x % 2 == 1 {
+                Ok(topology.index(y / 2, x / 2))
+            } else {
+                Err(MazeError::NotLinkable(y, x))
+            }
+        };
+        Ok(LinkedMaze::new(
+            topology,
+            passages,
+            cell_of(maze.start)?,
+            cell_of(maze.end)?,
+        ))
+    }
+
+    /// Block maze with a wall block around every cell and an open block for
+    /// every passage; `from_block_maze` turns it back into the same maze.
+    pub fn to_block_maze(&self) -> Maze {
+        let topology = self.topology;
+        let mut cells = vec![vec![Cell::Wall; topology.cols * 2 + 1]; topology.rows * 2 + 1];
+        for cell in 0..topology.cell_count() {
+            let (row, col) = topology.position(cell);
+            cells[2 * row + 1][2 * col + 1] = Cell::Path;
+            for &neighbor in self.passages.links(cell) {
+                let (n_row, n_col) = topology.position(neighbor);
+                cells[row + n_row + 1][col + n_col + 1] = Cell::Path;
+            }
+        }
+        let block = |cell: usize| {
+            let (row, col) = topology.position(cell);
+            (2 * row + 1, 2 * col + 1)
+        };
+        Maze::new(cells, block(self.start), block(self.end))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generators::generator::{
+        assert_spanning_tree, MazeGenerator, TopologyMazeGenerator,
+    };
+    use crate::generators::kruskal::KruskalMazeGenerator;
+    use crate::generators::recursive_backtracker::RecursiveBacktrackerMazeGenerator;
+    use crate::solvers::bfs_solver::BfsSolver;
+    use crate::solvers::solver::Solver;
+
+    #[test]
+    fn test_block_maze_round_trip() {
+        let block = RecursiveBacktrackerMazeGenerator
+            .generate(21, 15, None, None, 0.0, None)
+            .unwrap();
+        let linked = LinkedMaze::from_block_maze(&block).unwrap();
+        assert_spanning_tree(&linked.topology, &linked.passages);
+        let back = linked.to_block_maze();
+        assert_eq!(back.cells, block.cells);
+        assert_eq!((back.start, back.end), (block.start, block.end));
+
+        // both views agree on the solution: one logical step is two blocks
+        let linked_path = BfsSolver.solve_graph(&linked, None).unwrap();
+        let block_path = BfsSolver.solve(&block, None).unwrap();
+        assert_eq!(2 * (linked_path.len() - 1), block_path.len() - 1);
+    }
+
+    #[test]
+    fn test_carved_maze_converts_to_blocks() {
+        let topology = SquareTopology::new(6, 8);
+        let passages = KruskalMazeGenerator.carve(&topology, None);
+        let linked = LinkedMaze::new(topology, passages, 0, topology.cell_count() - 1);
+        let again = LinkedMaze::from_block_maze(&linked.to_block_maze()).unwrap();
+        for cell in 0..topology.cell_count() {
+            for neighbor in topology.neighbors(cell) {
+                assert_eq!(
+                    again.passages.is_linked(cell, neighbor),
+                    linked.passages.is_linked(cell, neighbor)
+                );
+            }
+        }
+        assert_eq!((again.start, again.end), (linked.start, linked.end));
+    }
+
+    #[test]
+    fn test_open_corner_is_not_linkable() {
+        let mut block = RecursiveBacktrackerMazeGenerator
+            .generate(11, 11, None, None, 0.0, None)
+            .unwrap();
+        block.cells[2][2] = Cell::Path;
+        assert!(matches!(
+            LinkedMaze::from_block_maze(&block),
+            Err(MazeError::NotLinkable(2, 2))
+        ));
+    }
+}
@@ -0,0 +1,89 @@
+/// Geometry of a single wall segment, in canvas units (roughly one unit per cell).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WallShape {
+    Line {
+        from: (f64, f64),
+        to: (f64, f64),
+    },
+    /// Circular arc going clockwise on screen from `start_angle` to `end_angle` (radians).
+    Arc {
+        center: (f64, f64),
+        radius: f64,
+        start_angle: f64,
+        end_angle: f64,
+    },
+}
+
+impl WallShape {
+    /// Approximates the wall with a polyline whose segments are at most `max_step` units long.
+    pub fn to_polyline(&self, max_step: f64) -> Vec<(f64, f64)> {
+        match *self {
+            WallShape::Line { from, to } => vec![from, to],
+            WallShape::Arc {
+                center,
+                radius,
+                start_angle,
+                end_angle,
+            } => {
+                let length = radius * (end_angle - start_angle).abs();
+                let steps = ((length / max_step).ceil() as usize).max(1);
+                (0..=steps)
+                    .map(|i| {
+                        let angle =
+                            start_angle + (end_angle - start_angle) * i as f64 / steps as f64;
+                        (
+                            center.0 + radius * angle.cos(),
+                            center.1 + radius * angle.sin(),
+                        )
+                    })
+                    .collect()
+            }
+        }
+    }
+}
+
+/// A wall owned by a cell. `neighbor` is the cell on the other side, or `None`
+/// for the outer boundary. Every shared wall is reported by exactly one of the
+/// two cells it separates, so renderers can draw each one once.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Wall {
+    pub neighbor: Option<usize>,
+    pub shape: WallShape,
+}
+
+/// Marker for a passage that has no wall on the canvas, such as stairs between floors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PassageMarker {
+    Up,
+    Down,
+}
+
+/// Describes how logical maze cells are laid out and which cells touch each other.
+/// Cells are identified by indices in `0..cell_count()`.
+pub trait Topology {
+    fn cell_count(&self) -> usize;
+
+    /// Cells sharing a wall with `cell`, regardless of whether a passage exists.
+    fn neighbors(&self, cell: usize) -> Vec<usize>;
+
+    /// Lower bound on the number of steps between two cells, used as A* heuristic.
+    fn distance(&self, a: usize, b: usize) -> usize;
+
+    /// Grid coordinates (row, column) of a cell, used for progress tracking.
+    fn position(&self, cell: usize) -> (usize, usize);
+
+    /// Center of a cell on the canvas.
+    fn center(&self, cell: usize) -> (f64, f64);
+
+    /// Walls owned by a cell.
+    fn walls(&self, cell: usize) -> Vec<Wall>;
+
+    /// Canvas size (width, height) covering all cells.
+    fn bounds(&self) -> (f64, f64);
+
+    /// Marker drawn in `cell` for its passage to `neighbor` when the two cells
+    /// are not drawn next to each other. Ordinary passages return `None`.
+    fn passage_marker(&self, _cell: usize, _neighbor: usize) -> Option<PassageMarker> {
+        None
+    }
+}
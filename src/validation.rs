@@ -0,0 +1,291 @@
+//! Checks that a path really solves a square maze, whether it came from one of
+//! the solvers or from a file written by hand.
+use std::fmt;
+
+use crate::cell::{Axis, Cell};
+use crate::maze::Maze;
+use crate::solvers::bfs_solver::BfsSolver;
+use crate::solvers::solver::Solver;
+
+/// First problem found along a path. `step` is the index into the path of the
+/// cell being moved onto.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PathError {
+    Empty,
+    WrongStart {
+        found: (usize, usize),
+    },
+    WrongEnd {
+        found: (usize, usize),
+    },
+    OutOfBounds {
+        step: usize,
+        position: (usize, usize),
+    },
+    /// Neither neighbouring cells nor the two ends of a portal
+    NotAdjacent {
+        step: usize,
+        from: (usize, usize),
+        to: (usize, usize),
+    },
+    /// Moving onto a wall or outside a shaped maze
+    Blocked {
+        step: usize,
+        position: (usize, usize),
+        cell: Cell,
+    },
+    /// A door entered before its key was picked up
+    LockedDoor {
+        step: usize,
+        position: (usize, usize),
+    },
+    /// Entering or leaving a one-way cell against its arrow
+    WrongWay {
+        step: usize,
+        from: (usize, usize),
+        to: (usize, usize),
+    },
+    /// Changing corridor in the middle of a crossing
+    TurnOnCrossing {
+        step: usize,
+        position: (usize, usize),
+    },
+    /// Valid, but longer than the shortest solution
+    NotShortest {
+        length: usize,
+        shortest: usize,
+    },
+}
+
+impl fmt::Display for PathError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PathError::Empty => write!(f, "path is empty"),
+            PathError::WrongStart { found } => write!(f, "path starts at {:?}", found),
+            PathError::WrongEnd { found } => write!(f, "path ends at {:?}", found),
+            PathError::OutOfBounds { step, position } => {
+                write!(f, "step {} leaves the maze at {:?}", step, position)
+            }
+            PathError::NotAdjacent { step, from, to } => {
+                write!(f, "step {} jumps from {:?} to {:?}", step, from, to)
+            }
+            PathError::Blocked {
+                step,
+                position,
+                cell,
+            } => write!(f, "step {} enters {:?} at {:?}", step, cell, position),
+            PathError::LockedDoor { step, position } => {
+                write!(
+                    f,
+                    "step {} opens the door at {:?} without its key",
+                    step, position
+                )
+            }
+            PathError::WrongWay { step, from, to } => write!(
+                f,
+                "step {} from {:?} to {:?} goes against a one-way cell",
+                step, from, to
+            ),
+            PathError::TurnOnCrossing { step, position } => {
+                write!(f, "step {} turns on the crossing at {:?}", step, position)
+            }
+            PathError::NotShortest { length, shortest } => write!(
+                f,
+                "path takes {} steps, the shortest takes {}",
+                length, shortest
+            ),
+        }
+    }
+}
+
+impl std::error::Error for PathError {}
+
+/// Checks that `path` runs from the maze's start to its end one step at a
+/// time, never entering walls, locked doors or one-way cells the wrong way,
+/// nor turning on crossings. Portal jumps count as steps. With
+/// `require_shortest` the path must also be no longer than a BFS solution.
+pub fn validate_path(
+    maze: &Maze,
+    path: &[(usize, usize)],
+    require_shortest: bool,
+) -> Result<(), PathError> {
+    let rows = maze.cells.len();
+    let cols = maze.cells[0].len();
+    let (&first, &last) = path.first().zip(path.last()).ok_or(PathError::Empty)?;
+    if first != maze.start {
+        return Err(PathError::WrongStart { found: first });
+    }
+
+    let mut keys = 0u8;
+    // axis of the previous step when it was a walk rather than a portal jump
+    let mut walked: Option<Axis> = None;
+    for (step, pair) in path.windows(2).enumerate() {
+        let step = step + 1;
+        let (from, to) = (pair[0], pair[1]);
+        if to.0 >= rows || to.1 >= cols {
+            return Err(PathError::OutOfBounds { step, position: to });
+        }
+        let is_walk =
+            Maze::get_wrapped_neighbors(from.0, from.1, rows, cols, maze.wrap).contains(&to);
+        if !is_walk && maze.portals.get(&from) != Some(&to) {
+            return Err(PathError::NotAdjacent { step, from, to });
+        }
+
+        let cell = maze.cells[to.0][to.1];
+        match cell {
+            Cell::Wall | Cell::Void => {
+                return Err(PathError::Blocked {
+                    step,
+                    position: to,
+                    cell,
+                })
+            }
+            Cell::Door(color) if keys & (1 << color) == 0 => {
+                return Err(PathError::LockedDoor { step, position: to })
+            }
+            Cell::Key(color) => keys |= 1 << color,
+            _ => {}
+        }
+
+        let axis = is_walk.then(|| Axis::of_step(from, to));
+        if let Some(axis) = axis {
+            if !maze.allows_step(from, to) {
+                return Err(PathError::WrongWay { step, from, to });
+            }
+            let on_crossing = matches!(maze.cells[from.0][from.1], Cell::Crossing(_));
+            if on_crossing && walked.is_some_and(|previous| previous != axis) {
+                return Err(PathError::TurnOnCrossing {
+                    step,
+                    position: from,
+                });
+            }
+        }
+        walked = axis;
+    }
+
+    if last != maze.end {
+        return Err(PathError::WrongEnd { found: last });
+    }
+    if require_shortest {
+        if let Some(shortest) = BfsSolver.solve(maze, None) {
+            if path.len() > shortest.len() {
+                return Err(PathError::NotShortest {
+                    length: path.len() - 1,
+                    shortest: shortest.len() - 1,
+                });
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Reads a path written one cell per line as `row,col`. Blank lines and lines
+/// starting with `#` are skipped.
+pub fn parse_path(text: &str) -> Result<Vec<(usize, usize)>, String> {
+    text.lines()
+        .enumerate()
+        .map(|(number, line)| (number + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+        .map(|(number, line)| {
+            let (row, col) = line
+                .split_once(',')
+                .ok_or_else(|| format!("line {}: expected 'row,col'", number))?;
+            let parse = |value: &str| {
+                value
+                    .trim()
+                    .parse::<usize>()
+                    .map_err(|e| format!("line {}: {}", number, e))
+            };
+            Ok((parse(row)?, parse(col)?))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cell::Direction;
+
+    // #####
+    // #S..#
+    // ##..#
+    // #..E#
+    // #####
+    fn small_maze() -> Maze {
+        let mut cells = vec![vec![Cell::Wall; 5]; 5];
+        cells[1][1..4].fill(Cell::Path);
+        cells[2][2..4].fill(Cell::Path);
+        cells[3][1..4].fill(Cell::Path);
+        Maze::new(cells, (1, 1), (3, 3))
+    }
+
+    #[test]
+    fn test_valid_paths() {
+        let maze = small_maze();
+        let shortest = [(1, 1), (1, 2), (1, 3), (2, 3), (3, 3)];
+        assert_eq!(validate_path(&maze, &shortest, true), Ok(()));
+
+        let detour = [(1, 1), (1, 2), (2, 2), (3, 2), (3, 3)];
+        assert_eq!(validate_path(&maze, &detour, true), Ok(()));
+
+        let longer = [(1, 1), (1, 2), (2, 2), (2, 3), (1, 3), (2, 3), (3, 3)];
+        assert_eq!(validate_path(&maze, &longer, false), Ok(()));
+        assert_eq!(
+            validate_path(&maze, &longer, true),
+            Err(PathError::NotShortest {
+                length: 6,
+                shortest: 4
+            })
+        );
+    }
+
+    #[test]
+    fn test_first_bad_step_is_reported() {
+        let mut maze = small_maze();
+        assert_eq!(validate_path(&maze, &[], false), Err(PathError::Empty));
+        assert_eq!(
+            validate_path(&maze, &[(1, 2), (1, 3), (2, 3), (3, 3)], false),
+            Err(PathError::WrongStart { found: (1, 2) })
+        );
+        assert_eq!(
+            validate_path(&maze, &[(1, 1), (1, 2), (2, 3), (3, 3)], false),
+            Err(PathError::NotAdjacent {
+                step: 2,
+                from: (1, 2),
+                to: (2, 3)
+            })
+        );
+        assert_eq!(
+            validate_path(&maze, &[(1, 1), (2, 1), (3, 1), (3, 2), (3, 3)], false),
+            Err(PathError::Blocked {
+                step: 1,
+                position: (2, 1),
+                cell: Cell::Wall
+            })
+        );
+        assert_eq!(
+            validate_path(&maze, &[(1, 1), (1, 2), (2, 2)], false),
+            Err(PathError::WrongEnd { found: (2, 2) })
+        );
+
+        maze.cells[1][3] = Cell::OneWay(Direction::North);
+        assert_eq!(
+            validate_path(&maze, &[(1, 1), (1, 2), (1, 3), (2, 3), (3, 3)], false),
+            Err(PathError::WrongWay {
+                step: 2,
+                from: (1, 2),
+                to: (1, 3)
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_path() {
+        assert_eq!(
+            parse_path("# solution\n1,1\n 1, 2\n\n2,2\n"),
+            Ok(vec![(1, 1), (1, 2), (2, 2)])
+        );
+        assert!(parse_path("1 1").is_err());
+        assert!(parse_path("1,x").is_err());
+    }
+}
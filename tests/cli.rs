@@ -0,0 +1,55 @@
+//! Runs the command line tool on sizes at the edge of what it accepts.
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+fn run(name: &str, args: &[&str]) -> Output {
+    let output_dir: PathBuf =
+        std::env::temp_dir().join(format!("maze_cli_{}_{}", name, std::process::id()));
+    let output = Command::new(env!("CARGO_BIN_EXE_maze_rust"))
+        .args(args)
+        .args(["--generators", "prims", "--solvers", "bfs", "--output-dir"])
+        .arg(&output_dir)
+        .output()
+        .expect("Failed to run maze_rust");
+    let _ = std::fs::remove_dir_all(&output_dir);
+    output
+}
+
+#[test]
+fn test_single_cell_topologies_are_solved() {
+    for (name, args) in [
+        ("hex", &["--topology", "hex"][..]),
+        ("3d", &["--topology", "3d", "--levels", "1"][..]),
+        ("thin", &["--topology", "thin"][..]),
+        ("polar", &["--topology", "polar"][..]),
+    ] {
+        let output = run(name, &[args, &["--width", "1", "--height", "1"]].concat());
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert!(
+            output.status.success(),
+            "{}: {}",
+            name,
+            String::from_utf8_lossy(&output.stderr)
+        );
+        assert!(
+            stdout.contains("Path found with bfs."),
+            "{}: {}",
+            name,
+            stdout
+        );
+    }
+}
+
+#[test]
+fn test_too_small_square_maze_is_an_error() {
+    let output = run("square", &["--width", "2", "--height", "2"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("at least 3"));
+}
+
+#[test]
+fn test_polar_maze_without_rings_is_an_error() {
+    let output = run("polar_empty", &["--topology", "polar", "--height", "0"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("at least 1 ring"));
+}